@@ -0,0 +1,542 @@
+use std::{fmt, time::Duration};
+
+use gtk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    authority::PeerRole,
+    location::Location,
+    protocol::{AlertInfo, AlertResponseInfo, MessageId},
+};
+
+/// Speed below which a responder is considered stopped, so no arrival time is estimated.
+const MIN_ETA_SPEED: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, glib::Enum)]
+#[enum_type(name = "DeltaAlertType")]
+pub enum AlertType {
+    Sos,
+    Hazard,
+    Yielding,
+    Accident,
+    RoadWork,
+    Flooding,
+    Debris,
+    StalledVehicle,
+    PoliceCheckpoint,
+    TrafficJam,
+    EmergencyVehicle,
+}
+
+impl fmt::Display for AlertType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sos => write!(f, "SOS"),
+            Self::Hazard => write!(f, "Hazard"),
+            Self::Yielding => write!(f, "Yielding"),
+            Self::Accident => write!(f, "Accident"),
+            Self::RoadWork => write!(f, "Road Work"),
+            Self::Flooding => write!(f, "Flooding"),
+            Self::Debris => write!(f, "Debris"),
+            Self::StalledVehicle => write!(f, "Stalled Vehicle"),
+            Self::PoliceCheckpoint => write!(f, "Police Checkpoint"),
+            Self::TrafficJam => write!(f, "Traffic Jam"),
+            Self::EmergencyVehicle => write!(f, "Emergency Vehicle"),
+        }
+    }
+}
+
+impl AlertType {
+    pub fn all() -> &'static [AlertType] {
+        &[
+            Self::Sos,
+            Self::Hazard,
+            Self::Yielding,
+            Self::Accident,
+            Self::RoadWork,
+            Self::Flooding,
+            Self::Debris,
+            Self::StalledVehicle,
+            Self::PoliceCheckpoint,
+            Self::TrafficJam,
+            Self::EmergencyVehicle,
+        ]
+    }
+
+    /// Returns the phrases used to publish an alert of this type by voice, each made of
+    /// lowercase words separated by a single space.
+    pub fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Self::Sos => &["sos"],
+            Self::Hazard => &["hazard"],
+            Self::Yielding => &["yielding"],
+            Self::Accident => &["accident"],
+            Self::RoadWork => &["road work", "roadwork"],
+            Self::Flooding => &["flooding"],
+            Self::Debris => &["debris"],
+            Self::StalledVehicle => &["stalled vehicle", "stalled"],
+            Self::PoliceCheckpoint => &["police checkpoint", "checkpoint"],
+            Self::TrafficJam => &["traffic jam", "traffic"],
+            Self::EmergencyVehicle => &["emergency vehicle", "emergency"],
+        }
+    }
+
+    /// Returns the alert type with the given keyword phrase.
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .find(|alert_type| alert_type.keywords().contains(&keyword))
+            .copied()
+    }
+
+    /// Returns the alert type whose keyword phrase starts the words, along with the words
+    /// after it.
+    ///
+    /// The longest matching phrase is used, so "traffic jam ahead" is a traffic jam described
+    /// as "ahead" rather than one described as "jam ahead".
+    pub fn from_words<'a, 'w>(words: &'w [&'a str]) -> Option<(Self, &'w [&'a str])> {
+        Self::all()
+            .iter()
+            .flat_map(|alert_type| {
+                alert_type
+                    .keywords()
+                    .iter()
+                    .map(move |keyword| (*alert_type, keyword.split(' ').collect::<Vec<_>>()))
+            })
+            .filter(|(_, keyword_words)| words.starts_with(keyword_words))
+            .max_by_key(|(_, keyword_words)| keyword_words.len())
+            .map(|(alert_type, keyword_words)| (alert_type, &words[keyword_words.len()..]))
+    }
+
+    pub fn icon_name(self) -> String {
+        let prefix = match self {
+            Self::Sos => "rescue",
+            Self::Hazard => "dialog-warning",
+            Self::Yielding => "thumbs-up",
+            Self::Accident => "car-crash",
+            Self::RoadWork => "road-work",
+            Self::Flooding => "flood",
+            Self::Debris => "debris",
+            Self::StalledVehicle => "stalled-vehicle",
+            Self::PoliceCheckpoint => "police-badge",
+            Self::TrafficJam => "traffic-jam",
+            Self::EmergencyVehicle => "ambulance",
+        };
+
+        format!("{}-symbolic", prefix)
+    }
+
+    pub fn blink_count(self) -> u32 {
+        match self {
+            Self::Sos => 10,
+            Self::Accident | Self::EmergencyVehicle => 8,
+            Self::Hazard | Self::Flooding => 5,
+            Self::Debris | Self::StalledVehicle => 4,
+            Self::Yielding | Self::RoadWork | Self::PoliceCheckpoint | Self::TrafficJam => 3,
+        }
+    }
+
+    pub fn severity(self) -> AlertSeverity {
+        match self {
+            Self::Sos => AlertSeverity::Critical,
+            Self::Hazard | Self::Accident | Self::Flooding | Self::EmergencyVehicle => {
+                AlertSeverity::High
+            }
+            Self::Debris | Self::StalledVehicle => AlertSeverity::Medium,
+            Self::Yielding | Self::RoadWork | Self::PoliceCheckpoint | Self::TrafficJam => {
+                AlertSeverity::Low
+            }
+        }
+    }
+
+    /// Returns how long an alert of this type applies.
+    pub fn ttl(self) -> Duration {
+        match self {
+            Self::Sos | Self::Accident => Duration::from_secs(60 * 60),
+            Self::Hazard | Self::Debris | Self::StalledVehicle | Self::TrafficJam => {
+                Duration::from_secs(30 * 60)
+            }
+            Self::RoadWork | Self::Flooding => Duration::from_secs(4 * 60 * 60),
+            Self::PoliceCheckpoint => Duration::from_secs(2 * 60 * 60),
+            Self::Yielding | Self::EmergencyVehicle => Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AlertSeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, glib::Boxed)]
+#[boxed_type(name = "DeltaAlertResponseKind")]
+pub enum AlertResponseKind {
+    /// The peer saw the alert.
+    #[default]
+    Acknowledged,
+    /// The peer is heading to the alert location to help.
+    Responding,
+}
+
+mod response_imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::AlertResponse)]
+    pub struct AlertResponse {
+        pub(super) responder: OnceCell<PeerId>,
+
+        #[property(get, set, construct_only)]
+        pub(super) responder_name: RefCell<String>,
+        #[property(get)]
+        pub(super) kind: Cell<AlertResponseKind>,
+        #[property(get)]
+        pub(super) location: RefCell<Option<Location>>,
+        /// Speed in meters per second
+        #[property(get)]
+        pub(super) speed: Cell<f64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AlertResponse {
+        const NAME: &'static str = "DeltaAlertResponse";
+        type Type = super::AlertResponse;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for AlertResponse {}
+}
+
+glib::wrapper! {
+    /// Latest response of a peer to one of our alerts.
+    pub struct AlertResponse(ObjectSubclass<response_imp::AlertResponse>);
+}
+
+impl AlertResponse {
+    fn new(info: &AlertResponseInfo) -> Self {
+        let this = glib::Object::builder::<Self>()
+            .property("responder-name", &info.responder_name)
+            .build();
+        this.imp().responder.set(info.responder).unwrap();
+        this.update(info);
+        this
+    }
+
+    pub fn responder(&self) -> &PeerId {
+        self.imp().responder.get().unwrap()
+    }
+
+    /// Returns the distance in meters between the responder and the location.
+    pub fn distance(&self, location: &Location) -> Option<f64> {
+        self.location().map(|l| l.distance(location))
+    }
+
+    /// Returns the estimated time for the responder to reach the location at its current speed.
+    pub fn eta(&self, location: &Location) -> Option<Duration> {
+        let distance = self.distance(location)?;
+        let speed = self.speed();
+
+        (speed >= MIN_ETA_SPEED).then(|| Duration::from_secs_f64(distance / speed))
+    }
+
+    fn update(&self, info: &AlertResponseInfo) {
+        let imp = self.imp();
+
+        if info.kind != self.kind() {
+            imp.kind.set(info.kind);
+            self.notify_kind();
+        }
+
+        if info.location != self.location() {
+            imp.location.replace(info.location);
+            self.notify_location();
+        }
+
+        if info.speed != self.speed() {
+            imp.speed.set(info.speed);
+            self.notify_speed();
+        }
+    }
+}
+
+mod imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::Alert)]
+    pub struct Alert {
+        pub(super) info: OnceCell<AlertInfo>,
+        /// Responses of peers to the alert, only tracked for ours.
+        pub(super) responses: RefCell<Vec<AlertResponse>>,
+
+        #[property(get, set, construct_only)]
+        pub(super) sender_name: RefCell<String>,
+        /// Role of the sender, as verified from the alert credential.
+        #[property(get, set, construct_only, builder(PeerRole::default()))]
+        pub(super) sender_role: Cell<PeerRole>,
+        /// Whether the time-to-live elapsed and the alert no longer applies.
+        #[property(get)]
+        pub(super) is_expired: Cell<bool>,
+        /// How we responded to the alert, if at all.
+        pub(super) our_response: Cell<Option<AlertResponseKind>>,
+
+        pub(super) expiry_source_id: RefCell<Option<glib::SourceId>>,
+        /// Periodically sends our response again, while we are responding.
+        pub(super) response_update_source_id: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Alert {
+        const NAME: &'static str = "DeltaAlert";
+        type Type = super::Alert;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Alert {
+        fn dispose(&self) {
+            if let Some(source_id) = self.expiry_source_id.take() {
+                source_id.remove();
+            }
+
+            self.obj().stop_response_updates();
+        }
+    }
+
+    impl ListModelImpl for Alert {
+        fn item_type(&self) -> glib::Type {
+            AlertResponse::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.responses.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.responses
+                .borrow()
+                .get(position as usize)
+                .map(|v| v.upcast_ref::<glib::Object>())
+                .cloned()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Alert sent by us or a peer, which expires on its own.
+    ///
+    /// As a list model, it contains the responses of peers to it.
+    pub struct Alert(ObjectSubclass<imp::Alert>)
+        @implements gio::ListModel;
+}
+
+impl Alert {
+    pub fn new(info: AlertInfo, sender_role: PeerRole) -> Self {
+        let this = glib::Object::builder::<Self>()
+            .property("sender-name", &info.sender_name)
+            .property("sender-role", sender_role)
+            .build();
+        this.imp().info.set(info).unwrap();
+        this.schedule_expiry();
+        this
+    }
+
+    pub fn info(&self) -> &AlertInfo {
+        self.imp().info.get().unwrap()
+    }
+
+    pub fn id(&self) -> MessageId {
+        self.info().id
+    }
+
+    pub fn alert_type(&self) -> AlertType {
+        self.info().alert_type
+    }
+
+    pub fn severity(&self) -> AlertSeverity {
+        self.info().severity
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        self.info().location
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.info().description.as_deref()
+    }
+
+    pub fn created_date_time(&self) -> glib::DateTime {
+        glib::DateTime::from_unix_local(self.info().created_at).unwrap()
+    }
+
+    /// Returns the time left before the alert expires.
+    pub fn remaining(&self) -> Duration {
+        let now = glib::DateTime::now_utc().unwrap().to_unix();
+        Duration::from_secs((self.info().expires_at() - now).max(0) as u64)
+    }
+
+    pub fn our_response(&self) -> Option<AlertResponseKind> {
+        self.imp().our_response.get()
+    }
+
+    /// Sets how we responded to the alert, stopping the updates of a previous response.
+    pub fn set_our_response(&self, kind: AlertResponseKind) {
+        let imp = self.imp();
+
+        if imp.our_response.get() != Some(kind) {
+            self.stop_response_updates();
+        }
+
+        imp.our_response.set(Some(kind));
+    }
+
+    /// Sets the source that periodically sends our response again, which is removed when our
+    /// response changes or the alert expires.
+    pub fn set_response_update_source_id(&self, source_id: glib::SourceId) {
+        self.stop_response_updates();
+        self.imp()
+            .response_update_source_id
+            .replace(Some(source_id));
+    }
+
+    fn stop_response_updates(&self) {
+        if let Some(source_id) = self.imp().response_update_source_id.take() {
+            source_id.remove();
+        }
+    }
+
+    /// Adds or updates the response of a peer, returning it if it is new or its kind changed.
+    pub fn handle_response(&self, info: &AlertResponseInfo) -> Option<AlertResponse> {
+        let imp = self.imp();
+
+        let existing = imp
+            .responses
+            .borrow()
+            .iter()
+            .find(|response| response.responder() == &info.responder)
+            .cloned();
+
+        if let Some(response) = existing {
+            let prev_kind = response.kind();
+            response.update(info);
+            (response.kind() != prev_kind).then_some(response)
+        } else {
+            let response = AlertResponse::new(info);
+
+            let position = imp.responses.borrow().len() as u32;
+            imp.responses.borrow_mut().push(response.clone());
+            self.items_changed(position, 0, 1);
+
+            Some(response)
+        }
+    }
+
+    fn schedule_expiry(&self) {
+        let remaining = self.remaining();
+
+        if remaining.is_zero() {
+            self.set_expired();
+            return;
+        }
+
+        let source_id = glib::timeout_add_local_once(
+            remaining,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    obj.imp().expiry_source_id.replace(None);
+                    obj.set_expired();
+                }
+            ),
+        );
+        self.imp().expiry_source_id.replace(Some(source_id));
+    }
+
+    fn set_expired(&self) {
+        if self.is_expired() {
+            return;
+        }
+
+        tracing::debug!("Alert {} expired", self.id());
+
+        self.stop_response_updates();
+
+        self.imp().is_expired.set(true);
+        self.notify_is_expired();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_keyword() {
+        for alert_type in AlertType::all() {
+            for keyword in alert_type.keywords() {
+                assert_eq!(AlertType::from_keyword(keyword), Some(*alert_type));
+            }
+        }
+
+        assert_eq!(
+            AlertType::from_keyword("road work"),
+            Some(AlertType::RoadWork)
+        );
+        assert_eq!(
+            AlertType::from_keyword("roadwork"),
+            Some(AlertType::RoadWork)
+        );
+        assert_eq!(AlertType::from_keyword("road"), None);
+        assert_eq!(AlertType::from_keyword("unknown"), None);
+    }
+
+    #[test]
+    fn from_words() {
+        assert_eq!(
+            AlertType::from_words(&["traffic", "jam", "near", "the", "bridge"]),
+            Some((AlertType::TrafficJam, ["near", "the", "bridge"].as_slice()))
+        );
+        assert_eq!(
+            AlertType::from_words(&["traffic", "near", "the", "bridge"]),
+            Some((AlertType::TrafficJam, ["near", "the", "bridge"].as_slice()))
+        );
+        assert_eq!(
+            AlertType::from_words(&["road", "work", "ahead"]),
+            Some((AlertType::RoadWork, ["ahead"].as_slice()))
+        );
+        assert_eq!(
+            AlertType::from_words(&["stalled", "vehicle"]),
+            Some((AlertType::StalledVehicle, [].as_slice()))
+        );
+        assert_eq!(
+            AlertType::from_words(&["police", "checkpoint", "at", "exit"]),
+            Some((AlertType::PoliceCheckpoint, ["at", "exit"].as_slice()))
+        );
+        assert_eq!(
+            AlertType::from_words(&["sos"]),
+            Some((AlertType::Sos, [].as_slice()))
+        );
+    }
+
+    #[test]
+    fn from_words_unknown() {
+        assert_eq!(AlertType::from_words(&[]), None);
+        assert_eq!(AlertType::from_words(&["road"]), None);
+        assert_eq!(AlertType::from_words(&["police", "car"]), None);
+        assert_eq!(AlertType::from_words(&["jam", "traffic"]), None);
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+
+use gtk::glib;
+use libp2p::{gossipsub, PeerId};
+
+use crate::{
+    alert::{Alert, AlertResponse, AlertResponseKind, AlertSeverity, AlertType},
+    alert_log::{AlertDirection, AlertLogEntry},
+    alert_rate_limiter::{AlertRateLimitVerdict, AlertRateLimiter, AUTO_MUTE_DURATION},
+    authority::PeerRole,
+    config,
+    peer::Peer,
+    protocol::{AlertInfo, AlertResponseInfo, MessageId},
+    Application,
+};
+
+/// Most peers an alert is relayed through, so it spreads beyond those in range of the sender.
+const MAX_HOPS: u8 = 5;
+
+/// Time after which relayed copies of an alert response are no longer recognized.
+const RESPONSE_TTL_SECS: i64 = 10 * 60;
+
+/// Distance within which alerts are always relevant, even if they are behind us.
+const ALWAYS_RELEVANT_DISTANCE: f64 = 200.0;
+
+/// Largest angle between our heading and the direction to an alert for it to be ahead of us.
+const AHEAD_MAX_ANGLE: f64 = 60.0;
+
+/// Outcome of validating a received alert, before it is forwarded by gossipsub.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertValidation {
+    Accept,
+    /// The alert is expired or was already seen, which is not the fault of the forwarder.
+    Ignore,
+    /// The alert is forged or from a sender flooding the network, so it must not spread.
+    Reject,
+    /// The sender just exceeded the rate limit and is now muted, so the alert is rejected.
+    SenderAutoMuted,
+}
+
+impl AlertValidation {
+    pub fn acceptance(self) -> gossipsub::MessageAcceptance {
+        match self {
+            Self::Accept => gossipsub::MessageAcceptance::Accept,
+            Self::Ignore => gossipsub::MessageAcceptance::Ignore,
+            Self::Reject | Self::SenderAutoMuted => gossipsub::MessageAcceptance::Reject,
+        }
+    }
+}
+
+/// What to do with a received alert response.
+#[derive(Debug)]
+pub enum AlertResponseOutcome {
+    /// The response is to one of our alerts, and is new or its kind changed.
+    Received(Alert, AlertResponse),
+    /// The response is to someone else's alert, so it should be relayed towards them.
+    Relay(AlertResponseInfo),
+    Ignored,
+}
+
+/// Creates, validates and filters alerts and their responses.
+#[derive(Debug, Default)]
+pub struct AlertHandler {
+    /// Alerts and responses already handled, mapped to when they expire, so relayed copies
+    /// are ignored.
+    seen_relayed_messages: HashMap<MessageId, i64>,
+    /// Our unexpired alerts, to which peers may respond.
+    published_alerts: Vec<Alert>,
+    rate_limiter: AlertRateLimiter,
+}
+
+impl AlertHandler {
+    /// Creates a signed alert about a hazard at our current location, returning it with the
+    /// info to publish.
+    pub fn create_alert(
+        &mut self,
+        alert_type: AlertType,
+        description: Option<&str>,
+    ) -> (Alert, AlertInfo) {
+        let app = Application::get();
+
+        let mut info = AlertInfo {
+            id: MessageId::new_random(),
+            sender: app.keypair().public().to_peer_id(),
+            sender_name: config::name(),
+            alert_type,
+            severity: alert_type.severity(),
+            location: app.gps().location(),
+            description: description
+                .map(|description| description.trim().to_string())
+                .filter(|description| !description.is_empty()),
+            created_at: glib::DateTime::now_utc().unwrap().to_unix(),
+            ttl_secs: alert_type.ttl().as_secs() as u32,
+            hops: 0,
+            credential: app.credential(),
+            sender_key: Vec::new(),
+            signature: Vec::new(),
+        };
+        if let Err(err) = info.sign(&app.keypair()) {
+            tracing::error!("Failed to sign alert: {:?}", err);
+        }
+
+        let sender_role = info
+            .credential
+            .as_ref()
+            .map(|credential| credential.role)
+            .unwrap_or_default();
+
+        self.mark_relayed_message_seen(info.id, info.expires_at());
+        let alert = Alert::new(info.clone(), sender_role);
+
+        app.alert_log()
+            .insert(AlertLogEntry::new(info.clone(), AlertDirection::Sent));
+
+        self.published_alerts.retain(|alert| !alert.is_expired());
+        self.published_alerts.push(alert.clone());
+
+        (alert, info)
+    }
+
+    /// Creates our signed response to the alert, with our current location.
+    pub fn create_response(&mut self, alert: &Alert, kind: AlertResponseKind) -> AlertResponseInfo {
+        let app = Application::get();
+        let gps = app.gps();
+
+        let mut info = AlertResponseInfo {
+            id: MessageId::new_random(),
+            alert_id: alert.id(),
+            responder: app.keypair().public().to_peer_id(),
+            responder_name: config::name(),
+            kind,
+            location: gps.location(),
+            speed: gps.speed(),
+            created_at: glib::DateTime::now_utc().unwrap().to_unix(),
+            hops: 0,
+            responder_key: Vec::new(),
+            signature: Vec::new(),
+        };
+        if let Err(err) = info.sign(&app.keypair()) {
+            tracing::error!("Failed to sign alert response: {:?}", err);
+        }
+        self.mark_relayed_message_seen(info.id, info.created_at + RESPONSE_TTL_SECS);
+
+        info
+    }
+
+    /// Decides whether a received alert should be handled and forwarded by gossipsub.
+    ///
+    /// Expired and already seen alerts are ignored, while forged alerts and alerts from senders
+    /// flooding the network are rejected so they don't spread any further.
+    pub fn validate_alert(&mut self, info: &AlertInfo, source: Option<PeerId>) -> AlertValidation {
+        if let Err(err) = info.verify() {
+            tracing::warn!(
+                "Rejected alert {} claiming to be from {}: {:?}",
+                info.id,
+                info.sender,
+                err
+            );
+
+            return AlertValidation::Reject;
+        }
+
+        if info.hops == 0 && source != Some(info.sender) {
+            tracing::warn!(
+                "Rejected alert {} claiming to be from {} but published by {:?}",
+                info.id,
+                info.sender,
+                source
+            );
+
+            return AlertValidation::Reject;
+        }
+
+        if info.expires_at() <= glib::DateTime::now_utc().unwrap().to_unix() {
+            tracing::debug!("Ignored expired alert {}", info.id);
+
+            return AlertValidation::Ignore;
+        }
+
+        if !self.mark_relayed_message_seen(info.id, info.expires_at()) {
+            tracing::trace!("Ignored already seen alert {}", info.id);
+
+            return AlertValidation::Ignore;
+        }
+
+        // The sender can be trusted here, as the alert signature was verified above
+        match self.rate_limiter.check(info.sender, info.alert_type) {
+            AlertRateLimitVerdict::Allowed => AlertValidation::Accept,
+            AlertRateLimitVerdict::Duplicate => {
+                tracing::debug!(
+                    "Ignored duplicate {:?} alert {} from {}",
+                    info.alert_type,
+                    info.id,
+                    info.sender
+                );
+
+                AlertValidation::Ignore
+            }
+            AlertRateLimitVerdict::LimitExceeded => {
+                tracing::warn!(
+                    "Muting alerts from {} for {:?} for exceeding the rate limit",
+                    info.sender,
+                    AUTO_MUTE_DURATION
+                );
+
+                AlertValidation::SenderAutoMuted
+            }
+            AlertRateLimitVerdict::Muted => {
+                tracing::debug!("Rejected alert {} from auto-muted {}", info.id, info.sender);
+
+                AlertValidation::Reject
+            }
+        }
+    }
+
+    /// Decides whether a received alert response should be handled and forwarded by gossipsub,
+    /// rejecting the ones not signed by their responder.
+    pub fn validate_response(info: &AlertResponseInfo) -> gossipsub::MessageAcceptance {
+        if let Err(err) = info.verify() {
+            tracing::warn!(
+                "Rejected alert response {} claiming to be from {}: {:?}",
+                info.id,
+                info.responder,
+                err
+            );
+
+            return gossipsub::MessageAcceptance::Reject;
+        }
+
+        gossipsub::MessageAcceptance::Accept
+    }
+
+    /// Returns the alert to publish again, if it may be relayed any further.
+    ///
+    /// This applies even to alerts we don't show, so muting a peer doesn't cut off vehicles
+    /// further down the road.
+    pub fn relayed_alert(info: &AlertInfo) -> Option<AlertInfo> {
+        (info.hops < MAX_HOPS).then(|| AlertInfo {
+            hops: info.hops + 1,
+            ..info.clone()
+        })
+    }
+
+    /// Handles a validated alert forwarded by the peer, returning it if it should be announced.
+    pub fn handle_alert(&self, mut info: AlertInfo, peer: &Peer) -> Option<Alert> {
+        let app = Application::get();
+
+        let sender_role = app.authorities().verify_alert(&info);
+        let is_allowed = if sender_role.is_authority() {
+            app.settings()
+                .is_allowed_authority(&info.sender, &info.sender_name)
+        } else {
+            app.settings()
+                .is_allowed_peer(&info.sender, &info.sender_name)
+        };
+        if !is_allowed {
+            tracing::debug!("Ignored alert from muted peer");
+
+            return None;
+        }
+
+        // Assume the hazard is where the sender is if it had no fix. This is only done on our
+        // copy, as the relayed alert must stay as signed.
+        if info.location.is_none() && info.hops == 0 {
+            info.location = peer.location();
+        }
+
+        // Keep a record of irrelevant alerts too, even if we don't announce them
+        app.alert_log()
+            .insert(AlertLogEntry::new(info.clone(), AlertDirection::Received));
+
+        if !is_relevant(&info, sender_role) {
+            return None;
+        }
+
+        Some(Alert::new(info, sender_role))
+    }
+
+    /// Handles a validated alert response, tracking it if it is to one of our alerts.
+    pub fn handle_response(&mut self, info: AlertResponseInfo) -> AlertResponseOutcome {
+        if !self.mark_relayed_message_seen(info.id, info.created_at + RESPONSE_TTL_SECS) {
+            tracing::trace!("Ignored already seen alert response {}", info.id);
+
+            return AlertResponseOutcome::Ignored;
+        }
+
+        let alert = self
+            .published_alerts
+            .iter()
+            .find(|alert| alert.id() == info.alert_id)
+            .cloned();
+
+        if let Some(alert) = alert {
+            tracing::debug!(
+                "Received {:?} from {} to alert {}",
+                info.kind,
+                info.responder,
+                info.alert_id
+            );
+
+            Application::get().alert_log().record_response(&info);
+
+            match alert.handle_response(&info) {
+                Some(response) => AlertResponseOutcome::Received(alert, response),
+                None => AlertResponseOutcome::Ignored,
+            }
+        } else if info.hops < MAX_HOPS {
+            AlertResponseOutcome::Relay(AlertResponseInfo {
+                hops: info.hops + 1,
+                ..info
+            })
+        } else {
+            AlertResponseOutcome::Ignored
+        }
+    }
+
+    /// Records the relayed message as handled until it expires, returning false if it
+    /// already was.
+    fn mark_relayed_message_seen(&mut self, id: MessageId, expires_at: i64) -> bool {
+        let now = glib::DateTime::now_utc().unwrap().to_unix();
+        self.seen_relayed_messages
+            .retain(|_, expires_at| *expires_at > now);
+
+        self.seen_relayed_messages.insert(id, expires_at).is_none()
+    }
+}
+
+/// Returns whether the alert is close enough, and ahead of us if enabled, to be announced.
+///
+/// Alerts are assumed relevant if either location is unknown.
+fn is_relevant(info: &AlertInfo, sender_role: PeerRole) -> bool {
+    let app = Application::get();
+    let settings = app.settings();
+    let gps = app.gps();
+
+    let (Some(alert_location), Some(our_location)) = (info.location, gps.location()) else {
+        return true;
+    };
+
+    let distance = our_location.distance(&alert_location);
+
+    if distance > settings.alert_radius_m() as f64 {
+        tracing::info!(
+            "Alert {} from {} is {:.0} m away, outside the alert radius",
+            info.id,
+            info.sender_name,
+            distance
+        );
+        return false;
+    }
+
+    // Someone in danger behind us may still need our help, and authorities may need
+    // us to make way even if they are behind us
+    if !settings.alerts_ahead_only()
+        || info.severity == AlertSeverity::Critical
+        || sender_role.is_authority()
+        || distance <= ALWAYS_RELEVANT_DISTANCE
+    {
+        return true;
+    }
+
+    let Some(heading) = gps.known_heading() else {
+        return true;
+    };
+
+    let angle = (our_location.bearing(&alert_location) - heading + 540.0) % 360.0 - 180.0;
+
+    if angle.abs() > AHEAD_MAX_ANGLE {
+        tracing::info!(
+            "Alert {} from {} is {:.0}° off our heading, not ahead of us",
+            info.id,
+            info.sender_name,
+            angle.abs()
+        );
+        return false;
+    }
+
+    true
+}
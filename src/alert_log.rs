@@ -0,0 +1,473 @@
+use std::fmt;
+
+use anyhow::Result;
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use libp2p::PeerId;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alert::{AlertResponseKind, AlertType},
+    config,
+    location::Location,
+    protocol::{AlertInfo, AlertResponseInfo, MessageId},
+    utils,
+};
+
+static ALERT_LOG_FILE: Lazy<gio::File> = Lazy::new(|| {
+    let mut path = config::user_config_dir();
+    path.push("alert_log.json");
+    gio::File::for_path(path)
+});
+
+const CSV_HEADER: &str = "time,direction,type,severity,sender_id,sender_name,latitude,longitude,description,acknowledged_count,responding_count,our_response";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, glib::Enum)]
+#[enum_type(name = "DeltaAlertDirection")]
+pub enum AlertDirection {
+    #[default]
+    Received,
+    Sent,
+}
+
+impl fmt::Display for AlertDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertDirection::Received => write!(f, "Received"),
+            AlertDirection::Sent => write!(f, "Sent"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LoggedResponse {
+    responder: PeerId,
+    responder_name: String,
+    kind: AlertResponseKind,
+    /// Time of the latest response, in seconds since the Unix epoch.
+    timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AlertLogEntryData {
+    info: AlertInfo,
+    direction: AlertDirection,
+    /// Time we sent or received the alert, in seconds since the Unix epoch.
+    logged_at: i64,
+    /// Responses of peers, only for alerts we sent.
+    #[serde(default)]
+    responses: Vec<LoggedResponse>,
+    /// How we responded, only for alerts we received.
+    #[serde(default)]
+    our_response: Option<AlertResponseKind>,
+}
+
+mod entry_imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::AlertLogEntry)]
+    pub struct AlertLogEntry {
+        pub(super) data: OnceCell<RefCell<AlertLogEntryData>>,
+
+        #[property(get)]
+        pub(super) acknowledged_count: Cell<u32>,
+        #[property(get)]
+        pub(super) responding_count: Cell<u32>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AlertLogEntry {
+        const NAME: &'static str = "DeltaAlertLogEntry";
+        type Type = super::AlertLogEntry;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for AlertLogEntry {}
+}
+
+glib::wrapper! {
+    /// Alert we sent or received, as recorded in the alert log.
+    pub struct AlertLogEntry(ObjectSubclass<entry_imp::AlertLogEntry>);
+}
+
+impl AlertLogEntry {
+    pub fn new(info: AlertInfo, direction: AlertDirection) -> Self {
+        Self::from_data(AlertLogEntryData {
+            info,
+            direction,
+            logged_at: glib::DateTime::now_utc().unwrap().to_unix(),
+            responses: Vec::new(),
+            our_response: None,
+        })
+    }
+
+    fn from_data(data: AlertLogEntryData) -> Self {
+        let this = glib::Object::new::<Self>();
+        this.imp().data.set(RefCell::new(data)).unwrap();
+        this.update_response_counts();
+        this
+    }
+
+    pub fn id(&self) -> MessageId {
+        self.data().borrow().info.id
+    }
+
+    pub fn alert_type(&self) -> AlertType {
+        self.data().borrow().info.alert_type
+    }
+
+    pub fn sender_name(&self) -> String {
+        self.data().borrow().info.sender_name.clone()
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        self.data().borrow().info.location
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.data().borrow().info.description.clone()
+    }
+
+    pub fn direction(&self) -> AlertDirection {
+        self.data().borrow().direction
+    }
+
+    pub fn our_response(&self) -> Option<AlertResponseKind> {
+        self.data().borrow().our_response
+    }
+
+    pub fn logged_date_time(&self) -> glib::DateTime {
+        glib::DateTime::from_unix_local(self.data().borrow().logged_at).unwrap()
+    }
+
+    fn data(&self) -> &RefCell<AlertLogEntryData> {
+        self.imp().data.get().unwrap()
+    }
+
+    /// Records the response, returning whether the entry changed.
+    fn record_response(&self, info: &AlertResponseInfo) -> bool {
+        let mut data = self.data().borrow_mut();
+
+        let response = LoggedResponse {
+            responder: info.responder,
+            responder_name: info.responder_name.clone(),
+            kind: info.kind,
+            timestamp: info.created_at,
+        };
+
+        if let Some(existing) = data
+            .responses
+            .iter_mut()
+            .find(|r| r.responder == info.responder)
+        {
+            if existing.kind == info.kind {
+                return false;
+            }
+
+            *existing = response;
+        } else {
+            data.responses.push(response);
+        }
+
+        drop(data);
+
+        self.update_response_counts();
+
+        true
+    }
+
+    fn record_our_response(&self, kind: AlertResponseKind) -> bool {
+        let mut data = self.data().borrow_mut();
+
+        if data.our_response == Some(kind) {
+            return false;
+        }
+
+        data.our_response = Some(kind);
+
+        true
+    }
+
+    fn update_response_counts(&self) {
+        let imp = self.imp();
+
+        let count = |kind| {
+            self.data()
+                .borrow()
+                .responses
+                .iter()
+                .filter(|response| response.kind == kind)
+                .count() as u32
+        };
+        let acknowledged_count = count(AlertResponseKind::Acknowledged);
+        let responding_count = count(AlertResponseKind::Responding);
+
+        if acknowledged_count != self.acknowledged_count() {
+            imp.acknowledged_count.set(acknowledged_count);
+            self.notify_acknowledged_count();
+        }
+
+        if responding_count != self.responding_count() {
+            imp.responding_count.set(responding_count);
+            self.notify_responding_count();
+        }
+    }
+
+    fn to_csv_record(&self) -> String {
+        let data = self.data().borrow();
+
+        let time = glib::DateTime::from_unix_utc(data.logged_at)
+            .and_then(|date_time| date_time.format_iso8601())
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        let (latitude, longitude) = data
+            .info
+            .location
+            .map(|l| (l.latitude.to_string(), l.longitude.to_string()))
+            .unwrap_or_default();
+
+        [
+            time,
+            data.direction.to_string(),
+            data.info.alert_type.to_string(),
+            format!("{:?}", data.info.severity),
+            data.info.sender.to_string(),
+            data.info.sender_name.clone(),
+            latitude,
+            longitude,
+            data.info.description.clone().unwrap_or_default(),
+            self.acknowledged_count().to_string(),
+            self.responding_count().to_string(),
+            data.our_response
+                .map(|kind| format!("{:?}", kind))
+                .unwrap_or_default(),
+        ]
+        .iter()
+        .map(|field| utils::csv_escape(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+
+    fn to_geojson_feature(&self) -> serde_json::Value {
+        let data = self.data().borrow();
+
+        // GeoJSON positions are longitude first
+        let geometry = data.info.location.map(|l| {
+            serde_json::json!({
+                "type": "Point",
+                "coordinates": [l.longitude, l.latitude],
+            })
+        });
+
+        serde_json::json!({
+            "type": "Feature",
+            "id": data.info.id.to_string(),
+            "geometry": geometry,
+            "properties": {
+                "time": glib::DateTime::from_unix_utc(data.logged_at)
+                    .and_then(|date_time| date_time.format_iso8601())
+                    .map(|s| s.to_string())
+                    .ok(),
+                "direction": data.direction.to_string(),
+                "type": data.info.alert_type.to_string(),
+                "severity": format!("{:?}", data.info.severity),
+                "sender_id": data.info.sender.to_string(),
+                "sender_name": data.info.sender_name,
+                "description": data.info.description,
+                "responses": data.responses.iter().map(|response| {
+                    serde_json::json!({
+                        "responder_id": response.responder.to_string(),
+                        "responder_name": response.responder_name,
+                        "kind": format!("{:?}", response.kind),
+                    })
+                }).collect::<Vec<_>>(),
+                "our_response": data.our_response.map(|kind| format!("{:?}", kind)),
+            },
+        })
+    }
+}
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct AlertLog {
+        pub(super) list: RefCell<Vec<AlertLogEntry>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AlertLog {
+        const NAME: &'static str = "DeltaAlertLog";
+        type Type = super::AlertLog;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    impl ObjectImpl for AlertLog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            if let Err(err) = obj.load() {
+                tracing::error!("Failed to load alert log: {:?}", err);
+            }
+        }
+    }
+
+    impl ListModelImpl for AlertLog {
+        fn item_type(&self) -> glib::Type {
+            AlertLogEntry::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.list.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.list
+                .borrow()
+                .get(position as usize)
+                .map(|v| v.upcast_ref::<glib::Object>())
+                .cloned()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Persisted log of alerts we sent and received, newest first.
+    pub struct AlertLog(ObjectSubclass<imp::AlertLog>)
+        @implements gio::ListModel;
+}
+
+impl AlertLog {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn insert(&self, entry: AlertLogEntry) {
+        self.imp().list.borrow_mut().insert(0, entry);
+        self.items_changed(0, 0, 1);
+
+        self.save_and_log();
+    }
+
+    /// Records the response of a peer to one of our alerts.
+    pub fn record_response(&self, info: &AlertResponseInfo) {
+        if self
+            .find(info.alert_id)
+            .is_some_and(|entry| entry.record_response(info))
+        {
+            self.save_and_log();
+        }
+    }
+
+    /// Records how we responded to an alert we received.
+    pub fn record_our_response(&self, alert_id: MessageId, kind: AlertResponseKind) {
+        if self
+            .find(alert_id)
+            .is_some_and(|entry| entry.record_our_response(kind))
+        {
+            self.save_and_log();
+        }
+    }
+
+    pub fn clear(&self) {
+        let n_removed = self.imp().list.take().len();
+
+        if n_removed > 0 {
+            self.items_changed(0, n_removed as u32, 0);
+
+            self.save_and_log();
+        }
+    }
+
+    /// Returns the log as CSV, oldest alert first.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+
+        for entry in self.imp().list.borrow().iter().rev() {
+            csv.push_str(&entry.to_csv_record());
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Returns the log as a GeoJSON feature collection, oldest alert first.
+    ///
+    /// Alerts without a location have a null geometry.
+    pub fn to_geojson(&self) -> Result<String> {
+        let features = self
+            .imp()
+            .list
+            .borrow()
+            .iter()
+            .rev()
+            .map(|entry| entry.to_geojson_feature())
+            .collect::<Vec<_>>();
+
+        let collection = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": features,
+        });
+
+        Ok(serde_json::to_string_pretty(&collection)?)
+    }
+
+    fn find(&self, id: MessageId) -> Option<AlertLogEntry> {
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .find(|entry| entry.id() == id)
+            .cloned()
+    }
+
+    fn save_and_log(&self) {
+        if let Err(err) = self.save() {
+            tracing::error!("Failed to save alert log: {:?}", err);
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let data = self
+            .imp()
+            .list
+            .borrow()
+            .iter()
+            .map(|entry| entry.data().borrow().clone())
+            .collect::<Vec<_>>();
+        utils::save_json(&ALERT_LOG_FILE, &data)
+    }
+
+    fn load(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let data = utils::load_json::<Vec<AlertLogEntryData>>(&ALERT_LOG_FILE)?.unwrap_or_default();
+
+        tracing::debug!(
+            "Loaded {} alert log entries from {}",
+            data.len(),
+            ALERT_LOG_FILE.path().unwrap().display()
+        );
+
+        let prev_len = imp.list.borrow().len();
+        imp.list
+            .replace(data.into_iter().map(AlertLogEntry::from_data).collect());
+        self.items_changed(0, prev_len as u32, self.n_items());
+
+        Ok(())
+    }
+}
+
+impl Default for AlertLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use libp2p::PeerId;
+
+use crate::alert::AlertType;
+
+/// Window over which alerts from a sender are counted.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+/// Most alerts a sender may publish within the window before being muted.
+const RATE_LIMIT_MAX_ALERTS: usize = 5;
+/// Time within which another alert of the same type from a sender is treated as a duplicate.
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(30);
+/// How long a sender exceeding the rate limit is muted for.
+pub const AUTO_MUTE_DURATION: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertRateLimitVerdict {
+    /// The alert should be handled as usual.
+    Allowed,
+    /// An alert of the same type was recently received from the same sender.
+    Duplicate,
+    /// The sender has just exceeded the rate limit and is now muted.
+    LimitExceeded,
+    /// The sender is still muted from exceeding the rate limit.
+    Muted,
+}
+
+#[derive(Debug, Default)]
+struct SenderState {
+    recent_alerts: VecDeque<(Instant, AlertType)>,
+    muted_until: Option<Instant>,
+}
+
+/// Tracks alerts per sender to stop peers from flooding others with them.
+#[derive(Debug, Default)]
+pub struct AlertRateLimiter {
+    senders: HashMap<PeerId, SenderState>,
+}
+
+impl AlertRateLimiter {
+    /// Records an alert from the sender and returns how it should be handled.
+    ///
+    /// The sender must be authenticated, otherwise anyone could get a peer muted by flooding
+    /// alerts in its name.
+    pub fn check(&mut self, sender: PeerId, alert_type: AlertType) -> AlertRateLimitVerdict {
+        self.check_at(sender, alert_type, Instant::now())
+    }
+
+    /// Like [`Self::check`], but for an alert received at the given time.
+    fn check_at(
+        &mut self,
+        sender: PeerId,
+        alert_type: AlertType,
+        now: Instant,
+    ) -> AlertRateLimitVerdict {
+        self.senders.retain(|_, state| {
+            state
+                .recent_alerts
+                .retain(|(received_at, _)| now.duration_since(*received_at) < RATE_LIMIT_WINDOW);
+
+            if state.muted_until.is_some_and(|until| until <= now) {
+                state.muted_until = None;
+            }
+
+            !state.recent_alerts.is_empty() || state.muted_until.is_some()
+        });
+
+        let state = self.senders.entry(sender).or_default();
+
+        if state.muted_until.is_some() {
+            return AlertRateLimitVerdict::Muted;
+        }
+
+        let is_duplicate = state.recent_alerts.iter().any(|(received_at, other_type)| {
+            *other_type == alert_type && now.duration_since(*received_at) < DUPLICATE_WINDOW
+        });
+
+        // Duplicates count too, so resending the same alert can't get around the limit
+        state.recent_alerts.push_back((now, alert_type));
+
+        if state.recent_alerts.len() > RATE_LIMIT_MAX_ALERTS {
+            state.recent_alerts.clear();
+            state.muted_until = Some(now + AUTO_MUTE_DURATION);
+
+            return AlertRateLimitVerdict::LimitExceeded;
+        }
+
+        if is_duplicate {
+            AlertRateLimitVerdict::Duplicate
+        } else {
+            AlertRateLimitVerdict::Allowed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Alert types that are all different from each other, so none is a duplicate.
+    const DISTINCT_TYPES: [AlertType; RATE_LIMIT_MAX_ALERTS] = [
+        AlertType::Hazard,
+        AlertType::Accident,
+        AlertType::RoadWork,
+        AlertType::Flooding,
+        AlertType::Debris,
+    ];
+
+    /// Sends as many distinct alerts as allowed from the sender, asserting each is allowed.
+    fn fill_limit(limiter: &mut AlertRateLimiter, sender: PeerId, now: Instant) {
+        for alert_type in DISTINCT_TYPES {
+            assert_eq!(
+                limiter.check_at(sender, alert_type, now),
+                AlertRateLimitVerdict::Allowed
+            );
+        }
+    }
+
+    #[test]
+    fn allowed() {
+        let mut limiter = AlertRateLimiter::default();
+        let sender = PeerId::random();
+        let now = Instant::now();
+
+        fill_limit(&mut limiter, sender, now);
+
+        // Alerts older than the window no longer count
+        let later = now + RATE_LIMIT_WINDOW;
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Sos, later),
+            AlertRateLimitVerdict::Allowed
+        );
+    }
+
+    #[test]
+    fn duplicate() {
+        let mut limiter = AlertRateLimiter::default();
+        let sender = PeerId::random();
+        let now = Instant::now();
+
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Hazard, now),
+            AlertRateLimitVerdict::Allowed
+        );
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Hazard, now + Duration::from_secs(1)),
+            AlertRateLimitVerdict::Duplicate
+        );
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Accident, now + Duration::from_secs(2)),
+            AlertRateLimitVerdict::Allowed
+        );
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Hazard, now + DUPLICATE_WINDOW * 2),
+            AlertRateLimitVerdict::Allowed
+        );
+    }
+
+    #[test]
+    fn limit_exceeded() {
+        let mut limiter = AlertRateLimiter::default();
+        let sender = PeerId::random();
+        let now = Instant::now();
+
+        fill_limit(&mut limiter, sender, now);
+
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Sos, now),
+            AlertRateLimitVerdict::LimitExceeded
+        );
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Sos, now + Duration::from_secs(1)),
+            AlertRateLimitVerdict::Muted
+        );
+    }
+
+    #[test]
+    fn duplicates_count_towards_limit() {
+        let mut limiter = AlertRateLimiter::default();
+        let sender = PeerId::random();
+        let now = Instant::now();
+
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Hazard, now),
+            AlertRateLimitVerdict::Allowed
+        );
+        for _ in 1..RATE_LIMIT_MAX_ALERTS {
+            assert_eq!(
+                limiter.check_at(sender, AlertType::Hazard, now),
+                AlertRateLimitVerdict::Duplicate
+            );
+        }
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Hazard, now),
+            AlertRateLimitVerdict::LimitExceeded
+        );
+    }
+
+    #[test]
+    fn auto_mute_expires() {
+        let mut limiter = AlertRateLimiter::default();
+        let sender = PeerId::random();
+        let now = Instant::now();
+
+        fill_limit(&mut limiter, sender, now);
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Sos, now),
+            AlertRateLimitVerdict::LimitExceeded
+        );
+
+        let almost_unmuted = now + AUTO_MUTE_DURATION - Duration::from_secs(1);
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Sos, almost_unmuted),
+            AlertRateLimitVerdict::Muted
+        );
+
+        let unmuted = now + AUTO_MUTE_DURATION;
+        assert_eq!(
+            limiter.check_at(sender, AlertType::Sos, unmuted),
+            AlertRateLimitVerdict::Allowed
+        );
+    }
+
+    #[test]
+    fn senders_are_isolated() {
+        let mut limiter = AlertRateLimiter::default();
+        let muted_sender = PeerId::random();
+        let other_sender = PeerId::random();
+        let now = Instant::now();
+
+        fill_limit(&mut limiter, muted_sender, now);
+        assert_eq!(
+            limiter.check_at(muted_sender, AlertType::Sos, now),
+            AlertRateLimitVerdict::LimitExceeded
+        );
+
+        assert_eq!(
+            limiter.check_at(other_sender, AlertType::Sos, now),
+            AlertRateLimitVerdict::Allowed
+        );
+        assert_eq!(
+            limiter.check_at(other_sender, AlertType::Hazard, now),
+            AlertRateLimitVerdict::Allowed
+        );
+    }
+}
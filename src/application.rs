@@ -4,12 +4,21 @@ use gtk::{
     gio,
     glib::{self, clone},
 };
+use libp2p::identity::Keypair;
 
 use crate::{
+    alert_log::AlertLog,
+    authority::{self, Authorities},
+    call_history::CallHistory,
     gps::Gps,
+    identity,
+    missed_call_list::MissedCallList,
+    protocol::Credential,
     remote::{LedColor, LedId, Remote},
     settings::{AllowedPeers, Settings},
     ui::Window,
+    voice_message::VoiceMessageInbox,
+    voice_message_outbox::VoiceMessageOutbox,
     wireless_info::WirelessInfo,
     APP_ID, GRESOURCE_PREFIX,
 };
@@ -26,9 +35,17 @@ mod imp {
     pub struct Application {
         pub(super) gps: Gps,
         pub(super) settings: Settings,
+        pub(super) call_history: CallHistory,
+        pub(super) alert_log: AlertLog,
+        pub(super) missed_call_list: MissedCallList,
+        pub(super) voice_message_inbox: VoiceMessageInbox,
+        pub(super) voice_message_outbox: VoiceMessageOutbox,
         pub(super) wireless_info: WirelessInfo,
 
         pub(super) remote: OnceCell<Remote>,
+        pub(super) keypair: OnceCell<Keypair>,
+        pub(super) authorities: OnceCell<Authorities>,
+        pub(super) credential: OnceCell<Option<Credential>>,
     }
 
     #[glib::object_subclass]
@@ -77,6 +94,24 @@ mod imp {
 
             let remote = Remote::new(self.settings.remote_ip_addr());
             self.remote.set(remote).unwrap();
+
+            let keypair = identity::load_or_generate().unwrap_or_else(|err| {
+                tracing::error!("Failed to load identity, using a temporary one: {:?}", err);
+                Keypair::generate_ed25519()
+            });
+            self.keypair.set(keypair).unwrap();
+
+            let authorities = Authorities::load().unwrap_or_else(|err| {
+                tracing::error!("Failed to load trusted authorities: {:?}", err);
+                Authorities::default()
+            });
+            self.authorities.set(authorities).unwrap();
+
+            let credential = authority::load_credential().unwrap_or_else(|err| {
+                tracing::error!("Failed to load credential: {:?}", err);
+                None
+            });
+            self.credential.set(credential).unwrap();
         }
 
         fn shutdown(&self) {
@@ -129,6 +164,26 @@ impl Application {
         self.imp().settings.clone()
     }
 
+    pub fn call_history(&self) -> CallHistory {
+        self.imp().call_history.clone()
+    }
+
+    pub fn alert_log(&self) -> AlertLog {
+        self.imp().alert_log.clone()
+    }
+
+    pub fn missed_call_list(&self) -> MissedCallList {
+        self.imp().missed_call_list.clone()
+    }
+
+    pub fn voice_message_inbox(&self) -> VoiceMessageInbox {
+        self.imp().voice_message_inbox.clone()
+    }
+
+    pub fn voice_message_outbox(&self) -> VoiceMessageOutbox {
+        self.imp().voice_message_outbox.clone()
+    }
+
     pub fn wireless_info(&self) -> WirelessInfo {
         self.imp().wireless_info.clone()
     }
@@ -137,6 +192,21 @@ impl Application {
         self.imp().remote.get().unwrap().clone()
     }
 
+    /// Returns the keypair used as our identity in the network.
+    pub fn keypair(&self) -> Keypair {
+        self.imp().keypair.get().unwrap().clone()
+    }
+
+    /// Returns the authorities trusted to issue credentials to peers.
+    pub fn authorities(&self) -> &Authorities {
+        self.imp().authorities.get().unwrap()
+    }
+
+    /// Returns the credential proving our role, if we were issued one.
+    pub fn credential(&self) -> Option<Credential> {
+        self.imp().credential.get().unwrap().clone()
+    }
+
     fn window(&self) -> Window {
         self.active_window()
             .map_or_else(|| Window::new(self), |w| w.downcast().unwrap())
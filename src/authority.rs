@@ -0,0 +1,330 @@
+use std::fmt;
+
+use anyhow::{ensure, Result};
+use gtk::{gio, glib, prelude::*};
+use libp2p::{identity::PublicKey, PeerId};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config,
+    protocol::{AlertInfo, Credential},
+    utils,
+};
+
+/// Directory of trusted authority public keys, one protobuf-encoded key per file.
+static AUTHORITIES_DIR: Lazy<gio::File> = Lazy::new(|| {
+    let mut path = config::user_config_dir();
+    path.push("authorities");
+    gio::File::for_path(path)
+});
+
+static CREDENTIAL_FILE: Lazy<gio::File> = Lazy::new(|| {
+    let mut path = config::user_config_dir();
+    path.push("credential.json");
+    gio::File::for_path(path)
+});
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, glib::Enum)]
+#[enum_type(name = "DeltaPeerRole")]
+pub enum PeerRole {
+    #[default]
+    Civilian,
+    EmergencyServices,
+    RoadOperator,
+}
+
+impl fmt::Display for PeerRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Civilian => write!(f, "Civilian"),
+            Self::EmergencyServices => write!(f, "Emergency Services"),
+            Self::RoadOperator => write!(f, "Road Operator"),
+        }
+    }
+}
+
+impl PeerRole {
+    /// Returns whether the role was granted by an authority, rather than being the default.
+    pub fn is_authority(self) -> bool {
+        !matches!(self, Self::Civilian)
+    }
+
+    pub fn icon_name(self) -> Option<&'static str> {
+        match self {
+            Self::Civilian => None,
+            Self::EmergencyServices => Some("rescue-symbolic"),
+            Self::RoadOperator => Some("road-work-symbolic"),
+        }
+    }
+}
+
+/// Locally trusted authorities, which issue credentials to peers.
+#[derive(Debug, Default)]
+pub struct Authorities {
+    keys: Vec<PublicKey>,
+}
+
+impl Authorities {
+    /// Loads the trusted authority keys, skipping the ones that can't be decoded.
+    pub fn load() -> Result<Self> {
+        let enumerator = match AUTHORITIES_DIR.enumerate_children(
+            gio::FILE_ATTRIBUTE_STANDARD_NAME,
+            gio::FileQueryInfoFlags::NONE,
+            gio::Cancellable::NONE,
+        ) {
+            Ok(enumerator) => enumerator,
+            Err(err) => {
+                if err.matches(gio::IOErrorEnum::NotFound) {
+                    return Ok(Self::default());
+                }
+
+                return Err(err.into());
+            }
+        };
+
+        let mut keys = Vec::new();
+        for info in enumerator {
+            let file = AUTHORITIES_DIR.child(info?.name());
+
+            let (bytes, _) = file.load_contents(gio::Cancellable::NONE)?;
+            match PublicKey::try_decode_protobuf(&bytes) {
+                Ok(key) => keys.push(key),
+                Err(err) => {
+                    tracing::warn!(
+                        "Skipped invalid authority key {}: {:?}",
+                        file.path().unwrap().display(),
+                        err
+                    );
+                }
+            }
+        }
+
+        tracing::debug!("Loaded {} trusted authorities", keys.len());
+
+        Ok(Self { keys })
+    }
+
+    /// Returns the role granted by the credential, or why it was rejected if it wasn't issued
+    /// to the peer by a trusted authority or has expired.
+    pub fn verify_credential(&self, credential: &Credential, peer_id: &PeerId) -> Result<PeerRole> {
+        let now = glib::DateTime::now_utc().unwrap().to_unix();
+        self.verify_credential_at(credential, peer_id, now)
+    }
+
+    /// Like [`Self::verify_credential`], but checks the expiry against the given time, in
+    /// seconds since the Unix epoch.
+    fn verify_credential_at(
+        &self,
+        credential: &Credential,
+        peer_id: &PeerId,
+        now: i64,
+    ) -> Result<PeerRole> {
+        let issuer_key = PublicKey::try_decode_protobuf(&credential.issuer_key)?;
+        ensure!(
+            self.keys.contains(&issuer_key),
+            "issuer {} is not trusted",
+            issuer_key.to_peer_id()
+        );
+
+        let subject_key = PublicKey::try_decode_protobuf(&credential.subject_key)?;
+        ensure!(
+            subject_key.to_peer_id() == *peer_id,
+            "credential was issued to {}",
+            subject_key.to_peer_id()
+        );
+
+        ensure!(credential.expires_at > now, "credential expired");
+
+        ensure!(
+            issuer_key.verify(&credential_signed_bytes(credential)?, &credential.signature),
+            "invalid issuer signature"
+        );
+
+        Ok(credential.role)
+    }
+
+    /// Returns the verified role of the alert sender, which is the default one if the
+    /// alert has no valid credential.
+    ///
+    /// The alert signature must have been checked already, so the sender is authentic.
+    pub fn verify_alert(&self, info: &AlertInfo) -> PeerRole {
+        let Some(credential) = &info.credential else {
+            return PeerRole::default();
+        };
+
+        self.verify_credential(credential, &info.sender)
+            .unwrap_or_else(|err| {
+                tracing::warn!(
+                    "Rejected credential of alert from {}: {:?}",
+                    info.sender,
+                    err
+                );
+                PeerRole::default()
+            })
+    }
+}
+
+/// Loads our credential, if we were issued one.
+pub fn load_credential() -> Result<Option<Credential>> {
+    let credential = utils::load_json::<Credential>(&CREDENTIAL_FILE)?;
+
+    if let Some(credential) = &credential {
+        tracing::debug!(
+            "Loaded {} credential from {}",
+            credential.role,
+            CREDENTIAL_FILE.path().unwrap().display()
+        );
+    }
+
+    Ok(credential)
+}
+
+/// Removes our credential, such as when our identity changed so it no longer applies to us.
+pub fn remove_credential() -> Result<()> {
+    if let Err(err) = CREDENTIAL_FILE.delete(gio::Cancellable::NONE) {
+        if err.matches(gio::IOErrorEnum::NotFound) {
+            return Ok(());
+        }
+
+        return Err(err.into());
+    }
+
+    tracing::debug!(
+        "Removed credential {}",
+        CREDENTIAL_FILE.path().unwrap().display()
+    );
+
+    Ok(())
+}
+
+/// Returns the bytes an authority signs to issue a credential.
+///
+/// These are the subject key, role and expiry of the credential serialized as a JSON array,
+/// such as `[[8,1,18,32,...],"EmergencyServices",1735689600]`, where the key is the array of
+/// its protobuf-encoded bytes and the expiry is in seconds since the Unix epoch. The issuer
+/// key is not included, as the signature is only valid for that key anyway.
+fn credential_signed_bytes(credential: &Credential) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&(
+        &credential.subject_key,
+        credential.role,
+        credential.expires_at,
+    ))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use libp2p::identity::Keypair;
+
+    use super::*;
+
+    const NOW: i64 = 1_735_689_600;
+
+    fn issue(issuer: &Keypair, subject: &Keypair, expires_at: i64) -> Credential {
+        let mut credential = Credential {
+            subject_key: subject.public().encode_protobuf(),
+            role: PeerRole::EmergencyServices,
+            expires_at,
+            issuer_key: issuer.public().encode_protobuf(),
+            signature: Vec::new(),
+        };
+        credential.signature = issuer
+            .sign(&credential_signed_bytes(&credential).unwrap())
+            .unwrap();
+        credential
+    }
+
+    #[test]
+    fn signed_bytes_format() {
+        let credential = Credential {
+            subject_key: vec![8, 1],
+            role: PeerRole::EmergencyServices,
+            expires_at: NOW,
+            issuer_key: Vec::new(),
+            signature: Vec::new(),
+        };
+
+        assert_eq!(
+            credential_signed_bytes(&credential).unwrap(),
+            br#"[[8,1],"EmergencyServices",1735689600]"#
+        );
+    }
+
+    #[test]
+    fn verify_valid() {
+        let issuer = Keypair::generate_ed25519();
+        let subject = Keypair::generate_ed25519();
+        let authorities = Authorities {
+            keys: vec![issuer.public()],
+        };
+
+        let credential = issue(&issuer, &subject, NOW + 60);
+        let role = authorities
+            .verify_credential_at(&credential, &subject.public().to_peer_id(), NOW)
+            .unwrap();
+        assert_eq!(role, PeerRole::EmergencyServices);
+    }
+
+    #[test]
+    fn verify_expired() {
+        let issuer = Keypair::generate_ed25519();
+        let subject = Keypair::generate_ed25519();
+        let authorities = Authorities {
+            keys: vec![issuer.public()],
+        };
+
+        let credential = issue(&issuer, &subject, NOW);
+        assert!(authorities
+            .verify_credential_at(&credential, &subject.public().to_peer_id(), NOW)
+            .is_err());
+    }
+
+    #[test]
+    fn verify_wrong_subject() {
+        let issuer = Keypair::generate_ed25519();
+        let subject = Keypair::generate_ed25519();
+        let authorities = Authorities {
+            keys: vec![issuer.public()],
+        };
+
+        let credential = issue(&issuer, &subject, NOW + 60);
+        assert!(authorities
+            .verify_credential_at(&credential, &PeerId::random(), NOW)
+            .is_err());
+    }
+
+    #[test]
+    fn verify_untrusted_issuer() {
+        let issuer = Keypair::generate_ed25519();
+        let subject = Keypair::generate_ed25519();
+        let authorities = Authorities {
+            keys: vec![Keypair::generate_ed25519().public()],
+        };
+
+        let credential = issue(&issuer, &subject, NOW + 60);
+        assert!(authorities
+            .verify_credential_at(&credential, &subject.public().to_peer_id(), NOW)
+            .is_err());
+    }
+
+    #[test]
+    fn verify_tampered() {
+        let issuer = Keypair::generate_ed25519();
+        let subject = Keypair::generate_ed25519();
+        let authorities = Authorities {
+            keys: vec![issuer.public()],
+        };
+
+        let mut credential = issue(&issuer, &subject, NOW + 60);
+        credential.role = PeerRole::RoadOperator;
+        assert!(authorities
+            .verify_credential_at(&credential, &subject.public().to_peer_id(), NOW)
+            .is_err());
+
+        let mut credential = issue(&issuer, &subject, NOW + 60);
+        credential.expires_at += 60;
+        assert!(authorities
+            .verify_credential_at(&credential, &subject.public().to_peer_id(), NOW)
+            .is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{cell::OnceCell, time::Duration};
+use std::{cell::OnceCell, fmt, time::Duration};
 
 use anyhow::{ensure, Context, Result};
 use gst::prelude::*;
@@ -7,15 +7,31 @@ use gtk::{
     prelude::*,
     subclass::prelude::*,
 };
+use libp2p::request_response;
+use serde::{Deserialize, Serialize};
 
-use crate::{audio_device, input_stream::InputStream, output_stream::OutputStream, peer::Peer};
+use crate::{
+    audio_device, input_stream::InputStream, output_stream::OutputStream, peer::Peer,
+    wireless_info::SignalQuality, Application,
+};
 
 const STREAMSRC_ELEMENT_NAME: &str = "giostreamsrc";
+const JITTERBUFFER_ELEMENT_NAME: &str = "rtpjitterbuffer";
 
 const PULSESRC_ELEMENT_NAME: &str = "pulsesrc";
+pub const OPUSENC_ELEMENT_NAME: &str = "opusenc";
 const STREAMSINK_ELEMENT_NAME: &str = "giostreamsink";
 
+const OPUS_RTP_PAYLOAD_TYPE: u8 = 96;
+
+/// Name shared by the echo probe in the input pipeline and the DSP in the output pipeline.
+const ECHO_PROBE_NAME: &str = "delta-echo-probe";
+
 const DURATION_SECS_NOTIFTY_INTERVAL: Duration = Duration::from_millis(200);
+const STATS_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time the jitter buffer waits for late packets before considering them lost.
+const JITTERBUFFER_LATENCY_MS: u32 = 80;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, glib::Boxed)]
 #[boxed_type(name = "DeltaCallState")]
@@ -28,14 +44,221 @@ pub enum CallState {
     Ended(CallEndReason),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Audio codec used for one direction of a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AudioCodec {
+    /// Low-latency codec, framed as RTP packets on the stream.
+    Opus,
+    /// Fallback for peers without Opus support, muxed in Matroska.
+    Vorbis,
+}
+
+impl AudioCodec {
+    /// Returns the codecs usable with the installed GStreamer plugins, most preferred first.
+    pub fn supported() -> Vec<Self> {
+        let has_opus = [
+            "opusenc",
+            "opusdec",
+            "rtpopuspay",
+            "rtpopusdepay",
+            "rtpstreampay",
+            "rtpstreamdepay",
+            "rtpjitterbuffer",
+        ]
+        .iter()
+        .all(|name| gst::ElementFactory::find(name).is_some());
+
+        if has_opus {
+            vec![Self::Opus, Self::Vorbis]
+        } else {
+            vec![Self::Vorbis]
+        }
+    }
+
+    /// Picks our most preferred codec that is also supported by the peer.
+    ///
+    /// Vorbis is always used as the fallback, as every build supports it.
+    pub fn negotiate(ours: &[Self], theirs: &[Self]) -> Self {
+        ours.iter()
+            .find(|codec| theirs.contains(codec))
+            .copied()
+            .unwrap_or(Self::Vorbis)
+    }
+
+    /// Header byte written by the receiving side when it opens the audio stream.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::Opus => 1,
+            Self::Vorbis => 0,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(Self::Opus),
+            0 => Some(Self::Vorbis),
+            _ => None,
+        }
+    }
+
+    pub fn decoder_pipeline_desc(self) -> String {
+        match self {
+            // The jitter buffer emits lost-packet events, which `opusdec` conceals using FEC
+            // data from the next packet or with PLC if that is missing too
+            Self::Opus => format!(
+                "application/x-rtp-stream,media=audio,clock-rate=48000,encoding-name=OPUS,payload={OPUS_RTP_PAYLOAD_TYPE} ! rtpstreamdepay ! rtpjitterbuffer name={JITTERBUFFER_ELEMENT_NAME} latency={JITTERBUFFER_LATENCY_MS} do-lost=true drop-on-latency=true ! rtpopusdepay ! opusdec plc=true use-inband-fec=true"
+            ),
+            Self::Vorbis => "matroskademux ! vorbisdec".to_string(),
+        }
+    }
+
+    pub fn encoder_pipeline_desc(self) -> String {
+        match self {
+            Self::Opus => format!(
+                "audioresample ! opusenc name={OPUSENC_ELEMENT_NAME} audio-type=voice frame-size=20 inband-fec=true packet-loss-percentage=10 ! rtpopuspay pt={OPUS_RTP_PAYLOAD_TYPE} ! rtpstreampay"
+            ),
+            Self::Vorbis => "vorbisenc ! matroskamux".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AudioCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Opus => write!(f, "Opus"),
+            Self::Vorbis => write!(f, "Vorbis"),
+        }
+    }
+}
+
+/// Audio processing applied to the call and voice channel pipelines, based on the settings.
+#[derive(Debug, Clone, Copy)]
+pub struct AudioProcessing {
+    echo_cancellation: bool,
+    noise_suppression: bool,
+}
+
+impl AudioProcessing {
+    pub fn from_settings() -> Self {
+        let settings = Application::get().settings();
+
+        let this = Self {
+            echo_cancellation: settings.echo_cancellation(),
+            noise_suppression: settings.noise_suppression(),
+        };
+
+        let is_enabled = this.echo_cancellation || this.noise_suppression;
+        let is_available = ["webrtcdsp", "webrtcechoprobe"]
+            .iter()
+            .all(|name| gst::ElementFactory::find(name).is_some());
+
+        if is_enabled && !is_available {
+            tracing::warn!("WebRTC audio processing is not available, disabling it");
+
+            return Self {
+                echo_cancellation: false,
+                noise_suppression: false,
+            };
+        }
+
+        this
+    }
+
+    /// Returns the elements to insert before the playback sink, so the far-end signal can be
+    /// removed from the microphone input.
+    pub fn probe_pipeline_desc(self) -> String {
+        if self.echo_cancellation {
+            format!("audioresample ! webrtcechoprobe name={ECHO_PROBE_NAME} ! audioconvert ! ")
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns the elements to insert after the microphone source.
+    pub fn dsp_pipeline_desc(self) -> String {
+        if self.echo_cancellation || self.noise_suppression {
+            format!(
+                "audioresample ! webrtcdsp probe={ECHO_PROBE_NAME} echo-cancel={} noise-suppression={} ! audioconvert ! ",
+                self.echo_cancellation, self.noise_suppression
+            )
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// Returns the Opus bitrate to use for a link with the given signal quality.
+pub fn opus_bitrate(signal_quality: SignalQuality) -> i32 {
+    match signal_quality {
+        SignalQuality::Excellent => 64_000,
+        SignalQuality::Good => 48_000,
+        SignalQuality::Ok => 32_000,
+        SignalQuality::Weak => 16_000,
+        SignalQuality::None => 8_000,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CallEndReason {
     PeerInAnotherCall,
     PeerRejected,
     PeerMuted,
+    /// The call was not answered before the ringing timeout.
+    NoAnswer,
+    SignallingFailed(CallSignalError),
     Other,
 }
 
+/// Failure to deliver a call signal to the peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CallSignalError {
+    /// The peer did not acknowledge the signal in time.
+    Timeout,
+    /// The peer could not be dialed or the connection closed.
+    Unreachable,
+    /// The peer runs a version without support for call signalling.
+    UnsupportedProtocol,
+    Other,
+}
+
+impl From<&request_response::OutboundFailure> for CallSignalError {
+    fn from(failure: &request_response::OutboundFailure) -> Self {
+        match failure {
+            request_response::OutboundFailure::Timeout => Self::Timeout,
+            request_response::OutboundFailure::DialFailure
+            | request_response::OutboundFailure::ConnectionClosed => Self::Unreachable,
+            request_response::OutboundFailure::UnsupportedProtocols => Self::UnsupportedProtocol,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for CallEndReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PeerInAnotherCall => write!(f, "Peer in another call"),
+            Self::PeerRejected => write!(f, "Rejected by peer"),
+            Self::PeerMuted => write!(f, "Muted by peer"),
+            Self::NoAnswer => write!(f, "No answer"),
+            Self::SignallingFailed(err) => write!(f, "Signalling failed ({})", err),
+            Self::Other => write!(f, "Ended"),
+        }
+    }
+}
+
+impl fmt::Display for CallSignalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "Timed out"),
+            Self::Unreachable => write!(f, "Unreachable"),
+            Self::UnsupportedProtocol => write!(f, "Unsupported protocol"),
+            Self::Other => write!(f, "Other error"),
+        }
+    }
+}
+
+impl std::error::Error for CallSignalError {}
+
 mod imp {
     use std::{
         cell::{Cell, RefCell},
@@ -56,13 +279,36 @@ mod imp {
         pub(super) state: Cell<CallState>,
         #[property(get = Self::duration_secs)]
         pub(super) duration_secs: PhantomData<u64>,
+        /// Round-trip time to the peer, in milliseconds.
+        #[property(get)]
+        pub(super) latency_ms: Cell<u32>,
+        /// Fraction of packets in the last interval that arrived too late to be played and were
+        /// dropped, from 0 to 1.
+        ///
+        /// The stream is reliable, so packets are delayed rather than lost.
+        #[property(get)]
+        pub(super) late_packets: Cell<f64>,
+        /// Received bitrate in bits per second.
+        #[property(get)]
+        pub(super) bitrate: Cell<u64>,
 
         pub(super) ongoing_time: Cell<Option<Instant>>,
+        pub(super) ended_time: Cell<Option<Instant>>,
         pub(super) ongoing_timer_id: RefCell<Option<glib::SourceId>>,
+        pub(super) stats_timer_id: RefCell<Option<glib::SourceId>>,
+
+        pub(super) prev_bytes_read: Cell<u64>,
+        pub(super) prev_packets_pushed: Cell<u64>,
+        pub(super) prev_packets_late: Cell<u64>,
 
         pub(super) input: RefCell<Option<(InputStream, gst::Pipeline, BusWatchGuard)>>,
         pub(super) output: RefCell<Option<(OutputStream, gst::Pipeline, BusWatchGuard)>>,
 
+        pub(super) peer_signal_quality_handler_id: RefCell<Option<glib::SignalHandlerId>>,
+
+        /// Whether the output pipeline is waiting for the echo probe in the input pipeline.
+        pub(super) output_waiting_for_input: Cell<bool>,
+
         pub(super) input_closed: Cell<bool>,
         pub(super) output_closed: Cell<bool>,
     }
@@ -112,13 +358,32 @@ mod imp {
                         ),
                     );
                     self.ongoing_timer_id.replace(Some(source_id));
+
+                    let source_id = glib::timeout_add_local_full(
+                        STATS_UPDATE_INTERVAL,
+                        glib::Priority::LOW,
+                        clone!(
+                            #[weak]
+                            obj,
+                            #[upgrade_or_panic]
+                            move || {
+                                obj.update_stats();
+                                glib::ControlFlow::Continue
+                            }
+                        ),
+                    );
+                    self.stats_timer_id.replace(Some(source_id));
                 }
                 CallState::Ended(_) => {
-                    self.ongoing_time.set(None);
+                    self.ended_time.set(Some(Instant::now()));
 
                     if let Some(source_id) = self.ongoing_timer_id.take() {
                         source_id.remove();
                     }
+
+                    if let Some(source_id) = self.stats_timer_id.take() {
+                        source_id.remove();
+                    }
                 }
                 _ => {}
             }
@@ -128,9 +393,12 @@ mod imp {
         }
 
         fn duration_secs(&self) -> u64 {
+            // Keep the final duration available after the call ended, so it can be recorded
+            let end_time = self.ended_time.get().unwrap_or_else(Instant::now);
+
             self.ongoing_time
                 .get()
-                .map(|start_time| start_time.elapsed().as_secs())
+                .map(|start_time| end_time.duration_since(start_time).as_secs())
                 .unwrap_or(0)
         }
     }
@@ -154,18 +422,34 @@ impl Call {
             tracing::debug!("Sent EOS to input pipeline");
         }
 
-        if let Some((_, ref pipeline, _)) = *imp.output.borrow() {
+        if imp.output_waiting_for_input.replace(false) {
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                async move {
+                    obj.dispose_output().await;
+                }
+            ));
+
+            tracing::debug!("Disposed output pipeline that was not started");
+        } else if let Some((_, ref pipeline, _)) = *imp.output.borrow() {
             pipeline.send_event(gst::event::Eos::new());
 
             tracing::debug!("Sent EOS to output pipeline");
         }
     }
 
-    pub fn set_input_stream(&self, input_stream: InputStream) -> Result<()> {
+    pub fn set_input_stream(&self, input_stream: InputStream, codec: AudioCodec) -> Result<()> {
         let imp = self.imp();
 
+        tracing::debug!("Using {codec} for input stream");
+
+        let audio_processing = AudioProcessing::from_settings();
+
         let pipeline = gst::parse::launch(&format!(
-            "giostreamsrc name={STREAMSRC_ELEMENT_NAME} ! matroskademux ! vorbisdec ! audioconvert ! autoaudiosink",
+            "giostreamsrc name={STREAMSRC_ELEMENT_NAME} ! {} ! audioconvert ! {}autoaudiosink",
+            codec.decoder_pipeline_desc(),
+            audio_processing.probe_pipeline_desc()
         ))?
         .downcast::<gst::Pipeline>()
         .unwrap();
@@ -173,6 +457,11 @@ impl Call {
         let streamsrc = pipeline.by_name(STREAMSRC_ELEMENT_NAME).unwrap();
         streamsrc.set_property("stream", &input_stream);
 
+        // The jitter buffer needs the arrival time of each packet
+        if codec == AudioCodec::Opus {
+            streamsrc.set_property("do-timestamp", true);
+        }
+
         let bus_watch_guard = pipeline
             .bus()
             .unwrap()
@@ -191,14 +480,30 @@ impl Call {
             .replace(Some((input_stream, pipeline, bus_watch_guard)));
         debug_assert!(prev_input.is_none());
 
+        if imp.output_waiting_for_input.get() {
+            if let Some((_, ref pipeline, _)) = *imp.output.borrow() {
+                pipeline.set_state(gst::State::Playing)?;
+
+                tracing::debug!("Started output pipeline after the echo probe became available");
+            }
+
+            imp.output_waiting_for_input.set(false);
+        }
+
         Ok(())
     }
 
-    pub fn set_output_stream(&self, output_stream: OutputStream) -> Result<()> {
+    pub fn set_output_stream(&self, output_stream: OutputStream, codec: AudioCodec) -> Result<()> {
         let imp = self.imp();
 
+        tracing::debug!("Using {codec} for output stream");
+
+        let audio_processing = AudioProcessing::from_settings();
+
         let pipeline = gst::parse::launch(&format!(
-            "pulsesrc name={PULSESRC_ELEMENT_NAME} ! audioconvert ! vorbisenc ! matroskamux ! giostreamsink name={STREAMSINK_ELEMENT_NAME}",
+            "pulsesrc name={PULSESRC_ELEMENT_NAME} ! audioconvert ! {}{} ! giostreamsink name={STREAMSINK_ELEMENT_NAME}",
+            audio_processing.dsp_pipeline_desc(),
+            codec.encoder_pipeline_desc()
         ))?
         .downcast::<gst::Pipeline>()
         .unwrap();
@@ -217,6 +522,20 @@ impl Call {
         let streamsink = pipeline.by_name(STREAMSINK_ELEMENT_NAME).unwrap();
         streamsink.set_property("stream", &output_stream);
 
+        if let Some(opusenc) = pipeline.by_name(OPUSENC_ELEMENT_NAME) {
+            let peer = self.peer();
+
+            opusenc.set_property("bitrate", opus_bitrate(peer.signal_quality()));
+
+            let handler_id = peer.connect_signal_quality_notify(move |peer| {
+                let bitrate = opus_bitrate(peer.signal_quality());
+                opusenc.set_property("bitrate", bitrate);
+
+                tracing::debug!("Adapted output bitrate to {bitrate} bps");
+            });
+            imp.peer_signal_quality_handler_id.replace(Some(handler_id));
+        }
+
         let bus_watch_guard = pipeline
             .bus()
             .unwrap()
@@ -227,7 +546,15 @@ impl Call {
                 move |_, message| obj.handle_output_bus_message(message)
             ))
             .unwrap();
-        pipeline.set_state(gst::State::Playing)?;
+
+        // The DSP looks up the echo probe when it starts, so wait for the input pipeline if needed
+        if audio_processing.echo_cancellation && imp.input.borrow().is_none() {
+            imp.output_waiting_for_input.set(true);
+
+            tracing::debug!("Delaying output pipeline until input pipeline is set");
+        } else {
+            pipeline.set_state(gst::State::Playing)?;
+        }
 
         let prev_output = imp
             .output
@@ -237,6 +564,69 @@ impl Call {
         Ok(())
     }
 
+    pub fn set_latency_ms(&self, latency_ms: u32) {
+        let imp = self.imp();
+
+        if latency_ms == self.latency_ms() {
+            return;
+        }
+
+        imp.latency_ms.set(latency_ms);
+        self.notify_latency_ms();
+    }
+
+    fn set_late_packets(&self, late_packets: f64) {
+        let imp = self.imp();
+
+        if late_packets == self.late_packets() {
+            return;
+        }
+
+        imp.late_packets.set(late_packets);
+        self.notify_late_packets();
+    }
+
+    fn set_bitrate(&self, bitrate: u64) {
+        let imp = self.imp();
+
+        if bitrate == self.bitrate() {
+            return;
+        }
+
+        imp.bitrate.set(bitrate);
+        self.notify_bitrate();
+    }
+
+    fn update_stats(&self) {
+        let imp = self.imp();
+
+        let Some((ref input_stream, ref pipeline, _)) = *imp.input.borrow() else {
+            return;
+        };
+
+        let bytes_read = input_stream.bytes_read();
+        let prev_bytes_read = imp.prev_bytes_read.replace(bytes_read);
+        self.set_bitrate(
+            (bytes_read - prev_bytes_read) * 8 / STATS_UPDATE_INTERVAL.as_secs().max(1),
+        );
+
+        if let Some(jitterbuffer) = pipeline.by_name(JITTERBUFFER_ELEMENT_NAME) {
+            let stats = jitterbuffer.property::<gst::Structure>("stats");
+            let packets_pushed = stats.get::<u64>("num-pushed").unwrap_or(0);
+            let packets_late = stats.get::<u64>("num-late").unwrap_or(0);
+
+            let pushed = packets_pushed - imp.prev_packets_pushed.replace(packets_pushed);
+            let late = packets_late - imp.prev_packets_late.replace(packets_late);
+
+            let late_packets = if pushed + late > 0 {
+                late as f64 / (pushed + late) as f64
+            } else {
+                0.0
+            };
+            self.set_late_packets(late_packets);
+        }
+    }
+
     fn handle_input_bus_message(&self, message: &gst::Message) -> glib::ControlFlow {
         match message.view() {
             gst::MessageView::Eos(..) => {
@@ -324,6 +714,10 @@ impl Call {
 
         let (output_stream, pipeline, _bus_watch_guard) = imp.output.take().unwrap();
 
+        if let Some(handler_id) = imp.peer_signal_quality_handler_id.take() {
+            self.peer().disconnect(handler_id);
+        }
+
         if let Err(err) = output_stream.close_future(glib::Priority::LOW).await {
             tracing::error!("Failed to close output stream: {:?}", err);
         }
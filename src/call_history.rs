@@ -0,0 +1,291 @@
+use std::fmt;
+
+use anyhow::Result;
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use libp2p::PeerId;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{call::CallEndReason, config, utils};
+
+static CALL_HISTORY_FILE: Lazy<gio::File> = Lazy::new(|| {
+    let mut path = config::user_config_dir();
+    path.push("call_history.json");
+    gio::File::for_path(path)
+});
+
+const CSV_HEADER: &str = "start_time,peer_id,peer_name,direction,duration_secs,end_reason";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, glib::Enum)]
+#[enum_type(name = "DeltaCallDirection")]
+pub enum CallDirection {
+    #[default]
+    Incoming,
+    Outgoing,
+}
+
+impl fmt::Display for CallDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallDirection::Incoming => write!(f, "Incoming"),
+            CallDirection::Outgoing => write!(f, "Outgoing"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CallRecordData {
+    peer_id: PeerId,
+    peer_name: String,
+    direction: CallDirection,
+    /// Time the call started ringing, in seconds since the Unix epoch.
+    start_timestamp: i64,
+    duration_secs: u64,
+    end_reason: CallEndReason,
+}
+
+mod call_record_imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::CallRecord)]
+    pub struct CallRecord {
+        pub(super) peer_id: OnceCell<PeerId>,
+        pub(super) end_reason: OnceCell<CallEndReason>,
+
+        #[property(get, set, construct_only)]
+        pub(super) peer_name: RefCell<String>,
+        #[property(get, set, construct_only, builder(CallDirection::default()))]
+        pub(super) direction: Cell<CallDirection>,
+        #[property(get, set, construct_only)]
+        pub(super) start_timestamp: Cell<i64>,
+        #[property(get, set, construct_only)]
+        pub(super) duration_secs: Cell<u64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CallRecord {
+        const NAME: &'static str = "DeltaCallRecord";
+        type Type = super::CallRecord;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for CallRecord {}
+}
+
+glib::wrapper! {
+    pub struct CallRecord(ObjectSubclass<call_record_imp::CallRecord>);
+}
+
+impl CallRecord {
+    pub fn new(
+        peer_id: PeerId,
+        peer_name: &str,
+        direction: CallDirection,
+        start_timestamp: i64,
+        duration_secs: u64,
+        end_reason: CallEndReason,
+    ) -> Self {
+        let this = glib::Object::builder::<Self>()
+            .property("peer-name", peer_name)
+            .property("direction", direction)
+            .property("start-timestamp", start_timestamp)
+            .property("duration-secs", duration_secs)
+            .build();
+
+        let imp = this.imp();
+        imp.peer_id.set(peer_id).unwrap();
+        imp.end_reason.set(end_reason).unwrap();
+
+        this
+    }
+
+    pub fn peer_id(&self) -> &PeerId {
+        self.imp().peer_id.get().unwrap()
+    }
+
+    pub fn end_reason(&self) -> CallEndReason {
+        *self.imp().end_reason.get().unwrap()
+    }
+
+    pub fn start_date_time(&self) -> glib::DateTime {
+        glib::DateTime::from_unix_local(self.start_timestamp()).unwrap()
+    }
+
+    fn to_data(&self) -> CallRecordData {
+        CallRecordData {
+            peer_id: *self.peer_id(),
+            peer_name: self.peer_name(),
+            direction: self.direction(),
+            start_timestamp: self.start_timestamp(),
+            duration_secs: self.duration_secs(),
+            end_reason: self.end_reason(),
+        }
+    }
+
+    fn to_csv_record(&self) -> String {
+        let start_time = glib::DateTime::from_unix_utc(self.start_timestamp())
+            .and_then(|date_time| date_time.format_iso8601())
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+
+        [
+            start_time,
+            self.peer_id().to_string(),
+            self.peer_name(),
+            self.direction().to_string(),
+            self.duration_secs().to_string(),
+            self.end_reason().to_string(),
+        ]
+        .iter()
+        .map(|field| utils::csv_escape(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct CallHistory {
+        pub(super) list: RefCell<Vec<CallRecord>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CallHistory {
+        const NAME: &'static str = "DeltaCallHistory";
+        type Type = super::CallHistory;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    impl ObjectImpl for CallHistory {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            if let Err(err) = obj.load() {
+                tracing::error!("Failed to load call history: {:?}", err);
+            }
+        }
+    }
+
+    impl ListModelImpl for CallHistory {
+        fn item_type(&self) -> glib::Type {
+            CallRecord::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.list.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.list
+                .borrow()
+                .get(position as usize)
+                .map(|v| v.upcast_ref::<glib::Object>())
+                .cloned()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Persisted log of past calls, newest first.
+    pub struct CallHistory(ObjectSubclass<imp::CallHistory>)
+        @implements gio::ListModel;
+}
+
+impl CallHistory {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn insert(&self, record: CallRecord) {
+        self.imp().list.borrow_mut().insert(0, record);
+        self.items_changed(0, 0, 1);
+
+        self.save_and_log();
+    }
+
+    pub fn clear(&self) {
+        let n_removed = self.imp().list.take().len();
+
+        if n_removed > 0 {
+            self.items_changed(0, n_removed as u32, 0);
+
+            self.save_and_log();
+        }
+    }
+
+    /// Returns the history as CSV, oldest call first.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+
+        for record in self.imp().list.borrow().iter().rev() {
+            csv.push_str(&record.to_csv_record());
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    fn save_and_log(&self) {
+        if let Err(err) = self.save() {
+            tracing::error!("Failed to save call history: {:?}", err);
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let data = self
+            .imp()
+            .list
+            .borrow()
+            .iter()
+            .map(|record| record.to_data())
+            .collect::<Vec<_>>();
+        utils::save_json(&CALL_HISTORY_FILE, &data)
+    }
+
+    fn load(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let data = utils::load_json::<Vec<CallRecordData>>(&CALL_HISTORY_FILE)?.unwrap_or_default();
+
+        tracing::debug!(
+            "Loaded {} call records from {}",
+            data.len(),
+            CALL_HISTORY_FILE.path().unwrap().display()
+        );
+
+        let prev_len = imp.list.borrow().len();
+        imp.list.replace(
+            data.into_iter()
+                .map(|d| {
+                    CallRecord::new(
+                        d.peer_id,
+                        &d.peer_name,
+                        d.direction,
+                        d.start_timestamp,
+                        d.duration_secs,
+                        d.end_reason,
+                    )
+                })
+                .collect(),
+        );
+        self.items_changed(0, prev_len as u32, self.n_items());
+
+        Ok(())
+    }
+}
+
+impl Default for CallHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
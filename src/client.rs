@@ -1,58 +1,81 @@
-use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use anyhow::{anyhow, ensure, Context, Result};
 use futures_channel::oneshot;
-use futures_util::{select, FutureExt, StreamExt};
+use futures_util::{future, select, AsyncReadExt, AsyncWriteExt, FutureExt, StreamExt};
 use gtk::{
     glib::{self, clone, closure_local},
     prelude::*,
     subclass::prelude::*,
 };
 use libp2p::{
-    gossipsub, mdns,
+    gossipsub, mdns, ping,
+    request_response::{self, ProtocolSupport},
     swarm::{NetworkBehaviour, SwarmEvent},
     PeerId, StreamProtocol, Swarm, SwarmBuilder,
 };
 use libp2p_stream as stream;
-use serde::{Deserialize, Serialize};
 
 use crate::{
-    call::{Call, CallEndReason, CallState},
+    alert::{Alert, AlertResponse, AlertResponseKind, AlertType},
+    alert_handler::{AlertHandler, AlertResponseOutcome, AlertValidation},
+    alert_rate_limiter::AUTO_MUTE_DURATION,
+    call::{AudioCodec, Call, CallEndReason, CallSignalError, CallState},
+    call_history::{CallDirection, CallRecord},
+    collision::CollisionMonitor,
     config,
+    emergency_approach::{ApproachDirection, EmergencyApproachTracker},
+    gps::{UNKNOWN, UNKNOWN_ALTITUDE},
     input_stream::InputStream,
-    location::Location,
+    missed_call_list::MissedCall,
     output_stream::OutputStream,
     peer::Peer,
     peer_list::PeerList,
-    wireless_info::SignalQuality,
+    protocol::{
+        AlertInfo, CallRequestRejectReason, CallRequestResponse, CallSignal, CallSignalAck,
+        Encoding, Envelope, MessageId, Property, PublishData, TextMessageReceipt,
+        TextMessageRequest, VoiceMessageAck, VoiceMessageInfo, VoiceMessageRequest,
+        PROTOCOL_VERSION,
+    },
+    push_to_talk::{PushToTalk, PUSH_TO_TALK_CODEC},
+    text_message::{Conversation, TextMessage},
+    voice_channel::{VoiceChannel, CHANNEL_CODEC},
+    voice_message::{self, VoiceMessage},
     Application,
 };
 
 const PUBLISH_PROPERTIES_INTERVAL: Duration = Duration::from_secs(3);
 
-const AUDIO_STREAM_PROTOCOL: StreamProtocol = StreamProtocol::new("/audio");
+const AUDIO_STREAM_PROTOCOL: StreamProtocol = StreamProtocol::new("/audio/2");
+const CHANNEL_AUDIO_STREAM_PROTOCOL: StreamProtocol = StreamProtocol::new("/channel-audio");
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, glib::Enum)]
-#[enum_type(name = "DeltaAlertType")]
-pub enum AlertType {
-    Sos,
-    Hazard,
-    Yielding,
-}
+/// Unlike the other audio protocols, the peer opening the stream is the one sending audio.
+const PUSH_TO_TALK_STREAM_PROTOCOL: StreamProtocol = StreamProtocol::new("/push-to-talk");
 
-impl AlertType {
-    pub fn blink_count(self) -> u32 {
-        match self {
-            AlertType::Sos => 10,
-            AlertType::Hazard => 5,
-            AlertType::Yielding => 3,
-        }
-    }
-}
+const CALL_SIGNAL_PROTOCOL: StreamProtocol = StreamProtocol::new("/call-signal");
+const CALL_SIGNAL_TIMEOUT: Duration = Duration::from_secs(5);
+
+const TEXT_MESSAGE_PROTOCOL: StreamProtocol = StreamProtocol::new("/text-message");
+const TEXT_MESSAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const VOICE_MESSAGE_PROTOCOL: StreamProtocol = StreamProtocol::new("/voice-message");
+const VOICE_MESSAGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval between attempts to deliver pending voice messages to nearby peers.
+const VOICE_MESSAGE_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Interval between location updates sent to the sender of an alert we are responding to.
+const ALERT_RESPONSE_UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Interval between round-trip time measurements, shown as the call latency.
+const PING_INTERVAL: Duration = Duration::from_secs(2);
 
 mod imp {
     use std::{
-        cell::{OnceCell, RefCell},
+        cell::{Cell, OnceCell, RefCell},
         sync::OnceLock,
     };
 
@@ -65,13 +88,33 @@ mod imp {
     pub struct Client {
         #[property(get)]
         pub(super) active_call: RefCell<Option<Call>>,
+        #[property(get)]
+        pub(super) active_voice_channel: RefCell<Option<VoiceChannel>>,
+        /// Whether we are an emergency vehicle on the way to an emergency, so nearby vehicles
+        /// are asked to make way.
+        #[property(get, set)]
+        pub(super) is_emergency_en_route: Cell<bool>,
 
         pub(super) command_tx: OnceCell<async_channel::Sender<Command>>,
+        pub(super) stream_control: OnceCell<stream::Control>,
         pub(super) call_incoming_response_tx:
             RefCell<Option<oneshot::Sender<CallIncomingResponse>>>,
         pub(super) call_incoming_cancel_tx: RefCell<Option<oneshot::Sender<()>>>,
+        pub(super) text_message_requests:
+            RefCell<HashMap<request_response::OutboundRequestId, TextMessage>>,
+        /// Voice messages being sent, to avoid sending the same one twice to a peer.
+        pub(super) voice_message_requests:
+            RefCell<HashMap<request_response::OutboundRequestId, (MessageId, PeerId)>>,
+        /// Protocol versions of peers we can't talk to, so the mismatch is only logged once.
+        pub(super) unsupported_protocol_versions: RefCell<HashSet<u16>>,
+        pub(super) alert_handler: RefCell<AlertHandler>,
+        pub(super) emergency_approach_tracker: RefCell<EmergencyApproachTracker>,
+        pub(super) collision_monitor: RefCell<CollisionMonitor>,
 
         pub(super) peer_list: PeerList,
+        pub(super) push_to_talk: PushToTalk,
+        pub(super) conversations: RefCell<HashMap<PeerId, Conversation>>,
+        pub(super) broadcast_conversation: Conversation,
     }
 
     #[glib::object_subclass]
@@ -104,6 +147,10 @@ mod imp {
                 #[weak]
                 obj,
                 move |gps| {
+                    for peer in obj.peer_list().iter::<Peer>() {
+                        obj.check_collision(&peer.unwrap());
+                    }
+
                     let location = gps.location();
                     glib::spawn_future_local(async move {
                         obj.publish(PublishData::PropertyChanged(vec![Property::Location(
@@ -125,6 +172,20 @@ mod imp {
                 }
             ));
 
+            gps.connect_heading_notify(clone!(
+                #[weak]
+                obj,
+                move |gps| {
+                    let heading = gps.known_heading();
+                    glib::spawn_future_local(async move {
+                        obj.publish(PublishData::PropertyChanged(vec![Property::Heading(
+                            heading,
+                        )]))
+                        .await;
+                    });
+                }
+            ));
+
             app.settings().connect_icon_name_notify(clone!(
                 #[weak]
                 obj,
@@ -139,6 +200,32 @@ mod imp {
                 }
             ));
 
+            obj.connect_is_emergency_en_route_notify(|obj| {
+                let is_emergency_en_route = obj.is_emergency_en_route();
+                glib::spawn_future_local(clone!(
+                    #[weak]
+                    obj,
+                    async move {
+                        obj.publish(PublishData::PropertyChanged(vec![
+                            Property::EmergencyEnRoute(is_emergency_en_route),
+                        ]))
+                        .await;
+                    }
+                ));
+            });
+
+            self.push_to_talk.connect_is_talking_notify(clone!(
+                #[weak]
+                obj,
+                move |push_to_talk| {
+                    if !push_to_talk.is_talking() {
+                        glib::spawn_future_local(async move {
+                            obj.publish(PublishData::TalkEnded).await;
+                        });
+                    }
+                }
+            ));
+
             app.wireless_info().connect_signal_quality_notify(clone!(
                 #[weak]
                 obj,
@@ -158,9 +245,37 @@ mod imp {
             static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
 
             SIGNALS.get_or_init(|| {
-                vec![Signal::builder("alert-received")
-                    .param_types([Peer::static_type(), AlertType::static_type()])
-                    .build()]
+                vec![
+                    Signal::builder("alert-received")
+                        .param_types([Alert::static_type()])
+                        .build(),
+                    Signal::builder("alert-response-received")
+                        .param_types([Alert::static_type(), AlertResponse::static_type()])
+                        .build(),
+                    Signal::builder("alert-sender-auto-muted")
+                        .param_types([String::static_type(), u64::static_type()])
+                        .build(),
+                    Signal::builder("emergency-vehicle-approaching")
+                        .param_types([
+                            Peer::static_type(),
+                            ApproachDirection::static_type(),
+                            f64::static_type(),
+                            u64::static_type(),
+                        ])
+                        .build(),
+                    Signal::builder("collision-warning")
+                        .param_types([Peer::static_type(), f64::static_type()])
+                        .build(),
+                    Signal::builder("call-missed")
+                        .param_types([MissedCall::static_type()])
+                        .build(),
+                    Signal::builder("text-message-received")
+                        .param_types([Peer::static_type(), TextMessage::static_type()])
+                        .build(),
+                    Signal::builder("voice-message-received")
+                        .param_types([VoiceMessage::static_type()])
+                        .build(),
+                ]
             })
         }
     }
@@ -177,29 +292,274 @@ impl Client {
 
     pub fn connect_alert_received<F>(&self, f: F) -> glib::SignalHandlerId
     where
-        F: Fn(&Self, &Peer, AlertType) + 'static,
+        F: Fn(&Self, &Alert) + 'static,
     {
         self.connect_closure(
             "alert-received",
             false,
-            closure_local!(|obj: &Self, peer: &Peer, alert_type: AlertType| f(
-                obj, peer, alert_type
+            closure_local!(|obj: &Self, alert: &Alert| f(obj, alert)),
+        )
+    }
+
+    pub fn connect_alert_response_received<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &Alert, &AlertResponse) + 'static,
+    {
+        self.connect_closure(
+            "alert-response-received",
+            false,
+            closure_local!(|obj: &Self, alert: &Alert, response: &AlertResponse| f(
+                obj, alert, response
+            )),
+        )
+    }
+
+    /// Emitted with the sender name and mute duration in seconds when a peer is muted for
+    /// sending too many alerts.
+    pub fn connect_alert_sender_auto_muted<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &str, u64) + 'static,
+    {
+        self.connect_closure(
+            "alert-sender-auto-muted",
+            false,
+            closure_local!(|obj: &Self, sender_name: String, duration_secs: u64| f(
+                obj,
+                &sender_name,
+                duration_secs
+            )),
+        )
+    }
+
+    /// Emitted with the direction, distance in meters and estimated arrival time in seconds
+    /// when an emergency vehicle en route is approaching us.
+    pub fn connect_emergency_vehicle_approaching<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &Peer, ApproachDirection, f64, u64) + 'static,
+    {
+        self.connect_closure(
+            "emergency-vehicle-approaching",
+            false,
+            closure_local!(|obj: &Self,
+                            peer: &Peer,
+                            direction: ApproachDirection,
+                            distance: f64,
+                            eta_secs: u64| f(
+                obj, peer, direction, distance, eta_secs
             )),
         )
     }
 
+    /// Emitted with the predicted time-to-collision in seconds when we are about to collide
+    /// with a peer.
+    pub fn connect_collision_warning<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &Peer, f64) + 'static,
+    {
+        self.connect_closure(
+            "collision-warning",
+            false,
+            closure_local!(|obj: &Self, peer: &Peer, ttc_secs: f64| f(obj, peer, ttc_secs)),
+        )
+    }
+
+    pub fn connect_call_missed<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &MissedCall) + 'static,
+    {
+        self.connect_closure(
+            "call-missed",
+            false,
+            closure_local!(|obj: &Self, missed_call: &MissedCall| f(obj, missed_call)),
+        )
+    }
+
+    pub fn connect_text_message_received<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &Peer, &TextMessage) + 'static,
+    {
+        self.connect_closure(
+            "text-message-received",
+            false,
+            closure_local!(|obj: &Self, peer: &Peer, message: &TextMessage| f(obj, peer, message)),
+        )
+    }
+
+    pub fn connect_voice_message_received<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &VoiceMessage) + 'static,
+    {
+        self.connect_closure(
+            "voice-message-received",
+            false,
+            closure_local!(|obj: &Self, message: &VoiceMessage| f(obj, message)),
+        )
+    }
+
     pub fn peer_list(&self) -> &PeerList {
         &self.imp().peer_list
     }
 
-    pub async fn publish_alert(&self, alert_type: AlertType) {
-        self.publish(PublishData::Alert(alert_type)).await;
+    pub fn push_to_talk(&self) -> &PushToTalk {
+        &self.imp().push_to_talk
+    }
+
+    /// Returns the messages exchanged with the peer.
+    pub fn conversation(&self, peer_id: &PeerId) -> Conversation {
+        self.imp()
+            .conversations
+            .borrow_mut()
+            .entry(*peer_id)
+            .or_default()
+            .clone()
+    }
+
+    /// Returns the messages we sent to all nearby peers.
+    pub fn broadcast_conversation(&self) -> &Conversation {
+        &self.imp().broadcast_conversation
+    }
+
+    pub async fn send_text_message(&self, peer_id: PeerId, text: &str) -> Result<TextMessage> {
+        ensure!(
+            self.peer_list().get(&peer_id).is_some(),
+            "Peer is not nearby"
+        );
+
+        let message = TextMessage::new_outgoing(MessageId::new_random(), text, false, 1);
+        self.conversation(&peer_id).push(message.clone());
+
+        self.send_text_message_request(peer_id, &message).await;
+
+        Ok(message)
+    }
+
+    /// Sends the text to all nearby allowed peers, each acknowledging it separately.
+    pub async fn broadcast_text_message(&self, text: &str) -> Result<TextMessage> {
+        let settings = Application::get().settings();
+        let peer_ids = self
+            .peer_list()
+            .iter::<Peer>()
+            .map(|peer| peer.unwrap())
+            .filter(|peer| settings.is_allowed_peer(peer.id(), &peer.name()))
+            .map(|peer| *peer.id())
+            .collect::<Vec<_>>();
+        ensure!(!peer_ids.is_empty(), "No nearby peers to message");
+
+        let message =
+            TextMessage::new_outgoing(MessageId::new_random(), text, true, peer_ids.len() as u32);
+        self.broadcast_conversation().push(message.clone());
+
+        for peer_id in peer_ids {
+            self.conversation(&peer_id).push(message.clone());
+
+            self.send_text_message_request(peer_id, &message).await;
+        }
+
+        Ok(message)
+    }
+
+    async fn send_text_message_request(&self, peer_id: PeerId, message: &TextMessage) {
+        tracing::debug!("Sending text message {} to {peer_id}", message.id());
+
+        self.send_command(Command::SendTextMessage {
+            peer_id,
+            request: TextMessageRequest {
+                id: message.id(),
+                text: message.text(),
+                is_broadcast: message.is_broadcast(),
+            },
+            message: message.clone(),
+        })
+        .await;
+    }
+
+    /// Publishes an alert about a hazard at our current location.
+    pub async fn publish_alert(&self, alert_type: AlertType, description: Option<&str>) -> Alert {
+        let (alert, info) = self
+            .imp()
+            .alert_handler
+            .borrow_mut()
+            .create_alert(alert_type, description);
+
+        self.publish(PublishData::Alert(info)).await;
+
+        alert
+    }
+
+    /// Lets the sender of the alert know that we saw it or are heading to help.
+    ///
+    /// While responding, our location is sent again periodically so the sender can follow
+    /// our approach, until the alert expires or we respond otherwise.
+    pub async fn respond_to_alert(&self, alert: &Alert, kind: AlertResponseKind) {
+        if alert.our_response() == Some(kind) {
+            return;
+        }
+
+        alert.set_our_response(kind);
+        Application::get()
+            .alert_log()
+            .record_our_response(alert.id(), kind);
+
+        self.publish_alert_response(alert, kind).await;
+
+        // We may have responded otherwise while the response was being sent
+        if kind == AlertResponseKind::Responding
+            && alert.our_response() == Some(kind)
+            && !alert.is_expired()
+        {
+            let source_id = glib::timeout_add_local(
+                ALERT_RESPONSE_UPDATE_INTERVAL,
+                clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    #[weak]
+                    alert,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || {
+                        glib::spawn_future_local(clone!(
+                            #[weak]
+                            obj,
+                            #[weak]
+                            alert,
+                            async move {
+                                obj.publish_alert_response(&alert, AlertResponseKind::Responding)
+                                    .await;
+                            }
+                        ));
+
+                        glib::ControlFlow::Continue
+                    }
+                ),
+            );
+            alert.set_response_update_source_id(source_id);
+        }
+    }
+
+    async fn publish_alert_response(&self, alert: &Alert, kind: AlertResponseKind) {
+        let info = self
+            .imp()
+            .alert_handler
+            .borrow_mut()
+            .create_response(alert, kind);
+
+        self.publish(PublishData::AlertResponse(info)).await;
     }
 
     pub async fn call_request(&self, destination: PeerId) -> Result<()> {
         ensure!(self.active_call().is_none(), "Already in a call");
+        ensure!(
+            self.active_voice_channel().is_none(),
+            "Already in a voice channel"
+        );
 
-        self.publish(PublishData::CallRequest { destination }).await;
+        self.send_call_signal(
+            destination,
+            CallSignal::Request {
+                codecs: AudioCodec::supported(),
+            },
+        )
+        .await;
 
         let destination_peer = self.peer_list().get(&destination).unwrap();
         let call = Call::new(&destination_peer);
@@ -207,6 +567,29 @@ impl Client {
 
         self.set_active_call(Some(call.clone()));
 
+        glib::timeout_add_local_once(
+            call_ringing_timeout(),
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[weak]
+                call,
+                move || {
+                    if call.state() != CallState::Outgoing {
+                        return;
+                    }
+
+                    tracing::debug!("Outgoing call to {destination} was not answered in time");
+
+                    call.set_state(CallState::Ended(CallEndReason::NoAnswer));
+
+                    glib::spawn_future_local(async move {
+                        obj.send_call_signal(destination, CallSignal::Cancel).await;
+                    });
+                }
+            ),
+        );
+
         Ok(())
     }
 
@@ -240,10 +623,8 @@ impl Client {
 
         active_call.set_state(CallState::Ended(CallEndReason::Other));
 
-        self.publish(PublishData::CallRequestCancel {
-            destination: *active_call.peer().id(),
-        })
-        .await;
+        self.send_call_signal(*active_call.peer().id(), CallSignal::Cancel)
+            .await;
 
         Ok(())
     }
@@ -259,13 +640,359 @@ impl Client {
         Ok(())
     }
 
+    /// Takes the floor and broadcasts our microphone to all nearby allowed peers.
+    pub async fn push_to_talk_start(&self) -> Result<()> {
+        ensure!(
+            self.active_call().is_none() && self.active_voice_channel().is_none(),
+            "Microphone is in use by a call"
+        );
+
+        let settings = Application::get().settings();
+        let peer_ids = self
+            .peer_list()
+            .iter::<Peer>()
+            .map(|peer| peer.unwrap())
+            .filter(|peer| settings.is_allowed_peer(peer.id(), &peer.name()))
+            .map(|peer| *peer.id())
+            .collect::<Vec<_>>();
+        ensure!(!peer_ids.is_empty(), "No nearby peers to talk to");
+
+        let push_to_talk = self.push_to_talk();
+        push_to_talk.start_talking()?;
+
+        self.publish(PublishData::TalkStarted).await;
+
+        let stream_control = self.imp().stream_control.get().unwrap();
+        let streams = future::join_all(peer_ids.into_iter().map(|peer_id| {
+            let mut stream_control = stream_control.clone();
+            async move {
+                let res = open_audio_stream(
+                    &mut stream_control,
+                    peer_id,
+                    PUSH_TO_TALK_STREAM_PROTOCOL,
+                    PUSH_TO_TALK_CODEC,
+                )
+                .await;
+
+                if let Err(ref err) = res {
+                    tracing::warn!("Failed to open push-to-talk stream to {peer_id}: {:?}", err);
+                }
+
+                res.ok()
+            }
+        }))
+        .await;
+
+        let output_streams = streams
+            .into_iter()
+            .flatten()
+            .map(OutputStream::new)
+            .collect::<Vec<_>>();
+
+        if let Err(err) = push_to_talk.set_output_streams(output_streams) {
+            push_to_talk.stop_talking();
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    pub fn push_to_talk_stop(&self) {
+        self.push_to_talk().stop_talking();
+    }
+
+    /// Queues a voice message for the recipient and sends it to every nearby peer, either
+    /// directly or to be relayed.
+    ///
+    /// The message is kept until it is delivered, so it survives restarts.
+    pub async fn send_voice_message(&self, recipient: PeerId, audio: Vec<u8>) -> Result<()> {
+        let app = Application::get();
+
+        let mut info = VoiceMessageInfo {
+            id: MessageId::new_random(),
+            sender: app.keypair().public().to_peer_id(),
+            sender_name: config::name(),
+            recipient,
+            timestamp: glib::DateTime::now_utc().unwrap().to_unix(),
+            hops: 0,
+            sender_key: Vec::new(),
+            signature: Vec::new(),
+        };
+        info.sign(&app.keypair(), &audio)?;
+
+        tracing::debug!("Queued voice message {} to {recipient}", info.id);
+
+        app.voice_message_outbox().insert(info, &audio)?;
+
+        self.deliver_all_voice_messages().await;
+
+        Ok(())
+    }
+
+    async fn deliver_all_voice_messages(&self) {
+        let peer_ids = self
+            .peer_list()
+            .iter::<Peer>()
+            .map(|peer| *peer.unwrap().id())
+            .collect::<Vec<_>>();
+
+        for peer_id in peer_ids {
+            self.deliver_voice_messages(peer_id).await;
+        }
+    }
+
+    /// Sends the pending voice messages addressed to the peer or that it can relay.
+    async fn deliver_voice_messages(&self, peer_id: PeerId) {
+        let imp = self.imp();
+
+        for info in Application::get().voice_message_outbox().to_send(&peer_id) {
+            let is_in_flight = imp
+                .voice_message_requests
+                .borrow()
+                .values()
+                .any(|entry| *entry == (info.id, peer_id));
+            if is_in_flight {
+                continue;
+            }
+
+            let audio = match voice_message::load_audio(info.id) {
+                Ok(audio) => audio,
+                Err(err) => {
+                    tracing::error!("Failed to load voice message audio: {:?}", err);
+                    continue;
+                }
+            };
+
+            tracing::debug!("Sending voice message {} to {peer_id}", info.id);
+
+            self.send_command(Command::SendVoiceMessage {
+                peer_id,
+                request: VoiceMessageRequest { info, audio },
+            })
+            .await;
+        }
+    }
+
+    /// Handles a voice message sent to us or for us to relay, returning the ack to send.
+    fn handle_voice_message_request(
+        &self,
+        local_peer_id: PeerId,
+        request: VoiceMessageRequest,
+    ) -> Result<VoiceMessageAck> {
+        let VoiceMessageRequest { info, audio } = request;
+
+        // Both the recipient and relays check it, so a forged message is dropped early
+        if let Err(err) = info.verify(&audio) {
+            tracing::warn!(
+                "Rejected voice message {} claiming to be from {}: {:?}",
+                info.id,
+                info.sender,
+                err
+            );
+
+            return Ok(VoiceMessageAck::Rejected);
+        }
+
+        let app = Application::get();
+
+        if info.recipient == local_peer_id {
+            if !app
+                .settings()
+                .is_allowed_peer(&info.sender, &info.sender_name)
+            {
+                tracing::debug!("Rejected voice message from muted peer");
+
+                return Ok(VoiceMessageAck::Rejected);
+            }
+
+            let inbox = app.voice_message_inbox();
+            if !inbox.contains(info.id) {
+                voice_message::save_audio(info.id, &audio)?;
+
+                let message = VoiceMessage::new(info);
+                inbox.insert(message.clone());
+
+                self.emit_by_name::<()>("voice-message-received", &[&message]);
+            }
+
+            return Ok(VoiceMessageAck::Delivered);
+        }
+
+        let outbox = app.voice_message_outbox();
+        if info.sender != local_peer_id && !outbox.contains(info.id) {
+            let recipient = info.recipient;
+
+            let id = info.id;
+
+            tracing::debug!("Storing voice message {id} to relay to {recipient}");
+
+            if !outbox.insert_relayed(info, &audio)? {
+                tracing::debug!("Not relaying voice message {id} as it is too large");
+
+                // Still ack it as relayed, so the sender keeps it for other peers
+                return Ok(VoiceMessageAck::Relayed);
+            }
+
+            if self.peer_list().get(&recipient).is_some() {
+                glib::spawn_future_local(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    async move {
+                        obj.deliver_voice_messages(recipient).await;
+                    }
+                ));
+            }
+        }
+
+        Ok(VoiceMessageAck::Relayed)
+    }
+
+    /// Joins the voice channel with the given name, leaving the current one if any.
+    pub async fn join_voice_channel(&self, name: &str) -> Result<()> {
+        let name = name.trim().to_lowercase();
+        ensure!(!name.is_empty(), "Empty channel name");
+
+        ensure!(self.active_call().is_none(), "Already in a call");
+        ensure!(
+            AudioCodec::supported().contains(&CHANNEL_CODEC),
+            "Voice channels require {CHANNEL_CODEC} support"
+        );
+
+        if let Some(prev_channel) = self.active_voice_channel() {
+            if prev_channel.name() == name {
+                return Ok(());
+            }
+
+            self.leave_voice_channel().await;
+        }
+
+        let channel = VoiceChannel::new(&name)?;
+
+        self.imp()
+            .active_voice_channel
+            .replace(Some(channel.clone()));
+        self.notify_active_voice_channel();
+
+        tracing::debug!("Joined voice channel `{name}`");
+
+        self.publish(PublishData::PropertyChanged(vec![Property::VoiceChannel(
+            Some(name.clone()),
+        )]))
+        .await;
+
+        for peer in self.peer_list().iter::<Peer>() {
+            let peer = peer.unwrap();
+
+            if peer.voice_channel().as_deref() == Some(name.as_str()) {
+                self.connect_voice_channel_participant(&channel, &peer);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn leave_voice_channel(&self) {
+        let Some(channel) = self.imp().active_voice_channel.take() else {
+            return;
+        };
+
+        channel.leave();
+        self.notify_active_voice_channel();
+
+        tracing::debug!("Left voice channel `{}`", channel.name());
+
+        self.publish(PublishData::PropertyChanged(vec![Property::VoiceChannel(
+            None,
+        )]))
+        .await;
+    }
+
+    /// Opens a stream to receive the audio of a peer in the same channel.
+    ///
+    /// The peer does the same on its side once it sees us in the channel, so each side only
+    /// handles receiving.
+    fn connect_voice_channel_participant(&self, channel: &VoiceChannel, peer: &Peer) {
+        if channel.has_input(peer.id())
+            || !Application::get()
+                .settings()
+                .is_allowed_peer(peer.id(), &peer.name())
+        {
+            return;
+        }
+
+        let mut stream_control = self.imp().stream_control.get().unwrap().clone();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[weak]
+            channel,
+            #[weak]
+            peer,
+            async move {
+                let peer_id = *peer.id();
+
+                tracing::debug!("Opening channel input stream to {peer_id}");
+
+                let stream = match open_audio_stream(
+                    &mut stream_control,
+                    peer_id,
+                    CHANNEL_AUDIO_STREAM_PROTOCOL,
+                    CHANNEL_CODEC,
+                )
+                .await
+                {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        tracing::warn!("Failed to open channel input stream: {:?}", err);
+                        return;
+                    }
+                };
+
+                let input_stream = InputStream::new(stream);
+
+                // The channel may have been left or the peer connected while the stream opened
+                if obj.active_voice_channel().as_ref() != Some(&channel)
+                    || channel.has_input(&peer_id)
+                {
+                    if let Err(err) = input_stream.close_future(glib::Priority::LOW).await {
+                        tracing::error!("Failed to close input stream: {:?}", err);
+                    }
+                    return;
+                }
+
+                if let Err(err) = channel.add_participant_input(&peer, input_stream) {
+                    tracing::error!("Failed to add channel participant input: {:?}", err);
+                }
+            }
+        ));
+    }
+
     fn set_active_call(&self, call: Option<Call>) {
         if let Some(ref call) = call {
+            let direction = if call.state() == CallState::Incoming {
+                CallDirection::Incoming
+            } else {
+                CallDirection::Outgoing
+            };
+            let start_timestamp = glib::DateTime::now_utc().unwrap().to_unix();
+
             call.connect_state_notify(clone!(
                 #[weak(rename_to = obj)]
                 self,
                 move |call| {
-                    if matches!(call.state(), CallState::Ended(_)) {
+                    if let CallState::Ended(end_reason) = call.state() {
+                        let peer = call.peer();
+                        let record = CallRecord::new(
+                            *peer.id(),
+                            &peer.name(),
+                            direction,
+                            start_timestamp,
+                            call.duration_secs(),
+                            end_reason,
+                        );
+                        Application::get().call_history().insert(record);
+
                         obj.set_active_call(None);
                     }
                 }
@@ -276,29 +1003,126 @@ impl Client {
         self.notify_active_call();
     }
 
+    /// Predicts whether we are about to collide with the peer, warning the user if so.
+    fn check_collision(&self, peer: &Peer) {
+        let ttc = self
+            .imp()
+            .collision_monitor
+            .borrow_mut()
+            .check_now(peer.id(), &Application::get().gps());
+
+        if let Some(ttc) = ttc {
+            self.emit_by_name::<()>("collision-warning", &[peer, &ttc.as_secs_f64()]);
+        }
+    }
+
+    /// Checks whether the peer is an emergency vehicle approaching us, letting the user know
+    /// if so.
+    fn update_emergency_approach(&self, peer: &Peer) {
+        let approach = self
+            .imp()
+            .emergency_approach_tracker
+            .borrow_mut()
+            .update_peer(peer, &Application::get().gps());
+
+        if let Some(approach) = approach {
+            self.emit_by_name::<()>(
+                "emergency-vehicle-approaching",
+                &[
+                    peer,
+                    &approach.direction,
+                    &approach.distance,
+                    &approach.eta.as_secs(),
+                ],
+            );
+        }
+    }
+
+    /// Decides whether a received alert should be forwarded by gossipsub, letting the user
+    /// know if its sender was muted for flooding the network.
+    fn validate_alert(
+        &self,
+        info: &AlertInfo,
+        source: Option<PeerId>,
+    ) -> gossipsub::MessageAcceptance {
+        let validation = self
+            .imp()
+            .alert_handler
+            .borrow_mut()
+            .validate_alert(info, source);
+
+        if validation == AlertValidation::SenderAutoMuted {
+            self.emit_by_name::<()>(
+                "alert-sender-auto-muted",
+                &[&info.sender_name, &AUTO_MUTE_DURATION.as_secs()],
+            );
+        }
+
+        validation.acceptance()
+    }
+
     async fn publish(&self, data: PublishData) {
         tracing::trace!("Publishing data: {:?}", data);
 
         self.send_command(Command::Publish(data)).await;
     }
 
+    fn add_missed_call(&self, call: &Call) {
+        let peer = call.peer();
+
+        tracing::debug!("Missed call from {}", peer.id());
+
+        let missed_call = MissedCall::new(
+            *peer.id(),
+            &peer.name(),
+            glib::DateTime::now_utc().unwrap().to_unix(),
+        );
+        Application::get()
+            .missed_call_list()
+            .insert(missed_call.clone());
+
+        self.emit_by_name::<()>("call-missed", &[&missed_call]);
+    }
+
+    async fn send_call_signal(&self, peer_id: PeerId, signal: CallSignal) {
+        tracing::trace!("Sending call signal to {peer_id}: {:?}", signal);
+
+        self.send_command(Command::SendCallSignal { peer_id, signal })
+            .await;
+    }
+
     async fn publish_properties(&self) {
         let app = Application::get();
 
         let gps = app.gps();
         let location = gps.location();
         let speed = gps.speed();
+        let heading = gps.known_heading();
+        let altitude = gps.known_altitude();
+        let horizontal_accuracy = gps.known_horizontal_accuracy();
 
         let icon_name = app.settings().icon_name();
 
         let signal_quality = app.wireless_info().signal_quality();
 
+        let voice_channel = self.active_voice_channel().map(|channel| channel.name());
+
+        let credential = app.credential();
+
+        let is_emergency_en_route = self.is_emergency_en_route();
+
         self.publish(PublishData::PropertyChanged(vec![
             Property::Name(config::name()),
             Property::Location(location),
             Property::Speed(speed),
+            Property::Heading(heading),
+            Property::Altitude(altitude),
+            Property::HorizontalAccuracy(horizontal_accuracy),
             Property::SignalQuality(signal_quality),
             Property::IconName(icon_name),
+            Property::VoiceChannel(voice_channel),
+            Property::Credential(credential),
+            Property::EmergencyEnRoute(is_emergency_en_route),
         ]))
         .await;
     }
@@ -316,11 +1140,16 @@ impl Client {
         let (command_tx, command_rx) = async_channel::bounded(1);
         imp.command_tx.set(command_tx).unwrap();
 
-        let mut swarm = SwarmBuilder::with_new_identity()
+        let keypair = Application::get().keypair();
+
+        let mut swarm = SwarmBuilder::with_existing_identity(keypair)
             .with_async_std()
             .with_quic()
             .with_behaviour(|key| {
-                let gossipsub_config = gossipsub::ConfigBuilder::default().build()?;
+                // Messages are only forwarded once accepted, so spam can be stopped from spreading
+                let gossipsub_config = gossipsub::ConfigBuilder::default()
+                    .validate_messages()
+                    .build()?;
 
                 let gossipsub = gossipsub::Behaviour::new(
                     gossipsub::MessageAuthenticity::Signed(key.clone()),
@@ -338,10 +1167,31 @@ impl Client {
 
                 let stream = stream::Behaviour::new();
 
+                let call_signal = request_response::cbor::Behaviour::new(
+                    [(CALL_SIGNAL_PROTOCOL, ProtocolSupport::Full)],
+                    request_response::Config::default().with_request_timeout(CALL_SIGNAL_TIMEOUT),
+                );
+
+                let text_message = request_response::cbor::Behaviour::new(
+                    [(TEXT_MESSAGE_PROTOCOL, ProtocolSupport::Full)],
+                    request_response::Config::default().with_request_timeout(TEXT_MESSAGE_TIMEOUT),
+                );
+
+                let voice_message = request_response::cbor::Behaviour::new(
+                    [(VOICE_MESSAGE_PROTOCOL, ProtocolSupport::Full)],
+                    request_response::Config::default().with_request_timeout(VOICE_MESSAGE_TIMEOUT),
+                );
+
+                let ping = ping::Behaviour::new(ping::Config::new().with_interval(PING_INTERVAL));
+
                 Ok(Behaviour {
                     gossipsub,
                     mdns,
                     stream,
+                    call_signal,
+                    text_message,
+                    voice_message,
+                    ping,
                 })
             })?
             .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(Duration::MAX))
@@ -354,6 +1204,10 @@ impl Client {
 
         swarm.listen_on("/ip4/0.0.0.0/udp/0/quic-v1".parse()?)?;
 
+        imp.stream_control
+            .set(swarm.behaviour().stream.new_control())
+            .unwrap();
+
         let mut incoming_streams = swarm
             .behaviour()
             .stream
@@ -364,13 +1218,21 @@ impl Client {
             #[weak(rename_to = obj)]
             self,
             async move {
-                while let Some((their_peer_id, output_stream)) = incoming_streams.next().await {
+                while let Some((their_peer_id, mut output_stream)) = incoming_streams.next().await {
                     tracing::debug!("Incoming stream from {}", their_peer_id);
 
                     if let Some(active_call) = obj.active_call() {
                         if active_call.peer().id() == &their_peer_id {
-                            if let Err(err) =
-                                active_call.set_output_stream(OutputStream::new(output_stream))
+                            let codec = match read_audio_stream_header(&mut output_stream).await {
+                                Ok(codec) => codec,
+                                Err(err) => {
+                                    tracing::error!("Failed to read stream header: {:?}", err);
+                                    continue;
+                                }
+                            };
+
+                            if let Err(err) = active_call
+                                .set_output_stream(OutputStream::new(output_stream), codec)
                             {
                                 tracing::error!("Failed to set output stream: {:?}", err);
                             }
@@ -384,6 +1246,88 @@ impl Client {
             }
         ));
 
+        let mut incoming_channel_streams = swarm
+            .behaviour()
+            .stream
+            .new_control()
+            .accept(CHANNEL_AUDIO_STREAM_PROTOCOL)?;
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                while let Some((their_peer_id, mut output_stream)) =
+                    incoming_channel_streams.next().await
+                {
+                    tracing::debug!("Incoming channel stream from {}", their_peer_id);
+
+                    let Some(channel) = obj.active_voice_channel() else {
+                        tracing::warn!("Received channel stream without active voice channel");
+                        continue;
+                    };
+
+                    let Some(peer) = obj.peer_list().get(&their_peer_id).filter(|peer| {
+                        peer.voice_channel()
+                            .is_some_and(|name| name == channel.name())
+                    }) else {
+                        tracing::warn!("Received channel stream from peer outside the channel");
+                        continue;
+                    };
+
+                    if !Application::get()
+                        .settings()
+                        .is_allowed_peer(peer.id(), &peer.name())
+                    {
+                        tracing::debug!("Ignored channel stream from muted peer");
+                        continue;
+                    }
+
+                    match read_audio_stream_header(&mut output_stream).await {
+                        Ok(codec) if codec == CHANNEL_CODEC => {}
+                        Ok(codec) => {
+                            tracing::warn!("Received channel stream with unexpected codec {codec}");
+                            continue;
+                        }
+                        Err(err) => {
+                            tracing::error!("Failed to read stream header: {:?}", err);
+                            continue;
+                        }
+                    }
+
+                    if let Err(err) =
+                        channel.add_participant_output(&peer, OutputStream::new(output_stream))
+                    {
+                        tracing::error!("Failed to add channel participant output: {:?}", err);
+                    }
+                }
+            }
+        ));
+
+        let mut incoming_push_to_talk_streams = swarm
+            .behaviour()
+            .stream
+            .new_control()
+            .accept(PUSH_TO_TALK_STREAM_PROTOCOL)?;
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                while let Some((their_peer_id, input_stream)) =
+                    incoming_push_to_talk_streams.next().await
+                {
+                    tracing::debug!("Incoming push-to-talk stream from {}", their_peer_id);
+
+                    if let Err(err) = obj
+                        .handle_incoming_push_to_talk_stream(their_peer_id, input_stream)
+                        .await
+                    {
+                        tracing::debug!("Ignored push-to-talk stream: {:?}", err);
+                    }
+                }
+            }
+        ));
+
         // Periodically publish our properties as a workaround to missing packets on unreliable networks
         glib::spawn_future_local(clone!(
             #[weak(rename_to = obj)]
@@ -397,6 +1341,19 @@ impl Client {
             }
         ));
 
+        // Retry pending voice messages, in case nearby peers could not be reached earlier
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                loop {
+                    glib::timeout_future(VOICE_MESSAGE_RETRY_INTERVAL).await;
+
+                    obj.deliver_all_voice_messages().await;
+                }
+            }
+        ));
+
         loop {
             select! {
                 command = command_rx.recv().fuse() => {
@@ -413,6 +1370,47 @@ impl Client {
         }
     }
 
+    async fn handle_incoming_push_to_talk_stream(
+        &self,
+        their_peer_id: PeerId,
+        mut stream: libp2p::Stream,
+    ) -> Result<()> {
+        let peer = self
+            .peer_list()
+            .get(&their_peer_id)
+            .context("Received push-to-talk stream from unknown peer")?;
+
+        ensure!(
+            Application::get()
+                .settings()
+                .is_allowed_peer(peer.id(), &peer.name()),
+            "Peer is muted"
+        );
+        ensure!(
+            self.active_call().is_none() && self.active_voice_channel().is_none(),
+            "Already in a call"
+        );
+
+        let push_to_talk = self.push_to_talk();
+        ensure!(
+            !push_to_talk
+                .floor_holder()
+                .is_some_and(|holder| holder.id() != peer.id()),
+            "Another peer holds the floor"
+        );
+
+        let codec = read_audio_stream_header(&mut stream).await?;
+
+        // The stream may arrive before the floor announcement
+        if push_to_talk.floor_holder().is_none() {
+            push_to_talk.set_floor_holder(Some(&peer));
+        }
+
+        push_to_talk.play(InputStream::new(stream), codec)?;
+
+        Ok(())
+    }
+
     // Handle outgoing commands
     fn handle_command(
         &self,
@@ -422,12 +1420,48 @@ impl Client {
     ) -> Result<()> {
         match command {
             Command::Publish(data) => {
-                let data_bytes = serde_json::to_vec(&data)?;
+                let encoding = if config::is_wire_json_enabled() {
+                    Encoding::Json
+                } else {
+                    Encoding::Cbor
+                };
+                let data_bytes = Envelope::new(data).encode(encoding)?;
                 swarm
                     .behaviour_mut()
                     .gossipsub
                     .publish(topic.clone(), data_bytes)?;
             }
+            Command::SendCallSignal { peer_id, signal } => {
+                swarm
+                    .behaviour_mut()
+                    .call_signal
+                    .send_request(&peer_id, signal);
+            }
+            Command::SendTextMessage {
+                peer_id,
+                request,
+                message,
+            } => {
+                let request_id = swarm
+                    .behaviour_mut()
+                    .text_message
+                    .send_request(&peer_id, request);
+                self.imp()
+                    .text_message_requests
+                    .borrow_mut()
+                    .insert(request_id, message);
+            }
+            Command::SendVoiceMessage { peer_id, request } => {
+                let message_id = request.info.id;
+                let request_id = swarm
+                    .behaviour_mut()
+                    .voice_message
+                    .send_request(&peer_id, request);
+                self.imp()
+                    .voice_message_requests
+                    .borrow_mut()
+                    .insert(request_id, (message_id, peer_id));
+            }
         }
 
         Ok(())
@@ -448,6 +1482,14 @@ impl Client {
 
                     swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
                     self.peer_list().insert(Peer::new(peer_id));
+
+                    glib::spawn_future_local(clone!(
+                        #[weak(rename_to = obj)]
+                        self,
+                        async move {
+                            obj.deliver_voice_messages(peer_id).await;
+                        }
+                    ));
                 }
             }
             SwarmEvent::Behaviour(BehaviourEvent::Mdns(mdns::Event::Expired(list))) => {
@@ -459,16 +1501,87 @@ impl Client {
                         .gossipsub
                         .remove_explicit_peer(&peer_id);
                     self.peer_list().remove(&peer_id);
+                    imp.collision_monitor.borrow_mut().remove_peer(&peer_id);
+
+                    if let Some(channel) = self.active_voice_channel() {
+                        channel.remove_participant(&peer_id);
+                    }
                 }
             }
             SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Message {
                 propagation_source: their_peer_id,
+                message_id,
                 message,
-                ..
             })) => {
                 tracing::debug!("received message from {}", their_peer_id);
 
-                match serde_json::from_slice(&message.data)? {
+                let envelope = match Envelope::decode(&message.data) {
+                    Ok(envelope) => envelope,
+                    Err(err) => {
+                        swarm
+                            .behaviour_mut()
+                            .gossipsub
+                            .report_message_validation_result(
+                                &message_id,
+                                &their_peer_id,
+                                gossipsub::MessageAcceptance::Reject,
+                            );
+
+                        return Err(err);
+                    }
+                };
+
+                // The data of other versions can't be trusted to mean the same, so don't handle
+                // or forward it, without penalizing the peer for running another version
+                if envelope.version != PROTOCOL_VERSION {
+                    if imp
+                        .unsupported_protocol_versions
+                        .borrow_mut()
+                        .insert(envelope.version)
+                    {
+                        tracing::warn!(
+                            "Ignoring messages with protocol version {} from {} (app version {}), ours is {}",
+                            envelope.version,
+                            envelope.sender.name,
+                            envelope.sender.app_version,
+                            PROTOCOL_VERSION
+                        );
+                    }
+
+                    swarm
+                        .behaviour_mut()
+                        .gossipsub
+                        .report_message_validation_result(
+                            &message_id,
+                            &their_peer_id,
+                            gossipsub::MessageAcceptance::Ignore,
+                        );
+
+                    return Ok(());
+                }
+
+                let acceptance = match &envelope.data {
+                    Some(PublishData::Alert(info)) => self.validate_alert(info, message.source),
+                    Some(PublishData::AlertResponse(info)) => AlertHandler::validate_response(info),
+                    _ => gossipsub::MessageAcceptance::Accept,
+                };
+                let is_accepted = matches!(acceptance, gossipsub::MessageAcceptance::Accept);
+                swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .report_message_validation_result(&message_id, &their_peer_id, acceptance);
+
+                if !is_accepted {
+                    return Ok(());
+                }
+
+                let Some(data) = envelope.data else {
+                    tracing::debug!("Ignoring message {} with unknown data", envelope.id);
+
+                    return Ok(());
+                };
+
+                match data {
                     PublishData::PropertyChanged(props) => {
                         let their_peer_id = message
                             .source
@@ -480,6 +1593,9 @@ impl Client {
                         for prop in props {
                             match prop {
                                 Property::Name(name) => {
+                                    Application::get()
+                                        .settings()
+                                        .update_muted_peer_name(&their_peer_id, &name);
                                     peer.set_name(name);
                                 }
                                 Property::Location(location) => {
@@ -488,29 +1604,192 @@ impl Client {
                                 Property::Speed(speed) => {
                                     peer.set_speed(speed);
                                 }
+                                Property::Heading(heading) => {
+                                    peer.set_heading(heading.unwrap_or(UNKNOWN));
+                                }
+                                Property::Altitude(altitude) => {
+                                    peer.set_altitude(altitude.unwrap_or(UNKNOWN_ALTITUDE));
+                                }
+                                Property::HorizontalAccuracy(horizontal_accuracy) => {
+                                    peer.set_horizontal_accuracy(
+                                        horizontal_accuracy.unwrap_or(UNKNOWN),
+                                    );
+                                }
                                 Property::SignalQuality(signal_quality) => {
                                     peer.set_signal_quality(signal_quality);
                                 }
                                 Property::IconName(icon_name) => {
                                     peer.set_icon_name(icon_name);
                                 }
+                                Property::VoiceChannel(voice_channel) => {
+                                    peer.set_voice_channel(voice_channel.clone());
+
+                                    if let Some(channel) = self.active_voice_channel() {
+                                        if voice_channel.is_some_and(|name| name == channel.name())
+                                        {
+                                            self.connect_voice_channel_participant(&channel, &peer);
+                                        } else if channel.has_participant(peer.id()) {
+                                            channel.remove_participant(peer.id());
+                                        }
+                                    }
+                                }
+                                Property::Credential(credential) => {
+                                    let role = credential
+                                        .and_then(|credential| {
+                                            Application::get()
+                                                .authorities()
+                                                .verify_credential(&credential, &their_peer_id)
+                                                .map_err(|err| {
+                                                    // Properties are published every few
+                                                    // seconds, so this would flood the log
+                                                    tracing::debug!(
+                                                        "Rejected credential of {}: {:?}",
+                                                        their_peer_id,
+                                                        err
+                                                    );
+                                                })
+                                                .ok()
+                                        })
+                                        .unwrap_or_default();
+                                    peer.set_role(role);
+                                }
+                                Property::EmergencyEnRoute(is_emergency_en_route) => {
+                                    peer.set_is_emergency_en_route(is_emergency_en_route);
+                                }
                             }
                         }
+
+                        self.update_emergency_approach(&peer);
+
+                        imp.collision_monitor.borrow_mut().update_from_peer(&peer);
+                        self.check_collision(&peer);
                     }
-                    PublishData::CallRequest { ref destination }
-                        if destination == swarm.local_peer_id() =>
-                    {
-                        if self.active_call().is_some() {
-                            self.publish(PublishData::CallRequestResponse {
-                                destination: their_peer_id,
-                                response: CallRequestResponse::Reject(
-                                    CallRequestRejectReason::AlreadyInCall,
-                                ),
-                            })
-                            .await;
+                    PublishData::Alert(info) => {
+                        let peer = self
+                            .peer_list()
+                            .get(&their_peer_id)
+                            .context("Received alert from unknown peer")?;
+
+                        if let Some(relayed_info) = AlertHandler::relayed_alert(&info) {
+                            glib::spawn_future_local(clone!(
+                                #[weak(rename_to = obj)]
+                                self,
+                                async move {
+                                    tracing::debug!(
+                                        "Relaying alert {} at hop {}",
+                                        relayed_info.id,
+                                        relayed_info.hops
+                                    );
+
+                                    obj.publish(PublishData::Alert(relayed_info)).await;
+                                }
+                            ));
+                        }
+
+                        let alert = imp.alert_handler.borrow().handle_alert(info, &peer);
+                        if let Some(alert) = alert {
+                            self.emit_by_name::<()>("alert-received", &[&alert]);
+                        }
+                    }
+                    PublishData::AlertResponse(info) => {
+                        let outcome = imp.alert_handler.borrow_mut().handle_response(info);
+                        match outcome {
+                            AlertResponseOutcome::Received(alert, response) => {
+                                self.emit_by_name::<()>(
+                                    "alert-response-received",
+                                    &[&alert, &response],
+                                );
+                            }
+                            AlertResponseOutcome::Relay(relayed_info) => {
+                                glib::spawn_future_local(clone!(
+                                    #[weak(rename_to = obj)]
+                                    self,
+                                    async move {
+                                        obj.publish(PublishData::AlertResponse(relayed_info)).await;
+                                    }
+                                ));
+                            }
+                            AlertResponseOutcome::Ignored => {}
+                        }
+                    }
+                    PublishData::TalkStarted => {
+                        let their_peer_id = message
+                            .source
+                            .context("Received talk start without source")?;
+                        let peer = self
+                            .peer_list()
+                            .get(&their_peer_id)
+                            .context("Received talk start from unknown peer")?;
+
+                        if !Application::get()
+                            .settings()
+                            .is_allowed_peer(peer.id(), &peer.name())
+                        {
+                            tracing::debug!("Ignored talk start from muted peer");
+
+                            return Ok(());
+                        }
+
+                        let push_to_talk = self.push_to_talk();
+
+                        // Every peer resolves simultaneous starts the same way, keeping the
+                        // floor for the lowest peer id
+                        if push_to_talk.is_talking() {
+                            if their_peer_id < *swarm.local_peer_id() {
+                                tracing::debug!("Yielding floor to {their_peer_id}");
+
+                                push_to_talk.stop_talking();
+                            } else {
+                                return Ok(());
+                            }
+                        }
+
+                        if push_to_talk
+                            .floor_holder()
+                            .is_some_and(|holder| *holder.id() < their_peer_id)
+                        {
+                            return Ok(());
+                        }
+
+                        push_to_talk.set_floor_holder(Some(&peer));
+                    }
+                    PublishData::TalkEnded => {
+                        let their_peer_id =
+                            message.source.context("Received talk end without source")?;
+
+                        let push_to_talk = self.push_to_talk();
+                        if push_to_talk
+                            .floor_holder()
+                            .is_some_and(|holder| holder.id() == &their_peer_id)
+                        {
+                            push_to_talk.set_floor_holder(None);
+                        }
+                    }
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::CallSignal(
+                request_response::Event::Message {
+                    peer: their_peer_id,
+                    message:
+                        request_response::Message::Request {
+                            request, channel, ..
+                        },
+                    ..
+                },
+            )) => {
+                tracing::debug!("Received call signal from {their_peer_id}: {:?}", request);
+
+                match request {
+                    CallSignal::Request { codecs } => {
+                        if self.active_call().is_some() || self.active_voice_channel().is_some() {
+                            send_call_signal_ack(
+                                swarm,
+                                channel,
+                                CallSignalAck::Rejected(CallRequestRejectReason::AlreadyInCall),
+                            )?;
 
                             tracing::debug!(
-                                "Rejected another call since a call is already in progress"
+                                "Rejected another call since a call or channel is already in progress"
                             );
 
                             return Ok(());
@@ -521,20 +1800,23 @@ impl Client {
                             .get(&their_peer_id)
                             .context("Received call request for unknown peer")?;
 
-                        if !Application::get().settings().is_allowed_peer(&peer.name()) {
-                            self.publish(PublishData::CallRequestResponse {
-                                destination: their_peer_id,
-                                response: CallRequestResponse::Reject(
-                                    CallRequestRejectReason::MutedByUser,
-                                ),
-                            })
-                            .await;
+                        if !Application::get()
+                            .settings()
+                            .is_allowed_peer(peer.id(), &peer.name())
+                        {
+                            send_call_signal_ack(
+                                swarm,
+                                channel,
+                                CallSignalAck::Rejected(CallRequestRejectReason::MutedByUser),
+                            )?;
 
                             tracing::debug!("Ignored call from muted peer");
 
                             return Ok(());
                         }
 
+                        send_call_signal_ack(swarm, channel, CallSignalAck::Received)?;
+
                         let (call_incoming_response_tx, mut call_incoming_response_rx) =
                             oneshot::channel();
                         imp.call_incoming_response_tx
@@ -545,7 +1827,6 @@ impl Client {
                         imp.call_incoming_cancel_tx
                             .replace(Some(call_incoming_cancel_tx));
 
-                        let peer = self.peer_list().get(&their_peer_id).unwrap();
                         let call = Call::new(&peer);
                         call.set_state(CallState::Incoming);
 
@@ -553,6 +1834,8 @@ impl Client {
 
                         let mut stream_control = swarm.behaviour().stream.new_control();
 
+                        let codec = AudioCodec::negotiate(&AudioCodec::supported(), &codecs);
+
                         // Spawn a task here so we don't block the loop while waiting for the response
                         glib::spawn_future_local(clone!(
                             #[weak(rename_to = obj)]
@@ -561,16 +1844,27 @@ impl Client {
                                 let response = select! {
                                     response = call_incoming_response_rx => response.unwrap(),
                                     _ = call_incoming_cancel_rx => CallIncomingResponse::Cancelled,
+                                    _ = glib::timeout_future(call_ringing_timeout()).fuse() => {
+                                        CallIncomingResponse::NoAnswer
+                                    }
                                 };
 
+                                let imp = obj.imp();
+                                imp.call_incoming_response_tx.replace(None);
+                                imp.call_incoming_cancel_tx.replace(None);
+
                                 tracing::debug!("Received call request: {:?}", response);
 
                                 if response == CallIncomingResponse::Accept {
                                     tracing::debug!("Opening output stream to {their_peer_id}");
 
-                                    let input_stream = match stream_control
-                                        .open_stream(their_peer_id, AUDIO_STREAM_PROTOCOL)
-                                        .await
+                                    let input_stream = match open_audio_stream(
+                                        &mut stream_control,
+                                        their_peer_id,
+                                        AUDIO_STREAM_PROTOCOL,
+                                        codec,
+                                    )
+                                    .await
                                     {
                                         Ok(stream) => stream,
                                         Err(err) => {
@@ -583,7 +1877,7 @@ impl Client {
                                     };
 
                                     if let Err(err) =
-                                        call.set_input_stream(InputStream::new(input_stream))
+                                        call.set_input_stream(InputStream::new(input_stream), codec)
                                     {
                                         tracing::error!("Failed to set input stream: {:?}", err);
                                         return;
@@ -591,101 +1885,294 @@ impl Client {
 
                                     call.set_state(CallState::Ongoing);
 
-                                    obj.publish(PublishData::CallRequestResponse {
-                                        destination: their_peer_id,
-                                        response: CallRequestResponse::Accept,
-                                    })
+                                    obj.send_call_signal(
+                                        their_peer_id,
+                                        CallSignal::Response(CallRequestResponse::Accept { codec }),
+                                    )
                                     .await;
                                 } else {
-                                    if response == CallIncomingResponse::Reject {
-                                        obj.publish(PublishData::CallRequestResponse {
-                                            destination: their_peer_id,
-                                            response: CallRequestResponse::Reject(
-                                                CallRequestRejectReason::RejectedByUser,
-                                            ),
-                                        })
+                                    let reject_reason = match response {
+                                        CallIncomingResponse::Reject => {
+                                            Some(CallRequestRejectReason::RejectedByUser)
+                                        }
+                                        CallIncomingResponse::NoAnswer => {
+                                            Some(CallRequestRejectReason::NoAnswer)
+                                        }
+                                        _ => None,
+                                    };
+
+                                    if let Some(reject_reason) = reject_reason {
+                                        obj.send_call_signal(
+                                            their_peer_id,
+                                            CallSignal::Response(CallRequestResponse::Reject(
+                                                reject_reason,
+                                            )),
+                                        )
                                         .await;
                                     }
 
-                                    call.set_state(CallState::Ended(CallEndReason::Other));
+                                    if matches!(
+                                        response,
+                                        CallIncomingResponse::NoAnswer
+                                            | CallIncomingResponse::Cancelled
+                                    ) {
+                                        obj.add_missed_call(&call);
+                                    }
+
+                                    let end_reason = if response == CallIncomingResponse::NoAnswer {
+                                        CallEndReason::NoAnswer
+                                    } else {
+                                        CallEndReason::Other
+                                    };
+                                    call.set_state(CallState::Ended(end_reason));
                                 }
                             }
                         ));
                     }
-                    PublishData::CallRequestCancel { ref destination }
-                        if destination == swarm.local_peer_id() =>
-                    {
-                        tracing::debug!("Received call request cancel: {their_peer_id}");
-
-                        if self.active_call().is_some() {
-                            let tx = imp.call_incoming_cancel_tx.take().unwrap();
-                            tx.send(()).unwrap();
+                    CallSignal::Cancel => {
+                        send_call_signal_ack(swarm, channel, CallSignalAck::Received)?;
+
+                        if self
+                            .active_call()
+                            .is_some_and(|call| call.peer().id() == &their_peer_id)
+                        {
+                            if let Some(tx) = imp.call_incoming_cancel_tx.take() {
+                                let _ = tx.send(());
+                            }
                         } else {
                             tracing::warn!("Received call request cancel without active call");
                         }
                     }
-                    PublishData::CallRequestResponse {
-                        ref destination,
-                        response,
-                    } if destination == swarm.local_peer_id() => {
-                        tracing::debug!("Received call request response: {:?}", response);
+                    CallSignal::Response(response) => {
+                        send_call_signal_ack(swarm, channel, CallSignalAck::Received)?;
+
+                        let active_call = self
+                            .active_call()
+                            .filter(|call| call.peer().id() == &their_peer_id)
+                            .context("Received call request response without active call")?;
 
                         match response {
-                            CallRequestResponse::Accept => {
+                            CallRequestResponse::Accept { codec } => {
                                 tracing::debug!("Opening output stream to {their_peer_id}");
 
-                                let input_stream = swarm
-                                    .behaviour()
-                                    .stream
-                                    .new_control()
-                                    .open_stream(their_peer_id, AUDIO_STREAM_PROTOCOL)
-                                    .await
-                                    .map_err(|err| anyhow!(err))?;
+                                let input_stream = open_audio_stream(
+                                    &mut swarm.behaviour().stream.new_control(),
+                                    their_peer_id,
+                                    AUDIO_STREAM_PROTOCOL,
+                                    codec,
+                                )
+                                .await?;
 
-                                let active_call = self.active_call().unwrap();
-                                active_call.set_input_stream(InputStream::new(input_stream))?;
+                                active_call
+                                    .set_input_stream(InputStream::new(input_stream), codec)?;
                                 active_call.set_state(CallState::Ongoing);
                             }
                             CallRequestResponse::Reject(reject_reason) => {
-                                let end_reason = match reject_reason {
-                                    CallRequestRejectReason::AlreadyInCall => {
-                                        CallEndReason::PeerInAnotherCall
-                                    }
-                                    CallRequestRejectReason::RejectedByUser => {
-                                        CallEndReason::PeerRejected
-                                    }
-                                    CallRequestRejectReason::MutedByUser => {
-                                        CallEndReason::PeerMuted
-                                    }
-                                    CallRequestRejectReason::Other => CallEndReason::Other,
-                                };
-
-                                let active_call = self.active_call().unwrap();
-                                active_call.set_state(CallState::Ended(end_reason));
+                                active_call.set_state(CallState::Ended(
+                                    CallEndReason::from_reject_reason(reject_reason),
+                                ));
                             }
-                            CallRequestResponse::Cancelled => unreachable!(),
                         }
                     }
-                    PublishData::Alert(alert_type) => {
-                        let peer = self
-                            .peer_list()
-                            .get(&their_peer_id)
-                            .context("Received alert from unknown peer")?;
-
-                        if !Application::get().settings().is_allowed_peer(&peer.name()) {
-                            tracing::debug!("Ignored alert from muted peer");
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::CallSignal(
+                request_response::Event::Message {
+                    peer: their_peer_id,
+                    message: request_response::Message::Response { response, .. },
+                    ..
+                },
+            )) => {
+                tracing::debug!(
+                    "Received call signal ack from {their_peer_id}: {:?}",
+                    response
+                );
+
+                if let CallSignalAck::Rejected(reject_reason) = response {
+                    if let Some(active_call) = self
+                        .active_call()
+                        .filter(|call| call.peer().id() == &their_peer_id)
+                    {
+                        active_call.set_state(CallState::Ended(CallEndReason::from_reject_reason(
+                            reject_reason,
+                        )));
+                    }
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::CallSignal(
+                request_response::Event::OutboundFailure {
+                    peer: their_peer_id,
+                    error,
+                    ..
+                },
+            )) => {
+                tracing::warn!("Failed to send call signal to {their_peer_id}: {error}");
+
+                if let Some(active_call) = self
+                    .active_call()
+                    .filter(|call| call.peer().id() == &their_peer_id)
+                {
+                    match active_call.state() {
+                        CallState::Ongoing => active_call.start_end(),
+                        CallState::Ended(_) => {}
+                        _ => active_call.set_state(CallState::Ended(
+                            CallEndReason::SignallingFailed(CallSignalError::from(&error)),
+                        )),
+                    }
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::CallSignal(
+                request_response::Event::InboundFailure {
+                    peer: their_peer_id,
+                    error,
+                    ..
+                },
+            )) => {
+                tracing::warn!("Failed to respond to call signal from {their_peer_id}: {error}");
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::TextMessage(
+                request_response::Event::Message {
+                    peer: their_peer_id,
+                    message:
+                        request_response::Message::Request {
+                            request, channel, ..
+                        },
+                    ..
+                },
+            )) => {
+                tracing::debug!("Received text message from {their_peer_id}: {:?}", request);
+
+                let peer = self
+                    .peer_list()
+                    .get(&their_peer_id)
+                    .context("Received text message from unknown peer")?;
+
+                let receipt = if Application::get()
+                    .settings()
+                    .is_allowed_peer(peer.id(), &peer.name())
+                {
+                    let message =
+                        TextMessage::new_incoming(request.id, &request.text, request.is_broadcast);
+                    self.conversation(&their_peer_id).push(message.clone());
+
+                    self.emit_by_name::<()>("text-message-received", &[&peer, &message]);
+
+                    TextMessageReceipt::Received
+                } else {
+                    tracing::debug!("Rejected text message from muted peer");
+
+                    TextMessageReceipt::Rejected
+                };
 
-                            return Ok(());
-                        }
+                swarm
+                    .behaviour_mut()
+                    .text_message
+                    .send_response(channel, receipt)
+                    .map_err(|_| {
+                        anyhow!("Failed to send text message receipt: connection closed")
+                    })?;
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::TextMessage(
+                request_response::Event::Message {
+                    peer: their_peer_id,
+                    message:
+                        request_response::Message::Response {
+                            request_id,
+                            response,
+                        },
+                    ..
+                },
+            )) => {
+                tracing::debug!(
+                    "Received text message receipt from {their_peer_id}: {:?}",
+                    response
+                );
+
+                if let Some(message) = imp.text_message_requests.borrow_mut().remove(&request_id) {
+                    message.handle_receipt(matches!(response, TextMessageReceipt::Received));
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::TextMessage(
+                request_response::Event::OutboundFailure {
+                    peer: their_peer_id,
+                    request_id,
+                    error,
+                    ..
+                },
+            )) => {
+                tracing::warn!("Failed to send text message to {their_peer_id}: {error}");
+
+                if let Some(message) = imp.text_message_requests.borrow_mut().remove(&request_id) {
+                    message.handle_receipt(false);
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::VoiceMessage(
+                request_response::Event::Message {
+                    peer: their_peer_id,
+                    message:
+                        request_response::Message::Request {
+                            request, channel, ..
+                        },
+                    ..
+                },
+            )) => {
+                tracing::debug!(
+                    "Received voice message {} from {their_peer_id}",
+                    request.info.id
+                );
+
+                let ack = self.handle_voice_message_request(*swarm.local_peer_id(), request)?;
 
-                        let peer = self.peer_list().get(&their_peer_id).unwrap();
-                        self.emit_by_name::<()>("alert-received", &[&peer, &alert_type]);
+                swarm
+                    .behaviour_mut()
+                    .voice_message
+                    .send_response(channel, ack)
+                    .map_err(|_| anyhow!("Failed to send voice message ack: connection closed"))?;
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::VoiceMessage(
+                request_response::Event::Message {
+                    peer: their_peer_id,
+                    message:
+                        request_response::Message::Response {
+                            request_id,
+                            response,
+                        },
+                    ..
+                },
+            )) => {
+                let Some((message_id, _)) =
+                    imp.voice_message_requests.borrow_mut().remove(&request_id)
+                else {
+                    return Ok(());
+                };
+
+                tracing::debug!(
+                    "Received voice message {message_id} ack from {their_peer_id}: {:?}",
+                    response
+                );
+
+                let outbox = Application::get().voice_message_outbox();
+                match response {
+                    VoiceMessageAck::Delivered | VoiceMessageAck::Rejected => {
+                        outbox.remove(message_id);
                     }
-                    other_published_data => {
-                        tracing::debug!("Ignoring published data: {:?}", other_published_data);
+                    VoiceMessageAck::Relayed => {
+                        outbox.mark_relayed(message_id, their_peer_id);
                     }
                 }
             }
+            SwarmEvent::Behaviour(BehaviourEvent::VoiceMessage(
+                request_response::Event::OutboundFailure {
+                    peer: their_peer_id,
+                    request_id,
+                    error,
+                    ..
+                },
+            )) => {
+                tracing::warn!("Failed to send voice message to {their_peer_id}: {error}");
+
+                imp.voice_message_requests.borrow_mut().remove(&request_id);
+            }
             SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Subscribed {
                 ..
             })) => {
@@ -695,6 +2182,18 @@ impl Client {
 
                 self.publish_properties().await;
             }
+            SwarmEvent::Behaviour(BehaviourEvent::Ping(ping::Event {
+                peer: their_peer_id,
+                result: Ok(rtt),
+                ..
+            })) => {
+                if let Some(active_call) = self
+                    .active_call()
+                    .filter(|call| call.peer().id() == &their_peer_id)
+                {
+                    active_call.set_latency_ms(rtt.as_millis() as u32);
+                }
+            }
             SwarmEvent::NewListenAddr { address, .. } => {
                 tracing::trace!("Local node is listening on {address}");
             }
@@ -707,60 +2206,95 @@ impl Client {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum CallRequestRejectReason {
-    AlreadyInCall,
-    RejectedByUser,
-    MutedByUser,
-    Other,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-enum CallRequestResponse {
-    Accept,
-    Reject(CallRequestRejectReason),
-    Cancelled,
-}
-
 #[derive(Debug, PartialEq, Eq)]
 enum CallIncomingResponse {
     Accept,
     Reject,
     Cancelled,
+    NoAnswer,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum Property {
-    Name(String),
-    Location(Option<Location>),
-    Speed(f64),
-    SignalQuality(SignalQuality),
-    IconName(String),
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-enum PublishData {
-    PropertyChanged(Vec<Property>),
-    Alert(AlertType),
-    CallRequest {
-        destination: PeerId,
+enum Command {
+    Publish(PublishData),
+    SendCallSignal {
+        peer_id: PeerId,
+        signal: CallSignal,
     },
-    CallRequestCancel {
-        destination: PeerId,
+    SendTextMessage {
+        peer_id: PeerId,
+        request: TextMessageRequest,
+        /// Message to update once the receipt arrives.
+        message: TextMessage,
     },
-    CallRequestResponse {
-        destination: PeerId,
-        response: CallRequestResponse,
+    SendVoiceMessage {
+        peer_id: PeerId,
+        request: VoiceMessageRequest,
     },
 }
 
-enum Command {
-    Publish(PublishData),
-}
-
 #[derive(NetworkBehaviour)]
 struct Behaviour {
     gossipsub: gossipsub::Behaviour,
     mdns: mdns::async_io::Behaviour,
     stream: stream::Behaviour,
+    call_signal: request_response::cbor::Behaviour<CallSignal, CallSignalAck>,
+    text_message: request_response::cbor::Behaviour<TextMessageRequest, TextMessageReceipt>,
+    voice_message: request_response::cbor::Behaviour<VoiceMessageRequest, VoiceMessageAck>,
+    ping: ping::Behaviour,
+}
+
+impl CallEndReason {
+    fn from_reject_reason(reject_reason: CallRequestRejectReason) -> Self {
+        match reject_reason {
+            CallRequestRejectReason::AlreadyInCall => Self::PeerInAnotherCall,
+            CallRequestRejectReason::RejectedByUser => Self::PeerRejected,
+            CallRequestRejectReason::MutedByUser => Self::PeerMuted,
+            CallRequestRejectReason::NoAnswer => Self::NoAnswer,
+            CallRequestRejectReason::Other => Self::Other,
+        }
+    }
+}
+
+/// Opens an audio stream to the peer, asking it to send audio encoded with `codec`.
+async fn open_audio_stream(
+    control: &mut stream::Control,
+    peer_id: PeerId,
+    protocol: StreamProtocol,
+    codec: AudioCodec,
+) -> Result<libp2p::Stream> {
+    let mut stream = control
+        .open_stream(peer_id, protocol)
+        .await
+        .map_err(|err| anyhow!(err))?;
+
+    stream.write_all(&[codec.to_byte()]).await?;
+    stream.flush().await?;
+
+    Ok(stream)
+}
+
+/// Reads the codec requested by the peer that opened the audio stream.
+async fn read_audio_stream_header(stream: &mut libp2p::Stream) -> Result<AudioCodec> {
+    let mut header = [0; 1];
+    stream.read_exact(&mut header).await?;
+
+    AudioCodec::from_byte(header[0])
+        .with_context(|| format!("Unknown codec in stream header: {}", header[0]))
+}
+
+fn call_ringing_timeout() -> Duration {
+    let secs = Application::get().settings().call_ringing_timeout_secs();
+    Duration::from_secs(secs as u64)
+}
+
+fn send_call_signal_ack(
+    swarm: &mut Swarm<Behaviour>,
+    channel: request_response::ResponseChannel<CallSignalAck>,
+    ack: CallSignalAck,
+) -> Result<()> {
+    swarm
+        .behaviour_mut()
+        .call_signal
+        .send_response(channel, ack)
+        .map_err(|_| anyhow!("Failed to send call signal ack: connection closed"))
 }
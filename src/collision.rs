@@ -0,0 +1,388 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use libp2p::PeerId;
+
+use crate::{gps::Gps, location::Location, peer::Peer};
+
+/// Time-to-collision below which the driver is warned.
+const TTC_WARNING_THRESHOLD: Duration = Duration::from_secs(4);
+/// Distance between two vehicles at which they are considered to collide, in meters.
+const COLLISION_DISTANCE: f64 = 5.0;
+/// Farthest a peer can be for a collision with it to be predicted, in meters.
+const MAX_PREDICTION_DISTANCE: f64 = 200.0;
+/// Speed below which a vehicle is considered stopped, in meters per second.
+const MIN_MOVING_SPEED: f64 = 0.5;
+/// Distance a peer has to move before its heading is estimated, so GPS jitter is ignored.
+const HEADING_MIN_DISTANCE: f64 = 5.0;
+/// Time after which the heading of a peer is no longer trusted without new locations.
+const MAX_TRACK_AGE: Duration = Duration::from_secs(10);
+/// Interval between repeated warnings about the same peer.
+const WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Position and motion of a vehicle at a moment.
+#[derive(Debug, Clone, Copy)]
+pub struct Kinematics {
+    pub location: Location,
+    /// Speed in meters per second.
+    pub speed: f64,
+    /// Direction of travel in degrees clockwise from north.
+    pub heading: Option<f64>,
+}
+
+impl Kinematics {
+    /// Returns our own position and motion, if we have a fix.
+    pub fn ours(gps: &Gps) -> Option<Self> {
+        Some(Self {
+            location: gps.location()?,
+            speed: gps.speed(),
+            heading: gps.known_heading(),
+        })
+    }
+
+    /// Returns the velocity as east and north components in meters per second, if known.
+    fn velocity(&self) -> Option<(f64, f64)> {
+        if self.speed < MIN_MOVING_SPEED {
+            return Some((0.0, 0.0));
+        }
+
+        let heading = self.heading?.to_radians();
+        Some((self.speed * heading.sin(), self.speed * heading.cos()))
+    }
+}
+
+/// Predicts the time until the vehicles come within collision distance, assuming both keep
+/// their current speed and heading.
+///
+/// Returns `None` if they never get that close, or if a moving vehicle has no known heading.
+pub fn time_to_collision(ours: &Kinematics, theirs: &Kinematics) -> Option<Duration> {
+    let distance = ours.location.distance(&theirs.location);
+    let bearing = ours.location.bearing(&theirs.location).to_radians();
+    let (px, py) = (distance * bearing.sin(), distance * bearing.cos());
+
+    let (our_vx, our_vy) = ours.velocity()?;
+    let (their_vx, their_vy) = theirs.velocity()?;
+    let (vx, vy) = (their_vx - our_vx, their_vy - our_vy);
+
+    // Solve |p + v·t| = COLLISION_DISTANCE for the earliest positive t
+    let a = vx * vx + vy * vy;
+    let b = 2.0 * (px * vx + py * vy);
+    let c = px * px + py * py - COLLISION_DISTANCE * COLLISION_DISTANCE;
+
+    // Vehicles keeping their distance or moving apart won't collide, even if already close
+    if a == 0.0 || b >= 0.0 {
+        return None;
+    }
+
+    if c <= 0.0 {
+        return Some(Duration::ZERO);
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    (t >= 0.0).then(|| Duration::from_secs_f64(t))
+}
+
+#[derive(Debug)]
+struct PeerTrack {
+    kinematics: Kinematics,
+    /// Location the heading is measured from.
+    heading_origin: Location,
+    updated_at: Instant,
+    warned_at: Option<Instant>,
+}
+
+/// Follows the tracks of peers to warn when we are about to collide with one.
+///
+/// If a peer doesn't share its heading, it is estimated from how its location changes.
+#[derive(Debug, Default)]
+pub struct CollisionMonitor {
+    tracks: HashMap<PeerId, PeerTrack>,
+}
+
+impl CollisionMonitor {
+    /// Records the latest location, speed and heading of a peer, received at the given time.
+    pub fn update_peer(
+        &mut self,
+        peer_id: PeerId,
+        location: Location,
+        speed: f64,
+        heading: Option<f64>,
+        at: Instant,
+    ) {
+        let track = self.tracks.entry(peer_id).or_insert_with(|| PeerTrack {
+            kinematics: Kinematics {
+                location,
+                speed,
+                heading: None,
+            },
+            heading_origin: location,
+            updated_at: at,
+            warned_at: None,
+        });
+
+        if at.duration_since(track.updated_at) > MAX_TRACK_AGE {
+            track.kinematics.heading = None;
+            track.heading_origin = location;
+        }
+
+        if heading.is_some() {
+            track.kinematics.heading = heading;
+            track.heading_origin = location;
+        } else if track.heading_origin.distance(&location) >= HEADING_MIN_DISTANCE {
+            track.kinematics.heading = Some(track.heading_origin.bearing(&location));
+            track.heading_origin = location;
+        }
+
+        track.kinematics.location = location;
+        track.kinematics.speed = speed;
+        track.updated_at = at;
+    }
+
+    /// Records the latest properties of the peer, or stops following it if its location is
+    /// unknown.
+    pub fn update_from_peer(&mut self, peer: &Peer) {
+        match peer.location() {
+            Some(location) => self.update_peer(
+                *peer.id(),
+                location,
+                peer.speed(),
+                peer.known_heading(),
+                Instant::now(),
+            ),
+            None => self.remove_peer(peer.id()),
+        }
+    }
+
+    /// Stops following the peer, such as when it is no longer nearby.
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.tracks.remove(peer_id);
+    }
+
+    /// Returns the time-to-collision with the peer if it is below the warning threshold and
+    /// we were not warned about it recently.
+    pub fn check(&mut self, peer_id: &PeerId, ours: &Kinematics, at: Instant) -> Option<Duration> {
+        let track = self.tracks.get_mut(peer_id)?;
+
+        if at.duration_since(track.updated_at) > MAX_TRACK_AGE
+            || ours.location.distance(&track.kinematics.location) > MAX_PREDICTION_DISTANCE
+        {
+            return None;
+        }
+
+        let ttc = time_to_collision(ours, &track.kinematics)?;
+        if ttc > TTC_WARNING_THRESHOLD {
+            return None;
+        }
+
+        if track
+            .warned_at
+            .is_some_and(|warned_at| at.duration_since(warned_at) < WARNING_INTERVAL)
+        {
+            return None;
+        }
+
+        track.warned_at = Some(at);
+
+        Some(ttc)
+    }
+
+    /// Returns the time-to-collision with the peer if we should be warned about it now,
+    /// predicted from our current position and motion.
+    pub fn check_now(&mut self, peer_id: &PeerId, gps: &Gps) -> Option<Duration> {
+        let ours = Kinematics::ours(gps)?;
+        let ttc = self.check(peer_id, &ours, Instant::now())?;
+
+        tracing::warn!(
+            "Predicted collision with {} in {:.1} s",
+            peer_id,
+            ttc.as_secs_f64()
+        );
+
+        Some(ttc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN: Location = Location {
+        latitude: 14.5995,
+        longitude: 120.9842,
+    };
+
+    /// Returns the location the given meters east and north of the origin.
+    fn offset(east: f64, north: f64) -> Location {
+        let meters_per_degree = 6_378_137.0_f64.to_radians();
+        Location {
+            latitude: ORIGIN.latitude + north / meters_per_degree,
+            longitude: ORIGIN.longitude
+                + east / (meters_per_degree * ORIGIN.latitude.to_radians().cos()),
+        }
+    }
+
+    /// Returns us at the origin, heading north at the given speed.
+    fn ours_heading_north(speed: f64) -> Kinematics {
+        Kinematics {
+            location: ORIGIN,
+            speed,
+            heading: Some(0.0),
+        }
+    }
+
+    fn assert_ttc(ttc: Option<Duration>, expected_secs: f64) {
+        let ttc = ttc.expect("expected a collision to be predicted");
+        assert!(
+            (ttc.as_secs_f64() - expected_secs).abs() < 0.05,
+            "expected a time-to-collision of {} s, got {:?}",
+            expected_secs,
+            ttc
+        );
+    }
+
+    #[test]
+    fn head_on_approach() {
+        let mut monitor = CollisionMonitor::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        monitor.update_peer(peer_id, offset(0.0, 60.0), 10.0, Some(180.0), now);
+
+        // Closing at 20 m/s, from 60 m to the collision distance of 5 m
+        assert_ttc(
+            monitor.check(&peer_id, &ours_heading_north(10.0), now),
+            2.75,
+        );
+    }
+
+    #[test]
+    fn parallel_vehicles() {
+        let mut monitor = CollisionMonitor::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        monitor.update_peer(peer_id, offset(10.0, 0.0), 10.0, Some(0.0), now);
+
+        assert_eq!(
+            monitor.check(&peer_id, &ours_heading_north(10.0), now),
+            None
+        );
+    }
+
+    #[test]
+    fn diverging_vehicles() {
+        let mut monitor = CollisionMonitor::default();
+        let now = Instant::now();
+
+        // Pulling away ahead of us
+        let ahead = PeerId::random();
+        monitor.update_peer(ahead, offset(0.0, 30.0), 15.0, Some(0.0), now);
+        assert_eq!(monitor.check(&ahead, &ours_heading_north(10.0), now), None);
+
+        // Going away from us, even though it is already close
+        let behind = PeerId::random();
+        monitor.update_peer(behind, offset(0.0, -4.0), 10.0, Some(180.0), now);
+        assert_eq!(monitor.check(&behind, &ours_heading_north(10.0), now), None);
+    }
+
+    #[test]
+    fn stationary_peer_in_our_path() {
+        let mut monitor = CollisionMonitor::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        // A stopped peer needs no heading for its path to be known
+        monitor.update_peer(peer_id, offset(0.0, 30.0), 0.0, None, now);
+
+        assert_ttc(monitor.check(&peer_id, &ours_heading_north(10.0), now), 2.5);
+    }
+
+    #[test]
+    fn heading_estimated_from_location_changes() {
+        let mut monitor = CollisionMonitor::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+        let ours = ours_heading_north(10.0);
+
+        monitor.update_peer(peer_id, offset(0.0, 80.0), 10.0, None, now);
+
+        // Its path is unknown until it moves
+        assert_eq!(monitor.check(&peer_id, &ours, now), None);
+
+        let later = now + Duration::from_secs(1);
+        monitor.update_peer(peer_id, offset(0.0, 70.0), 10.0, None, later);
+
+        // Estimated to be heading south, towards us
+        assert_ttc(monitor.check(&peer_id, &ours, later), 3.25);
+    }
+
+    #[test]
+    fn heading_estimate_ignores_jitter() {
+        let mut monitor = CollisionMonitor::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        monitor.update_peer(peer_id, offset(0.0, 60.0), 10.0, None, now);
+        monitor.update_peer(
+            peer_id,
+            offset(0.0, 58.0),
+            10.0,
+            None,
+            now + Duration::from_secs(1),
+        );
+
+        assert_eq!(
+            monitor.check(
+                &peer_id,
+                &ours_heading_north(10.0),
+                now + Duration::from_secs(1)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn track_expires() {
+        let mut monitor = CollisionMonitor::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+        let ours = ours_heading_north(10.0);
+
+        monitor.update_peer(peer_id, offset(0.0, 80.0), 10.0, None, now);
+        monitor.update_peer(peer_id, offset(0.0, 70.0), 10.0, None, now);
+
+        // Not updated for too long
+        let expired_at = now + MAX_TRACK_AGE + Duration::from_secs(1);
+        assert_eq!(monitor.check(&peer_id, &ours, expired_at), None);
+
+        // Updated again, but the old estimated heading is no longer trusted
+        monitor.update_peer(peer_id, offset(0.0, 60.0), 10.0, None, expired_at);
+        assert_eq!(monitor.check(&peer_id, &ours, expired_at), None);
+    }
+
+    #[test]
+    fn repeated_warnings_are_suppressed() {
+        let mut monitor = CollisionMonitor::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+        let ours = ours_heading_north(10.0);
+
+        monitor.update_peer(peer_id, offset(0.0, 60.0), 10.0, Some(180.0), now);
+        assert!(monitor.check(&peer_id, &ours, now).is_some());
+
+        let soon = now + Duration::from_secs(1);
+        monitor.update_peer(peer_id, offset(0.0, 60.0), 10.0, Some(180.0), soon);
+        assert_eq!(monitor.check(&peer_id, &ours, soon), None);
+
+        let later = now + WARNING_INTERVAL;
+        monitor.update_peer(peer_id, offset(0.0, 60.0), 10.0, Some(180.0), later);
+        assert!(monitor.check(&peer_id, &ours, later).is_some());
+    }
+}
@@ -2,7 +2,7 @@ use std::{env, path::PathBuf};
 
 use gtk::glib;
 
-use crate::{location::Location, APP_ID};
+use crate::{gpsd, location::Location, APP_ID};
 
 pub fn is_stt_enabled() -> bool {
     env::var("STT").is_ok_and(|s| s == "1")
@@ -12,6 +12,16 @@ pub fn is_gps_enabled() -> bool {
     env::var("GPS").is_ok_and(|s| s == "1")
 }
 
+/// Whether to publish messages as JSON instead of CBOR, for debugging.
+pub fn is_wire_json_enabled() -> bool {
+    env::var("WIRE_JSON").is_ok_and(|s| s == "1")
+}
+
+/// Address of the gpsd to get the location from, which may also be a fake one for testing.
+pub fn gpsd_address() -> String {
+    env::var("GPSD_ADDRESS").unwrap_or_else(|_| gpsd::DEFAULT_ADDRESS.to_string())
+}
+
 pub fn name() -> String {
     env::var("NAME").unwrap_or_else(|_| "Anonymous".to_string())
 }
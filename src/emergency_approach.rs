@@ -0,0 +1,334 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use gtk::glib;
+use libp2p::PeerId;
+
+use crate::{authority::PeerRole, gps::Gps, location::Location, peer::Peer};
+
+/// Distance within which we are warned about an approaching emergency vehicle.
+const WARNING_DISTANCE: f64 = 1000.0;
+/// Slowest speed at which an emergency vehicle is considered to be approaching, in meters per
+/// second.
+const MIN_APPROACH_SPEED: f64 = 1.0;
+/// Interval between repeated prompts about the same emergency vehicle.
+const PROMPT_INTERVAL: Duration = Duration::from_secs(30);
+/// Largest angle between our heading and the direction to a vehicle for it to be ahead of us.
+const AHEAD_MAX_ANGLE: f64 = 90.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "DeltaApproachDirection")]
+pub enum ApproachDirection {
+    Ahead,
+    Behind,
+    /// Our heading is not known yet.
+    Unknown,
+}
+
+/// Emergency vehicle closing in on us.
+#[derive(Debug, Clone, Copy)]
+pub struct EmergencyApproach {
+    pub direction: ApproachDirection,
+    /// Distance to the vehicle in meters.
+    pub distance: f64,
+    /// Estimated time until the vehicle reaches us.
+    pub eta: Duration,
+}
+
+#[derive(Debug)]
+struct VehicleState {
+    distance: f64,
+    updated_at: Instant,
+    prompted_at: Option<Instant>,
+}
+
+/// Follows emergency vehicles en route to tell whether they are approaching us.
+#[derive(Debug, Default)]
+pub struct EmergencyApproachTracker {
+    vehicles: HashMap<PeerId, VehicleState>,
+}
+
+impl EmergencyApproachTracker {
+    /// Follows the peer if it is a verified emergency vehicle en route, returning its approach
+    /// if we should be prompted to move aside.
+    pub fn update_peer(&mut self, peer: &Peer, gps: &Gps) -> Option<EmergencyApproach> {
+        // Only trust verified emergency services, so no one else can make us pull over
+        if !peer.is_emergency_en_route() || peer.role() != PeerRole::EmergencyServices {
+            self.remove(peer.id());
+            return None;
+        }
+
+        let (Some(vehicle_location), Some(our_location)) = (peer.location(), gps.location()) else {
+            return None;
+        };
+
+        let approach = self.update(
+            *peer.id(),
+            &vehicle_location,
+            peer.speed(),
+            &our_location,
+            gps.known_heading(),
+            Instant::now(),
+        )?;
+
+        tracing::debug!(
+            "Emergency vehicle {} approaching {:?}, {:.0} m away",
+            peer.id(),
+            approach.direction,
+            approach.distance
+        );
+
+        Some(approach)
+    }
+
+    /// Updates the position of an emergency vehicle, received at the given time, returning its
+    /// approach if we should be prompted to move aside.
+    ///
+    /// The closing speed is measured from how the distance changed since the last update, so
+    /// no prompt is given on the first one.
+    fn update(
+        &mut self,
+        peer_id: PeerId,
+        vehicle_location: &Location,
+        vehicle_speed: f64,
+        our_location: &Location,
+        our_heading: Option<f64>,
+        now: Instant,
+    ) -> Option<EmergencyApproach> {
+        let distance = our_location.distance(vehicle_location);
+
+        let Some(state) = self.vehicles.get_mut(&peer_id) else {
+            self.vehicles.insert(
+                peer_id,
+                VehicleState {
+                    distance,
+                    updated_at: now,
+                    prompted_at: None,
+                },
+            );
+            return None;
+        };
+
+        let elapsed = now.duration_since(state.updated_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+
+        let closing_speed = (state.distance - distance) / elapsed;
+
+        state.distance = distance;
+        state.updated_at = now;
+
+        if distance > WARNING_DISTANCE
+            || vehicle_speed < MIN_APPROACH_SPEED
+            || closing_speed < MIN_APPROACH_SPEED
+        {
+            return None;
+        }
+
+        if state
+            .prompted_at
+            .is_some_and(|prompted_at| now.duration_since(prompted_at) < PROMPT_INTERVAL)
+        {
+            return None;
+        }
+
+        state.prompted_at = Some(now);
+
+        let direction = match our_heading {
+            Some(heading) => {
+                let angle =
+                    (our_location.bearing(vehicle_location) - heading + 540.0) % 360.0 - 180.0;
+                if angle.abs() <= AHEAD_MAX_ANGLE {
+                    ApproachDirection::Ahead
+                } else {
+                    ApproachDirection::Behind
+                }
+            }
+            None => ApproachDirection::Unknown,
+        };
+
+        Some(EmergencyApproach {
+            direction,
+            distance,
+            eta: Duration::from_secs_f64(distance / closing_speed),
+        })
+    }
+
+    /// Stops following the vehicle, such as when it is no longer en route.
+    fn remove(&mut self, peer_id: &PeerId) {
+        self.vehicles.remove(peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN: Location = Location {
+        latitude: 14.5995,
+        longitude: 120.9842,
+    };
+
+    /// Returns the location the given meters north of the origin.
+    fn north_of_origin(north: f64) -> Location {
+        Location {
+            latitude: ORIGIN.latitude + north / 6_378_137.0_f64.to_radians(),
+            longitude: ORIGIN.longitude,
+        }
+    }
+
+    /// Moves the vehicle from one distance north of us to another over a second, returning the
+    /// approach reported on the second update.
+    fn track(
+        tracker: &mut EmergencyApproachTracker,
+        peer_id: PeerId,
+        from: f64,
+        to: f64,
+        our_heading: Option<f64>,
+        at: Instant,
+    ) -> Option<EmergencyApproach> {
+        let first = tracker.update(
+            peer_id,
+            &north_of_origin(from),
+            20.0,
+            &ORIGIN,
+            our_heading,
+            at,
+        );
+        assert!(first.is_none(), "no prompt is expected on the first update");
+
+        tracker.update(
+            peer_id,
+            &north_of_origin(to),
+            20.0,
+            &ORIGIN,
+            our_heading,
+            at + Duration::from_secs(1),
+        )
+    }
+
+    #[test]
+    fn approaching() {
+        let mut tracker = EmergencyApproachTracker::default();
+
+        let approach = track(
+            &mut tracker,
+            PeerId::random(),
+            520.0,
+            500.0,
+            Some(0.0),
+            Instant::now(),
+        )
+        .expect("expected an approach");
+
+        assert_eq!(approach.direction, ApproachDirection::Ahead);
+        assert!((approach.distance - 500.0).abs() < 0.1);
+        // Closing at 20 m/s from 500 m away
+        assert!((approach.eta.as_secs_f64() - 25.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn approach_direction() {
+        let mut tracker = EmergencyApproachTracker::default();
+        let now = Instant::now();
+
+        let from_behind = track(
+            &mut tracker,
+            PeerId::random(),
+            520.0,
+            500.0,
+            Some(180.0),
+            now,
+        );
+        assert_eq!(from_behind.unwrap().direction, ApproachDirection::Behind);
+
+        let unknown = track(&mut tracker, PeerId::random(), 520.0, 500.0, None, now);
+        assert_eq!(unknown.unwrap().direction, ApproachDirection::Unknown);
+    }
+
+    #[test]
+    fn receding() {
+        let mut tracker = EmergencyApproachTracker::default();
+
+        let approach = track(
+            &mut tracker,
+            PeerId::random(),
+            500.0,
+            520.0,
+            Some(0.0),
+            Instant::now(),
+        );
+        assert!(approach.is_none());
+    }
+
+    #[test]
+    fn beyond_warning_distance() {
+        let mut tracker = EmergencyApproachTracker::default();
+
+        let approach = track(
+            &mut tracker,
+            PeerId::random(),
+            1520.0,
+            1500.0,
+            Some(0.0),
+            Instant::now(),
+        );
+        assert!(approach.is_none());
+    }
+
+    #[test]
+    fn repeated_prompts_are_throttled() {
+        let mut tracker = EmergencyApproachTracker::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        assert!(track(&mut tracker, peer_id, 900.0, 880.0, Some(0.0), now).is_some());
+
+        let soon = now + Duration::from_secs(2);
+        let approach = tracker.update(
+            peer_id,
+            &north_of_origin(860.0),
+            20.0,
+            &ORIGIN,
+            Some(0.0),
+            soon,
+        );
+        assert!(approach.is_none());
+
+        let later = now + Duration::from_secs(1) + PROMPT_INTERVAL;
+        let approach = tracker.update(
+            peer_id,
+            &north_of_origin(300.0),
+            20.0,
+            &ORIGIN,
+            Some(0.0),
+            later,
+        );
+        assert!(approach.is_some());
+    }
+
+    #[test]
+    fn removed_vehicle_is_followed_again_from_scratch() {
+        let mut tracker = EmergencyApproachTracker::default();
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        assert!(track(&mut tracker, peer_id, 520.0, 500.0, Some(0.0), now).is_some());
+
+        tracker.remove(&peer_id);
+
+        // Its previous distance is forgotten, so the next update can't tell its closing speed
+        let approach = tracker.update(
+            peer_id,
+            &north_of_origin(300.0),
+            20.0,
+            &ORIGIN,
+            Some(0.0),
+            now + Duration::from_secs(2),
+        );
+        assert!(approach.is_none());
+    }
+}
@@ -1,11 +1,6 @@
-use std::{io, process::Command as StdCommand, time::Duration};
+use std::time::Duration;
 
-use anyhow::{bail, Result};
-use async_process::{Child, Command, Stdio};
-use futures_util::{
-    io::{AsyncBufReadExt, BufReader},
-    StreamExt,
-};
+use anyhow::Result;
 use gtk::{
     glib::{self, clone},
     prelude::*,
@@ -14,11 +9,27 @@ use gtk::{
 use isahc::AsyncReadResponseExt;
 use serde::Deserialize;
 
-use crate::{config, location::Location};
+use crate::{
+    config,
+    gpsd::{GpsdClient, Report, Sky, Tpv},
+    location::Location,
+};
 
-const GPSD_DEVICE_PATH: &str = "/dev/ttyAMA0";
+/// Time to wait for a location from gpsd before falling back to the IP location.
 const GPSD_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Distance to move before the direction of travel is updated, so GPS jitter is ignored.
+const HEADING_MIN_DISTANCE: f64 = 10.0;
+
+/// Speed below which the course reported by gpsd is ignored, in meters per second.
+const MIN_TRACK_SPEED: f64 = 0.5;
+
+/// Value of the heading, horizontal accuracy and dilutions of precision when unknown,
+/// following GeoClue.
+pub const UNKNOWN: f64 = -1.0;
+/// Value of the altitude when unknown, following GeoClue.
+pub const UNKNOWN_ALTITUDE: f64 = f64::MIN;
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
 #[enum_type(name = "DeltaFixMode")]
 pub enum FixMode {
@@ -28,17 +39,6 @@ pub enum FixMode {
     ThreeD,
 }
 
-#[derive(Debug, Deserialize)]
-struct RawData {
-    device: Option<String>,
-    mode: Option<i32>,
-    #[serde(rename = "lat")]
-    latitude: Option<f64>,
-    #[serde(rename = "lon")]
-    longitude: Option<f64>,
-    speed: Option<f64>,
-}
-
 mod imp {
     use std::cell::{Cell, RefCell};
 
@@ -54,8 +54,35 @@ mod imp {
         /// Speed in meters per second
         #[property(get)]
         pub(super) speed: Cell<f64>,
+        /// Direction of travel in degrees clockwise from north, or [`UNKNOWN`]
+        #[property(get)]
+        pub(super) heading: Cell<f64>,
+        /// Altitude above mean sea level in meters, or [`UNKNOWN_ALTITUDE`]
+        #[property(get)]
+        pub(super) altitude: Cell<f64>,
+        /// Radius in meters within which the actual location likely is, or [`UNKNOWN`]
+        #[property(get)]
+        pub(super) horizontal_accuracy: Cell<f64>,
+        /// Number of satellites seen
+        #[property(get)]
+        pub(super) satellites_visible: Cell<u32>,
+        /// Number of satellites used in the fix
+        #[property(get)]
+        pub(super) satellites_used: Cell<u32>,
+        /// Horizontal dilution of precision, or [`UNKNOWN`]
+        #[property(get)]
+        pub(super) hdop: Cell<f64>,
+        /// Vertical dilution of precision, or [`UNKNOWN`]
+        #[property(get)]
+        pub(super) vdop: Cell<f64>,
+        /// Position (3D) dilution of precision, or [`UNKNOWN`]
+        #[property(get)]
+        pub(super) pdop: Cell<f64>,
 
-        pub(super) child: RefCell<Option<Child>>,
+        /// Location the heading is estimated from, when not reported by gpsd.
+        pub(super) heading_origin: Cell<Option<Location>>,
+
+        pub(super) gpsd_handle: RefCell<Option<glib::JoinHandle<()>>>,
     }
 
     #[glib::object_subclass]
@@ -71,19 +98,31 @@ mod imp {
 
             let obj = self.obj();
 
+            self.heading.set(UNKNOWN);
+            self.altitude.set(UNKNOWN_ALTITUDE);
+            self.horizontal_accuracy.set(UNKNOWN);
+            self.hdop.set(UNKNOWN);
+            self.vdop.set(UNKNOWN);
+            self.pdop.set(UNKNOWN);
+
             if config::is_gps_enabled() {
                 tracing::debug!("GPS is enabled, initializing GPS");
 
+                obj.start_gpsd_client();
+
                 glib::spawn_future_local(clone!(
                     #[weak]
                     obj,
                     async move {
-                        if let Err(err) = obj.init_gpsd().await {
-                            tracing::error!("Failed to initialize GPSD: {:?}", err);
+                        glib::timeout_future(GPSD_TIMEOUT).await;
+
+                        // Keep watching gpsd, so its location is used once it gets a fix
+                        if obj.location().is_none() {
+                            tracing::warn!("No location from gpsd, falling back to IP location");
 
                             match ip_location().await {
                                 Ok(location) => {
-                                    obj.set_location(Some(location));
+                                    obj.set_location(Some(location), None);
 
                                     tracing::debug!("Got location from IP: {:?}", location);
                                 }
@@ -97,15 +136,13 @@ mod imp {
             }
 
             if let Some(location) = config::location() {
-                obj.set_location(Some(location));
+                obj.set_location(Some(location), None);
             }
         }
 
         fn dispose(&self) {
-            if let Some(mut child) = self.child.take() {
-                if let Err(err) = child.kill() {
-                    tracing::error!("Failed to kill gpspipe: {:?}", err);
-                }
+            if let Some(handle) = self.gpsd_handle.take() {
+                handle.abort();
             }
         }
     }
@@ -121,56 +158,59 @@ impl Gps {
     }
 
     pub fn override_location(&self, location: Option<Location>) {
-        self.set_location(location);
+        self.set_location(location, None);
+    }
+
+    /// Returns the direction of travel in degrees clockwise from north, or `None` if unknown.
+    ///
+    /// Without a course from gpsd, it is estimated from the recent fixes once we moved enough
+    /// to tell.
+    pub fn known_heading(&self) -> Option<f64> {
+        let heading = self.heading();
+        (heading != UNKNOWN).then_some(heading)
+    }
+
+    /// Returns the altitude above mean sea level in meters, or `None` if unknown.
+    pub fn known_altitude(&self) -> Option<f64> {
+        let altitude = self.altitude();
+        (altitude != UNKNOWN_ALTITUDE).then_some(altitude)
     }
 
-    async fn init_gpsd(&self) -> Result<()> {
-        ensure_gpsd()?;
+    /// Returns the horizontal accuracy radius in meters, or `None` if unknown.
+    pub fn known_horizontal_accuracy(&self) -> Option<f64> {
+        let horizontal_accuracy = self.horizontal_accuracy();
+        (horizontal_accuracy != UNKNOWN).then_some(horizontal_accuracy)
+    }
 
-        let mut child = Command::new("gpspipe")
-            .stdout(Stdio::piped())
-            .arg("-w")
-            .spawn()?;
+    fn start_gpsd_client(&self) {
+        let imp = self.imp();
 
-        let stdout = child.stdout.take().unwrap();
-        let reader = BufReader::new(stdout);
+        let client = GpsdClient::new(config::gpsd_address());
 
-        let handle = glib::spawn_future_local(clone!(
+        let handle_report = clone!(
             #[weak(rename_to = obj)]
             self,
-            async move {
-                let mut lines = reader.lines();
-
-                while let Some(line) = lines.next().await {
-                    if let Err(err) = obj.handle_gpspipe_output(line) {
-                        tracing::error!("Failed to handle gpspipe output: {:?}", err);
-                    }
-                }
+            move |report: Report| match report {
+                Report::Tpv(tpv) => obj.handle_tpv(tpv),
+                Report::Sky(sky) => obj.handle_sky(sky),
+                Report::Other => {}
             }
-        ));
-
-        glib::timeout_future(GPSD_TIMEOUT).await;
-
-        if self.location().is_none() {
-            handle.abort();
-
-            bail!("Failed to get location from device");
-        }
+        );
 
-        Ok(())
+        let handle = glib::spawn_future_local(async move {
+            client.run(handle_report).await;
+        });
+        imp.gpsd_handle.replace(Some(handle));
     }
 
-    fn handle_gpspipe_output(&self, line: io::Result<String>) -> Result<()> {
-        let line = line?;
-        let data = serde_json::from_str::<RawData>(&line)?;
-
-        if let Some(device) = &data.device {
-            tracing::debug!("Received data from device: {}", device);
+    fn handle_tpv(&self, tpv: Tpv) {
+        if let Some(device) = &tpv.device {
+            tracing::trace!("Received TPV from device: {}", device);
         }
 
-        if let Some(mode) = data.mode {
+        if let Some(mode) = tpv.mode {
             self.set_fix_mode(match mode {
-                1 => FixMode::None,
+                0 | 1 => FixMode::None,
                 2 => FixMode::TwoD,
                 3 => FixMode::ThreeD,
                 _ => {
@@ -180,28 +220,66 @@ impl Gps {
             });
         }
 
-        match (data.latitude, data.longitude) {
+        if let Some(speed) = tpv.speed {
+            self.set_speed(speed);
+        }
+
+        // The course is meaningless when standing still, so keep the last one
+        let track = tpv.track.filter(|_| self.speed() >= MIN_TRACK_SPEED);
+
+        match (tpv.latitude, tpv.longitude) {
             (Some(latitude), Some(longitude)) => {
                 if latitude == 0.0 && longitude == 0.0 {
-                    self.set_location(None);
+                    self.set_location(None, None);
                 } else {
-                    self.set_location(Some(Location {
-                        latitude,
-                        longitude,
-                    }));
+                    self.set_location(
+                        Some(Location {
+                            latitude,
+                            longitude,
+                        }),
+                        track,
+                    );
+                }
+            }
+            (None, None) => {
+                if let Some(track) = track {
+                    self.set_heading(track);
                 }
             }
-            (None, None) => {}
             _ => {
-                tracing::warn!("Invalid GPS data: {:?}", data);
+                tracing::warn!("Invalid TPV: {:?}", tpv);
             }
         }
 
-        if let Some(speed) = data.speed {
-            self.set_speed(speed);
+        if let Some(altitude) = tpv.alt_msl.or(tpv.alt) {
+            self.set_altitude(altitude);
+        }
+
+        if let Some(eph) = tpv.eph {
+            self.set_horizontal_accuracy(eph);
+        }
+    }
+
+    fn handle_sky(&self, sky: Sky) {
+        if let Some(n_visible) = sky.n_visible() {
+            self.set_satellites_visible(n_visible);
+        }
+
+        if let Some(n_used) = sky.n_used() {
+            self.set_satellites_used(n_used);
+        }
+
+        if let Some(hdop) = sky.hdop {
+            self.set_hdop(hdop);
+        }
+
+        if let Some(vdop) = sky.vdop {
+            self.set_vdop(vdop);
         }
 
-        Ok(())
+        if let Some(pdop) = sky.pdop {
+            self.set_pdop(pdop);
+        }
     }
 
     fn set_fix_mode(&self, fix_mode: FixMode) {
@@ -215,17 +293,143 @@ impl Gps {
         self.notify_fix_mode();
     }
 
-    fn set_location(&self, location: Option<Location>) {
+    /// Sets the location, along with the course over ground if known.
+    fn set_location(&self, location: Option<Location>, track: Option<f64>) {
         let imp = self.imp();
 
         if location == self.location() {
+            if let Some(track) = track {
+                self.set_heading(track);
+            }
             return;
         }
 
         imp.location.replace(location);
+        self.update_heading(location, track);
+
+        if location.is_none() {
+            self.set_altitude(UNKNOWN_ALTITUDE);
+            self.set_horizontal_accuracy(UNKNOWN);
+        }
+
         self.notify_location();
     }
 
+    /// Updates the heading to the course over ground if known, otherwise to the bearing from
+    /// a location far enough away.
+    fn update_heading(&self, location: Option<Location>, track: Option<f64>) {
+        let imp = self.imp();
+
+        let Some(location) = location else {
+            imp.heading_origin.set(None);
+            self.set_heading(UNKNOWN);
+            return;
+        };
+
+        if let Some(track) = track {
+            imp.heading_origin.set(Some(location));
+            self.set_heading(track);
+            return;
+        }
+
+        match imp.heading_origin.get() {
+            Some(origin) if origin.distance(&location) >= HEADING_MIN_DISTANCE => {
+                imp.heading_origin.set(Some(location));
+                self.set_heading(origin.bearing(&location));
+            }
+            Some(_) => {}
+            None => imp.heading_origin.set(Some(location)),
+        }
+    }
+
+    fn set_heading(&self, heading: f64) {
+        let imp = self.imp();
+
+        if heading == self.heading() {
+            return;
+        }
+
+        imp.heading.set(heading);
+        self.notify_heading();
+    }
+
+    fn set_altitude(&self, altitude: f64) {
+        let imp = self.imp();
+
+        if altitude == self.altitude() {
+            return;
+        }
+
+        imp.altitude.set(altitude);
+        self.notify_altitude();
+    }
+
+    fn set_horizontal_accuracy(&self, horizontal_accuracy: f64) {
+        let imp = self.imp();
+
+        if horizontal_accuracy == self.horizontal_accuracy() {
+            return;
+        }
+
+        imp.horizontal_accuracy.set(horizontal_accuracy);
+        self.notify_horizontal_accuracy();
+    }
+
+    fn set_satellites_visible(&self, satellites_visible: u32) {
+        let imp = self.imp();
+
+        if satellites_visible == self.satellites_visible() {
+            return;
+        }
+
+        imp.satellites_visible.set(satellites_visible);
+        self.notify_satellites_visible();
+    }
+
+    fn set_satellites_used(&self, satellites_used: u32) {
+        let imp = self.imp();
+
+        if satellites_used == self.satellites_used() {
+            return;
+        }
+
+        imp.satellites_used.set(satellites_used);
+        self.notify_satellites_used();
+    }
+
+    fn set_hdop(&self, hdop: f64) {
+        let imp = self.imp();
+
+        if hdop == self.hdop() {
+            return;
+        }
+
+        imp.hdop.set(hdop);
+        self.notify_hdop();
+    }
+
+    fn set_vdop(&self, vdop: f64) {
+        let imp = self.imp();
+
+        if vdop == self.vdop() {
+            return;
+        }
+
+        imp.vdop.set(vdop);
+        self.notify_vdop();
+    }
+
+    fn set_pdop(&self, pdop: f64) {
+        let imp = self.imp();
+
+        if pdop == self.pdop() {
+            return;
+        }
+
+        imp.pdop.set(pdop);
+        self.notify_pdop();
+    }
+
     fn set_speed(&self, speed: f64) {
         let imp = self.imp();
 
@@ -244,19 +448,6 @@ impl Default for Gps {
     }
 }
 
-fn ensure_gpsd() -> Result<()> {
-    let status = StdCommand::new("gpsd")
-        .arg(GPSD_DEVICE_PATH)
-        .spawn()?
-        .wait()?;
-
-    if !status.success() {
-        bail!("Failed to start gpsd: {:?}", status.code());
-    }
-
-    Ok(())
-}
-
 async fn ip_location() -> Result<Location> {
     #[derive(Deserialize)]
     struct Response {
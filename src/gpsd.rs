@@ -0,0 +1,323 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_std::{io::BufReader, net::TcpStream};
+use futures_util::{AsyncBufReadExt, AsyncWriteExt, StreamExt};
+use gtk::glib;
+use serde::Deserialize;
+
+/// Address gpsd listens on by default.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:2947";
+
+/// Enables streaming of reports as JSON objects, one per line.
+const WATCH_COMMAND: &[u8] = b"?WATCH={\"enable\":true,\"json\":true}\n";
+/// Asks for the last fix, which gpsd answers even without a receiver attached.
+const POLL_COMMAND: &[u8] = b"?POLL;\n";
+
+/// Delay before the first reconnection attempt, doubled on each failed one.
+const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Time without any line from gpsd after which it is polled, and after which the connection is
+/// assumed to be dead if it doesn't answer either.
+///
+/// gpsd reports at least once a second while a receiver is attached, but stays quiet without
+/// one, so it is only polled rather than reconnected to right away.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Report sent by gpsd, of which only the ones we use are parsed.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "class")]
+pub enum Report {
+    #[serde(rename = "TPV")]
+    Tpv(Tpv),
+    #[serde(rename = "SKY")]
+    Sky(Sky),
+    #[serde(other)]
+    Other,
+}
+
+/// Time-position-velocity report.
+#[derive(Debug, Deserialize)]
+pub struct Tpv {
+    pub device: Option<String>,
+    pub mode: Option<i32>,
+    #[serde(rename = "lat")]
+    pub latitude: Option<f64>,
+    #[serde(rename = "lon")]
+    pub longitude: Option<f64>,
+    /// Speed over ground in meters per second
+    pub speed: Option<f64>,
+    /// Course over ground in degrees clockwise from true north
+    pub track: Option<f64>,
+    /// Altitude in meters, only reported by older gpsd versions
+    pub alt: Option<f64>,
+    /// Altitude above mean sea level in meters
+    #[serde(rename = "altMSL")]
+    pub alt_msl: Option<f64>,
+    /// Estimated horizontal position error in meters
+    pub eph: Option<f64>,
+}
+
+/// Sky view report, with the satellites and the dilution of precision.
+#[derive(Debug, Deserialize)]
+pub struct Sky {
+    #[serde(rename = "nSat")]
+    n_satellites: Option<u32>,
+    #[serde(rename = "uSat")]
+    n_used_satellites: Option<u32>,
+    #[serde(default)]
+    satellites: Vec<Satellite>,
+    /// Horizontal dilution of precision
+    pub hdop: Option<f64>,
+    /// Vertical dilution of precision
+    pub vdop: Option<f64>,
+    /// Position (3D) dilution of precision
+    pub pdop: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Satellite {
+    #[serde(default)]
+    used: bool,
+}
+
+impl Sky {
+    /// Returns the number of satellites seen, if reported.
+    pub fn n_visible(&self) -> Option<u32> {
+        self.n_satellites
+            .or_else(|| (!self.satellites.is_empty()).then_some(self.satellites.len() as u32))
+    }
+
+    /// Returns the number of satellites used in the fix, if reported.
+    pub fn n_used(&self) -> Option<u32> {
+        self.n_used_satellites.or_else(|| {
+            (!self.satellites.is_empty())
+                .then(|| self.satellites.iter().filter(|s| s.used).count() as u32)
+        })
+    }
+}
+
+/// Client for the JSON protocol of gpsd over TCP.
+#[derive(Debug)]
+pub struct GpsdClient {
+    address: String,
+    idle_timeout: Duration,
+}
+
+impl GpsdClient {
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            idle_timeout: IDLE_TIMEOUT,
+        }
+    }
+
+    /// Watches for reports until the future is dropped, reconnecting with exponential backoff
+    /// whenever the connection fails or is closed.
+    pub async fn run(&self, mut handle_report: impl FnMut(Report)) {
+        let mut backoff = RECONNECT_INITIAL_BACKOFF;
+
+        loop {
+            let mut received_report = false;
+
+            let res = self
+                .watch(|report| {
+                    received_report = true;
+                    handle_report(report);
+                })
+                .await;
+
+            match res {
+                Ok(()) => tracing::warn!("gpsd at {} closed the connection", self.address),
+                Err(err) => {
+                    tracing::warn!("Lost connection to gpsd at {}: {:?}", self.address, err)
+                }
+            }
+
+            // Only back off further if gpsd keeps failing before sending anything
+            if received_report {
+                backoff = RECONNECT_INITIAL_BACKOFF;
+            }
+
+            tracing::debug!("Reconnecting to gpsd in {:?}", backoff);
+
+            glib::timeout_future(backoff).await;
+            backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+        }
+    }
+
+    /// Connects to gpsd and handles reports until the connection is closed or stops
+    /// answering.
+    async fn watch(&self, mut handle_report: impl FnMut(Report)) -> Result<()> {
+        let mut stream = TcpStream::connect(&self.address)
+            .await
+            .with_context(|| format!("Failed to connect to {}", self.address))?;
+        stream.write_all(WATCH_COMMAND).await?;
+
+        tracing::debug!("Watching gpsd at {}", self.address);
+
+        let mut lines = BufReader::new(stream.clone()).lines();
+        let mut is_polling = false;
+        loop {
+            let line = match glib::future_with_timeout(self.idle_timeout, lines.next()).await {
+                Ok(Some(line)) => line?,
+                Ok(None) => break,
+                Err(_) if !is_polling => {
+                    tracing::debug!("Received nothing for {:?}, polling gpsd", self.idle_timeout);
+
+                    stream.write_all(POLL_COMMAND).await?;
+                    is_polling = true;
+                    continue;
+                }
+                Err(_) => bail!("Received no answer to poll for {:?}", self.idle_timeout),
+            };
+
+            is_polling = false;
+
+            match serde_json::from_str::<Report>(&line) {
+                Ok(report) => handle_report(report),
+                Err(err) => tracing::warn!("Failed to parse gpsd report `{}`: {:?}", line, err),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::net::TcpListener;
+    use futures_channel::mpsc;
+
+    use super::*;
+
+    const TPV_LINE: &str = r#"{"class":"TPV","device":"/dev/ttyACM0","mode":3,"lat":14.5995,"lon":120.9842,"speed":12.5,"track":271.3,"altMSL":15.2,"eph":4.1}"#;
+    const SKY_LINE: &str = r#"{"class":"SKY","uSat":2,"satellites":[{"used":true},{"used":true},{"used":false}],"hdop":1.2,"vdop":1.8,"pdop":2.2}"#;
+    const POLL_LINE: &str =
+        r#"{"class":"POLL","time":"2026-01-01T00:00:00.000Z","active":0,"tpv":[],"sky":[]}"#;
+    const VERSION_LINE: &str =
+        r#"{"class":"VERSION","release":"3.25","proto_major":3,"proto_minor":15}"#;
+
+    /// Accepts the next connection from the client, checking that it starts watching.
+    async fn accept_watch(listener: &TcpListener) -> TcpStream {
+        let (stream, _) = glib::future_with_timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("client did not connect in time")
+            .unwrap();
+
+        let mut command = String::new();
+        BufReader::new(stream.clone())
+            .read_line(&mut command)
+            .await
+            .unwrap();
+        assert_eq!(command.as_bytes(), WATCH_COMMAND);
+
+        stream
+    }
+
+    async fn next_report(reports: &mut mpsc::UnboundedReceiver<Report>) -> Report {
+        glib::future_with_timeout(Duration::from_secs(5), reports.next())
+            .await
+            .expect("no report received in time")
+            .unwrap()
+    }
+
+    #[test]
+    fn watch_and_reconnect() {
+        let ctx = glib::MainContext::new();
+        ctx.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let client = GpsdClient::new(listener.local_addr().unwrap().to_string());
+
+            let (tx, mut reports) = mpsc::unbounded();
+            let client_handle = ctx.spawn_local(async move {
+                client
+                    .run(|report| tx.unbounded_send(report).unwrap())
+                    .await;
+            });
+
+            let mut stream = accept_watch(&listener).await;
+            for line in [TPV_LINE, SKY_LINE, VERSION_LINE, "not json"] {
+                stream.write_all(line.as_bytes()).await.unwrap();
+                stream.write_all(b"\n").await.unwrap();
+            }
+
+            let Report::Tpv(tpv) = next_report(&mut reports).await else {
+                panic!("expected a TPV report");
+            };
+            assert_eq!(tpv.device.as_deref(), Some("/dev/ttyACM0"));
+            assert_eq!(tpv.mode, Some(3));
+            assert_eq!(tpv.latitude, Some(14.5995));
+            assert_eq!(tpv.longitude, Some(120.9842));
+            assert_eq!(tpv.speed, Some(12.5));
+            assert_eq!(tpv.track, Some(271.3));
+            assert_eq!(tpv.alt, None);
+            assert_eq!(tpv.alt_msl, Some(15.2));
+            assert_eq!(tpv.eph, Some(4.1));
+
+            let Report::Sky(sky) = next_report(&mut reports).await else {
+                panic!("expected a SKY report");
+            };
+            assert_eq!(sky.n_visible(), Some(3));
+            assert_eq!(sky.n_used(), Some(2));
+            assert_eq!(sky.hdop, Some(1.2));
+            assert_eq!(sky.vdop, Some(1.8));
+            assert_eq!(sky.pdop, Some(2.2));
+
+            assert!(matches!(next_report(&mut reports).await, Report::Other));
+
+            drop(stream);
+
+            let mut stream = accept_watch(&listener).await;
+            stream.write_all(TPV_LINE.as_bytes()).await.unwrap();
+            stream.write_all(b"\n").await.unwrap();
+
+            // The line that failed to parse was skipped
+            assert!(matches!(next_report(&mut reports).await, Report::Tpv(_)));
+
+            client_handle.abort();
+        });
+    }
+
+    #[test]
+    fn poll_when_idle() {
+        let ctx = glib::MainContext::new();
+        ctx.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let mut client = GpsdClient::new(listener.local_addr().unwrap().to_string());
+            client.idle_timeout = Duration::from_millis(200);
+
+            let (tx, mut reports) = mpsc::unbounded();
+            let client_handle = ctx.spawn_local(async move {
+                client
+                    .run(|report| tx.unbounded_send(report).unwrap())
+                    .await;
+            });
+
+            let mut stream = accept_watch(&listener).await;
+            let mut commands = BufReader::new(stream.clone()).lines();
+
+            let command = glib::future_with_timeout(Duration::from_secs(5), commands.next())
+                .await
+                .expect("client did not poll in time")
+                .unwrap()
+                .unwrap();
+            assert_eq!(format!("{command}\n").as_bytes(), POLL_COMMAND);
+
+            stream.write_all(POLL_LINE.as_bytes()).await.unwrap();
+            stream.write_all(b"\n").await.unwrap();
+            assert!(matches!(next_report(&mut reports).await, Report::Other));
+
+            // The answered poll kept the connection open
+            stream.write_all(TPV_LINE.as_bytes()).await.unwrap();
+            stream.write_all(b"\n").await.unwrap();
+            assert!(matches!(next_report(&mut reports).await, Report::Tpv(_)));
+
+            // Without an answer to the next poll, the client gives up and reconnects
+            let _stream = accept_watch(&listener).await;
+
+            client_handle.abort();
+        });
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use gtk::{gio, prelude::*};
+use libp2p::identity::Keypair;
+use once_cell::sync::Lazy;
+
+use crate::{config, utils};
+
+static IDENTITY_FILE: Lazy<gio::File> = Lazy::new(|| {
+    let mut path = config::user_config_dir();
+    path.push("identity.key");
+    gio::File::for_path(path)
+});
+
+/// Loads the stored keypair, generating and storing a new one if there is none yet.
+pub fn load_or_generate() -> Result<Keypair> {
+    match IDENTITY_FILE.load_contents(gio::Cancellable::NONE) {
+        Ok((bytes, _)) => {
+            let keypair = Keypair::from_protobuf_encoding(&bytes)?;
+
+            tracing::debug!(
+                "Loaded identity from {}",
+                IDENTITY_FILE.path().unwrap().display()
+            );
+
+            Ok(keypair)
+        }
+        Err(err) => {
+            if err.matches(gio::IOErrorEnum::NotFound) {
+                regenerate()
+            } else {
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Generates a new keypair and stores it, replacing the previous one.
+///
+/// The new identity is only used by the network once the application is restarted. Any
+/// credential issued to the previous identity no longer applies to the new one.
+pub fn regenerate() -> Result<Keypair> {
+    let keypair = Keypair::generate_ed25519();
+
+    save(&keypair)?;
+
+    tracing::debug!("Generated new identity {}", keypair.public().to_peer_id());
+
+    Ok(keypair)
+}
+
+fn save(keypair: &Keypair) -> Result<()> {
+    let bytes = keypair.to_protobuf_encoding()?;
+    utils::save_bytes(
+        &IDENTITY_FILE,
+        &bytes,
+        gio::FileCreateFlags::PRIVATE | gio::FileCreateFlags::REPLACE_DESTINATION,
+    )
+}
@@ -1,4 +1,7 @@
-use std::cell::RefMut;
+use std::{
+    cell::RefMut,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use futures_util::{AsyncReadExt, AsyncWriteExt};
 use gtk::{gio, glib, subclass::prelude::*};
@@ -12,6 +15,7 @@ mod imp {
     #[derive(Default)]
     pub struct InputStream {
         pub(super) inner: RefCell<Option<Stream>>,
+        pub(super) bytes_read: AtomicU64,
     }
 
     #[glib::object_subclass]
@@ -29,8 +33,12 @@ mod imp {
             buffer: &mut [u8],
             _cancellable: Option<&gio::Cancellable>,
         ) -> Result<usize, glib::Error> {
-            async_std::task::block_on(self.obj().inner().read(buffer))
-                .map_err(|e| glib::Error::new(gio::IOErrorEnum::Failed, &e.to_string()))
+            let n_read = async_std::task::block_on(self.obj().inner().read(buffer))
+                .map_err(|e| glib::Error::new(gio::IOErrorEnum::Failed, &e.to_string()))?;
+
+            self.bytes_read.fetch_add(n_read as u64, Ordering::Relaxed);
+
+            Ok(n_read)
         }
 
         fn close(&self, _cancellable: Option<&gio::Cancellable>) -> Result<(), glib::Error> {
@@ -64,6 +72,11 @@ impl InputStream {
         this
     }
 
+    /// Returns the total number of bytes read from the stream so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.imp().bytes_read.load(Ordering::Relaxed)
+    }
+
     fn inner(&self) -> RefMut<'_, Stream> {
         RefMut::map(self.imp().inner.borrow_mut(), |inner| {
             inner.as_mut().unwrap()
@@ -22,4 +22,17 @@ impl Location {
         (lat1.sin() * lat2.sin() + lat1.cos() * lat2.cos() * (lon2 - lon1).cos()).acos()
             * EARTH_RADIUS
     }
+
+    /// Calculate the initial bearing from this location to another in degrees clockwise
+    /// from north, in the range [0, 360).
+    pub fn bearing(&self, other: &Location) -> f64 {
+        let lat1 = self.latitude.to_radians();
+        let lat2 = other.latitude.to_radians();
+        let delta_lon = (other.longitude - self.longitude).to_radians();
+
+        let y = delta_lon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta_lon.cos();
+
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
 }
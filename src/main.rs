@@ -1,25 +1,42 @@
 #![allow(clippy::new_without_default)]
 #![warn(rust_2018_idioms, clippy::unused_async, clippy::dbg_macro)]
 
+mod alert;
+mod alert_handler;
+mod alert_log;
+mod alert_rate_limiter;
 mod application;
 mod audio_device;
+mod authority;
 mod call;
+mod call_history;
 mod client;
+mod collision;
 mod colors;
 mod config;
+mod emergency_approach;
 mod gps;
+mod gpsd;
+mod identity;
 mod input_stream;
 mod location;
+mod missed_call_list;
 mod output_stream;
 mod peer;
 mod peer_list;
 mod place_finder;
+mod protocol;
+mod push_to_talk;
 mod remote;
 mod settings;
 mod stt;
+mod text_message;
 mod tts;
 mod ui;
 mod utils;
+mod voice_channel;
+mod voice_message;
+mod voice_message_outbox;
 mod wireless_info;
 
 use std::path::Path;
@@ -0,0 +1,213 @@
+use anyhow::Result;
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use libp2p::PeerId;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{config, utils};
+
+static MISSED_CALLS_FILE: Lazy<gio::File> = Lazy::new(|| {
+    let mut path = config::user_config_dir();
+    path.push("missed_calls.json");
+    gio::File::for_path(path)
+});
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MissedCallData {
+    peer_id: PeerId,
+    peer_name: String,
+    /// Time the call came in, in seconds since the Unix epoch.
+    timestamp: i64,
+}
+
+mod missed_call_imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::MissedCall)]
+    pub struct MissedCall {
+        pub(super) peer_id: OnceCell<PeerId>,
+
+        #[property(get, set, construct_only)]
+        pub(super) peer_name: RefCell<String>,
+        #[property(get, set, construct_only)]
+        pub(super) timestamp: Cell<i64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MissedCall {
+        const NAME: &'static str = "DeltaMissedCall";
+        type Type = super::MissedCall;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MissedCall {}
+}
+
+glib::wrapper! {
+    pub struct MissedCall(ObjectSubclass<missed_call_imp::MissedCall>);
+}
+
+impl MissedCall {
+    pub fn new(peer_id: PeerId, peer_name: &str, timestamp: i64) -> Self {
+        let this = glib::Object::builder::<Self>()
+            .property("peer-name", peer_name)
+            .property("timestamp", timestamp)
+            .build();
+        this.imp().peer_id.set(peer_id).unwrap();
+        this
+    }
+
+    pub fn peer_id(&self) -> &PeerId {
+        self.imp().peer_id.get().unwrap()
+    }
+
+    pub fn date_time(&self) -> glib::DateTime {
+        glib::DateTime::from_unix_local(self.timestamp()).unwrap()
+    }
+
+    fn to_data(&self) -> MissedCallData {
+        MissedCallData {
+            peer_id: *self.peer_id(),
+            peer_name: self.peer_name(),
+            timestamp: self.timestamp(),
+        }
+    }
+}
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct MissedCallList {
+        pub(super) list: RefCell<Vec<MissedCall>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MissedCallList {
+        const NAME: &'static str = "DeltaMissedCallList";
+        type Type = super::MissedCallList;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    impl ObjectImpl for MissedCallList {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            if let Err(err) = obj.load() {
+                tracing::error!("Failed to load missed calls: {:?}", err);
+            }
+        }
+    }
+
+    impl ListModelImpl for MissedCallList {
+        fn item_type(&self) -> glib::Type {
+            MissedCall::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.list.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.list
+                .borrow()
+                .get(position as usize)
+                .map(|v| v.upcast_ref::<glib::Object>())
+                .cloned()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Persisted list of incoming calls that were not answered, newest first.
+    pub struct MissedCallList(ObjectSubclass<imp::MissedCallList>)
+        @implements gio::ListModel;
+}
+
+impl MissedCallList {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn insert(&self, missed_call: MissedCall) {
+        self.imp().list.borrow_mut().insert(0, missed_call);
+        self.items_changed(0, 0, 1);
+
+        self.save_and_log();
+    }
+
+    pub fn remove(&self, missed_call: &MissedCall) {
+        let imp = self.imp();
+
+        let position = imp.list.borrow().iter().position(|c| c == missed_call);
+
+        if let Some(position) = position {
+            imp.list.borrow_mut().remove(position);
+            self.items_changed(position as u32, 1, 0);
+
+            self.save_and_log();
+        }
+    }
+
+    pub fn clear(&self) {
+        let n_removed = self.imp().list.take().len();
+
+        if n_removed > 0 {
+            self.items_changed(0, n_removed as u32, 0);
+
+            self.save_and_log();
+        }
+    }
+
+    fn save_and_log(&self) {
+        if let Err(err) = self.save() {
+            tracing::error!("Failed to save missed calls: {:?}", err);
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let data = self
+            .imp()
+            .list
+            .borrow()
+            .iter()
+            .map(|missed_call| missed_call.to_data())
+            .collect::<Vec<_>>();
+        utils::save_json(&MISSED_CALLS_FILE, &data)
+    }
+
+    fn load(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let data = utils::load_json::<Vec<MissedCallData>>(&MISSED_CALLS_FILE)?.unwrap_or_default();
+
+        tracing::debug!(
+            "Loaded {} missed calls from {}",
+            data.len(),
+            MISSED_CALLS_FILE.path().unwrap().display()
+        );
+
+        let prev_len = imp.list.borrow().len();
+        imp.list.replace(
+            data.into_iter()
+                .map(|d| MissedCall::new(d.peer_id, &d.peer_name, d.timestamp))
+                .collect(),
+        );
+        self.items_changed(0, prev_len as u32, self.n_items());
+
+        Ok(())
+    }
+}
+
+impl Default for MissedCallList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
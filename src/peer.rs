@@ -1,7 +1,12 @@
 use gtk::{glib, prelude::*, subclass::prelude::*};
 use libp2p::PeerId;
 
-use crate::{location::Location, wireless_info::SignalQuality};
+use crate::{
+    authority::PeerRole,
+    gps::{UNKNOWN, UNKNOWN_ALTITUDE},
+    location::Location,
+    wireless_info::SignalQuality,
+};
 
 mod imp {
     use std::cell::{Cell, OnceCell, RefCell};
@@ -19,10 +24,28 @@ mod imp {
         pub(super) location: RefCell<Option<Location>>,
         #[property(get, set)]
         pub(super) speed: Cell<f64>,
+        /// Direction of travel in degrees clockwise from north, or [`UNKNOWN`]
+        #[property(get, set)]
+        pub(super) heading: Cell<f64>,
+        /// Altitude above mean sea level in meters, or [`UNKNOWN_ALTITUDE`]
+        #[property(get, set)]
+        pub(super) altitude: Cell<f64>,
+        /// Radius in meters within which the actual location likely is, or [`UNKNOWN`]
+        #[property(get, set)]
+        pub(super) horizontal_accuracy: Cell<f64>,
         #[property(get, set, builder(SignalQuality::default()))]
         pub(super) signal_quality: Cell<SignalQuality>,
         #[property(get, set)]
         pub(super) icon_name: RefCell<String>,
+        /// Name of the voice channel the peer is in.
+        #[property(get, set, nullable)]
+        pub(super) voice_channel: RefCell<Option<String>>,
+        /// Role proven by a credential from a trusted authority.
+        #[property(get, set, builder(PeerRole::default()))]
+        pub(super) role: Cell<PeerRole>,
+        /// Whether the peer is an emergency vehicle on the way to an emergency.
+        #[property(get, set)]
+        pub(super) is_emergency_en_route: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -41,7 +64,11 @@ glib::wrapper! {
 
 impl Peer {
     pub fn new(id: PeerId) -> Self {
-        let this = glib::Object::new::<Self>();
+        let this = glib::Object::builder::<Self>()
+            .property("heading", UNKNOWN)
+            .property("altitude", UNKNOWN_ALTITUDE)
+            .property("horizontal-accuracy", UNKNOWN)
+            .build();
         this.imp().id.set(id).unwrap();
         this
     }
@@ -49,4 +76,10 @@ impl Peer {
     pub fn id(&self) -> &PeerId {
         self.imp().id.get().unwrap()
     }
+
+    /// Returns the direction of travel in degrees clockwise from north, or `None` if unknown.
+    pub fn known_heading(&self) -> Option<f64> {
+        let heading = self.heading();
+        (heading != UNKNOWN).then_some(heading)
+    }
 }
@@ -0,0 +1,562 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{ensure, Context, Result};
+use gtk::glib;
+use libp2p::{
+    identity::{Keypair, PublicKey},
+    PeerId,
+};
+use serde::{de::IgnoredAny, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    alert::{AlertResponseKind, AlertSeverity, AlertType},
+    authority::PeerRole,
+    call::AudioCodec,
+    config,
+    location::Location,
+    wireless_info::SignalQuality,
+};
+
+/// Version of the wire protocol, bumped on incompatible changes to the envelope or its data.
+///
+/// Messages with another version are ignored, as their data may not mean the same.
+pub const PROTOCOL_VERSION: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Compact binary encoding used by default.
+    Cbor,
+    /// Human-readable encoding, useful for debugging.
+    Json,
+}
+
+impl Encoding {
+    fn detect(bytes: &[u8]) -> Self {
+        // JSON envelopes are always objects, while a CBOR map never starts with `{`
+        if bytes.first() == Some(&b'{') {
+            Self::Json
+        } else {
+            Self::Cbor
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MessageId(u64);
+
+impl MessageId {
+    pub fn new_random() -> Self {
+        Self(((glib::random_int() as u64) << 32) | glib::random_int() as u64)
+    }
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SenderInfo {
+    pub name: String,
+    pub app_version: String,
+}
+
+impl SenderInfo {
+    fn ours() -> Self {
+        Self {
+            name: config::name(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Envelope<D = PublishData> {
+    pub version: u16,
+    pub id: MessageId,
+    /// Time the message was created, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub sender: SenderInfo,
+    pub data: D,
+}
+
+impl Envelope {
+    pub fn new(data: PublishData) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            id: MessageId::new_random(),
+            timestamp_ms: now_ms(),
+            sender: SenderInfo::ours(),
+            data,
+        }
+    }
+
+    pub fn encode(&self, encoding: Encoding) -> Result<Vec<u8>> {
+        let bytes = match encoding {
+            Encoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(self, &mut bytes)?;
+                bytes
+            }
+            Encoding::Json => serde_json::to_vec(self)?,
+        };
+
+        Ok(bytes)
+    }
+}
+
+impl Envelope<Option<PublishData>> {
+    /// Decodes an envelope in any supported encoding.
+    ///
+    /// This only fails if the envelope itself is malformed. Data that we don't understand, such as
+    /// variants added by newer versions, is returned as `None`.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        match Encoding::detect(bytes) {
+            Encoding::Cbor => {
+                let envelope = ciborium::from_reader::<Envelope<ciborium::Value>, _>(bytes)
+                    .context("Malformed CBOR envelope")?;
+                Ok(envelope.map_data(|value| value.deserialized()))
+            }
+            Encoding::Json => {
+                let envelope = serde_json::from_slice::<Envelope<serde_json::Value>>(bytes)
+                    .context("Malformed JSON envelope")?;
+                Ok(envelope.map_data(serde_json::from_value))
+            }
+        }
+    }
+}
+
+impl<V> Envelope<V> {
+    fn map_data<D, E>(self, f: impl FnOnce(V) -> Result<D, E>) -> Envelope<Option<D>>
+    where
+        E: fmt::Display,
+    {
+        let data = match f(self.data) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                tracing::debug!(
+                    "Unknown data in message {} from {} (protocol version {}, app version {}): {}",
+                    self.id,
+                    self.sender.name,
+                    self.version,
+                    self.sender.app_version,
+                    err
+                );
+                None
+            }
+        };
+
+        Envelope {
+            version: self.version,
+            id: self.id,
+            timestamp_ms: self.timestamp_ms,
+            sender: self.sender,
+            data,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CallRequestRejectReason {
+    AlreadyInCall,
+    RejectedByUser,
+    MutedByUser,
+    NoAnswer,
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CallRequestResponse {
+    Accept {
+        /// Codec chosen from the ones offered in the request.
+        codec: AudioCodec,
+    },
+    Reject(CallRequestRejectReason),
+}
+
+/// Call signalling requests sent directly to a peer.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CallSignal {
+    /// Ask the peer to ring for an incoming call.
+    Request {
+        /// Codecs supported by the caller, most preferred first.
+        #[serde(default, deserialize_with = "deserialize_known_codecs")]
+        codecs: Vec<AudioCodec>,
+    },
+    /// Cancel a previously sent call request.
+    Cancel,
+    /// The user's answer to a call request.
+    Response(CallRequestResponse),
+}
+
+/// Acknowledgement of a [`CallSignal`].
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CallSignalAck {
+    Received,
+    /// The call request was rejected without asking the user.
+    Rejected(CallRequestRejectReason),
+}
+
+/// Metadata of a voice message, stored alongside its audio until it is delivered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceMessageInfo {
+    pub id: MessageId,
+    pub sender: PeerId,
+    pub sender_name: String,
+    pub recipient: PeerId,
+    /// Time the message was recorded, in seconds since the Unix epoch.
+    pub timestamp: i64,
+    /// Number of peers that relayed the message so far.
+    pub hops: u8,
+    /// Protobuf-encoded public key of the sender.
+    #[serde(default, with = "bytes")]
+    pub sender_key: Vec<u8>,
+    /// Signature of the sender over the message, without its hops, and a hash of its audio.
+    #[serde(default, with = "bytes")]
+    pub signature: Vec<u8>,
+}
+
+impl VoiceMessageInfo {
+    /// Signs the message and its audio with the keypair of the sender.
+    pub fn sign(&mut self, keypair: &Keypair, audio: &[u8]) -> Result<()> {
+        self.sender_key = keypair.public().encode_protobuf();
+        self.signature = keypair
+            .sign(&self.signed_bytes(audio)?)
+            .context("Failed to sign voice message")?;
+        Ok(())
+    }
+
+    /// Checks that the message and its audio were signed by the sender.
+    pub fn verify(&self, audio: &[u8]) -> Result<()> {
+        check_signature(
+            &self.sender_key,
+            &self.sender,
+            &self.signed_bytes(audio)?,
+            &self.signature,
+        )
+    }
+
+    /// Returns the bytes covered by the signature.
+    ///
+    /// Hops are excluded so the signature stays valid as the message is relayed.
+    fn signed_bytes(&self, audio: &[u8]) -> Result<Vec<u8>> {
+        let unsigned = Self {
+            hops: 0,
+            signature: Vec::new(),
+            ..self.clone()
+        };
+        let audio_hash = glib::compute_checksum_for_data(glib::ChecksumType::Sha256, audio)
+            .context("Failed to hash audio")?;
+        Ok(serde_json::to_vec(&(unsigned, audio_hash.as_str()))?)
+    }
+}
+
+/// Voice message sent directly to the recipient or to a peer that relays it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoiceMessageRequest {
+    pub info: VoiceMessageInfo,
+    /// Ogg Vorbis audio of the message.
+    #[serde(with = "bytes")]
+    pub audio: Vec<u8>,
+}
+
+/// Acknowledgement of a [`VoiceMessageRequest`].
+#[derive(Debug, Serialize, Deserialize)]
+pub enum VoiceMessageAck {
+    /// The recipient received the message.
+    Delivered,
+    /// The peer stored the message to forward it to the recipient later.
+    Relayed,
+    /// The message was refused, for example because the sender is muted.
+    Rejected,
+}
+
+/// Text message sent directly to a peer.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TextMessageRequest {
+    pub id: MessageId,
+    pub text: String,
+    /// Whether the same message was sent to all nearby peers.
+    pub is_broadcast: bool,
+}
+
+/// Delivery receipt of a [`TextMessageRequest`].
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TextMessageReceipt {
+    Received,
+    /// The message was refused, for example because the sender is muted.
+    Rejected,
+}
+
+/// Deserializes a list of codecs, skipping the ones added by newer versions.
+fn deserialize_known_codecs<'de, D>(deserializer: D) -> Result<Vec<AudioCodec>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MaybeKnown {
+        Known(AudioCodec),
+        Unknown(IgnoredAny),
+    }
+
+    let codecs = Vec::<MaybeKnown>::deserialize(deserializer)?
+        .into_iter()
+        .filter_map(|codec| match codec {
+            MaybeKnown::Known(codec) => Some(codec),
+            MaybeKnown::Unknown(_) => None,
+        })
+        .collect();
+
+    Ok(codecs)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Property {
+    Name(String),
+    Location(Option<Location>),
+    Speed(f64),
+    /// Direction of travel in degrees clockwise from north.
+    Heading(Option<f64>),
+    /// Altitude above mean sea level in meters.
+    Altitude(Option<f64>),
+    /// Radius in meters within which the actual location likely is.
+    HorizontalAccuracy(Option<f64>),
+    SignalQuality(SignalQuality),
+    IconName(String),
+    /// Name of the voice channel we are in, if any.
+    VoiceChannel(Option<String>),
+    /// Credential proving our role, if we have one.
+    Credential(Option<Credential>),
+    /// Whether we are an emergency vehicle on the way to an emergency.
+    EmergencyEnRoute(bool),
+}
+
+/// Statement signed by an authority that the holder of a key has a role.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Credential {
+    /// Protobuf-encoded public key of the peer the credential was issued to.
+    #[serde(with = "bytes")]
+    pub subject_key: Vec<u8>,
+    pub role: PeerRole,
+    /// Time the credential expires, in seconds since the Unix epoch.
+    pub expires_at: i64,
+    /// Protobuf-encoded public key of the authority that issued the credential.
+    #[serde(with = "bytes")]
+    pub issuer_key: Vec<u8>,
+    /// Signature of the issuer over the subject key, role and expiry.
+    #[serde(with = "bytes")]
+    pub signature: Vec<u8>,
+}
+
+/// Alert about a hazard, valid until its time-to-live elapses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertInfo {
+    pub id: MessageId,
+    /// Peer that created the alert, which may not be the one we received it from.
+    pub sender: PeerId,
+    pub sender_name: String,
+    #[serde(rename = "type")]
+    pub alert_type: AlertType,
+    pub severity: AlertSeverity,
+    /// Where the hazard is, which may differ from where the sender currently is.
+    pub location: Option<Location>,
+    pub description: Option<String>,
+    /// Time the alert was created, in seconds since the Unix epoch.
+    pub created_at: i64,
+    /// How long the alert applies after its creation, in seconds.
+    pub ttl_secs: u32,
+    /// Number of peers that relayed the alert so far.
+    pub hops: u8,
+    /// Present if the sender has a credential, so the alert can be trusted as from their role.
+    #[serde(default)]
+    pub credential: Option<Credential>,
+    /// Protobuf-encoded public key of the sender.
+    #[serde(default, with = "bytes")]
+    pub sender_key: Vec<u8>,
+    /// Signature of the sender over the alert, without its hops.
+    #[serde(default, with = "bytes")]
+    pub signature: Vec<u8>,
+}
+
+impl AlertInfo {
+    /// Returns the time the alert expires, in seconds since the Unix epoch.
+    pub fn expires_at(&self) -> i64 {
+        self.created_at + self.ttl_secs as i64
+    }
+
+    /// Signs the alert with the keypair of the sender.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<()> {
+        self.sender_key = keypair.public().encode_protobuf();
+        self.signature = keypair
+            .sign(&self.signed_bytes()?)
+            .context("Failed to sign alert")?;
+        Ok(())
+    }
+
+    /// Checks that the alert was signed by the sender, so it wasn't forged or altered.
+    pub fn verify(&self) -> Result<()> {
+        check_signature(
+            &self.sender_key,
+            &self.sender,
+            &self.signed_bytes()?,
+            &self.signature,
+        )
+    }
+
+    /// Returns the bytes covered by the signature.
+    ///
+    /// Hops are excluded so the signature stays valid as the alert is relayed.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let unsigned = Self {
+            hops: 0,
+            signature: Vec::new(),
+            ..self.clone()
+        };
+        Ok(serde_json::to_vec(&unsigned)?)
+    }
+}
+
+/// Response of a peer to an alert, relayed back to its sender like alerts are.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertResponseInfo {
+    pub id: MessageId,
+    pub alert_id: MessageId,
+    pub responder: PeerId,
+    pub responder_name: String,
+    pub kind: AlertResponseKind,
+    pub location: Option<Location>,
+    /// Speed of the responder in meters per second.
+    pub speed: f64,
+    /// Time the response was sent, in seconds since the Unix epoch.
+    pub created_at: i64,
+    /// Number of peers that relayed the response so far.
+    pub hops: u8,
+    /// Protobuf-encoded public key of the responder.
+    #[serde(default, with = "bytes")]
+    pub responder_key: Vec<u8>,
+    /// Signature of the responder over the response, without its hops.
+    #[serde(default, with = "bytes")]
+    pub signature: Vec<u8>,
+}
+
+impl AlertResponseInfo {
+    /// Signs the response with the keypair of the responder.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<()> {
+        self.responder_key = keypair.public().encode_protobuf();
+        self.signature = keypair
+            .sign(&self.signed_bytes()?)
+            .context("Failed to sign alert response")?;
+        Ok(())
+    }
+
+    /// Checks that the response was signed by the responder, so it wasn't forged or altered.
+    pub fn verify(&self) -> Result<()> {
+        check_signature(
+            &self.responder_key,
+            &self.responder,
+            &self.signed_bytes()?,
+            &self.signature,
+        )
+    }
+
+    /// Returns the bytes covered by the signature.
+    ///
+    /// Hops are excluded so the signature stays valid as the response is relayed.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let unsigned = Self {
+            hops: 0,
+            signature: Vec::new(),
+            ..self.clone()
+        };
+        Ok(serde_json::to_vec(&unsigned)?)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PublishData {
+    PropertyChanged(Vec<Property>),
+    Alert(AlertInfo),
+    AlertResponse(AlertResponseInfo),
+    /// The sender took the push-to-talk floor.
+    TalkStarted,
+    /// The sender released the push-to-talk floor.
+    TalkEnded,
+}
+
+/// Checks that the signature over the bytes was made with the protobuf-encoded key, and that
+/// the key belongs to the peer.
+fn check_signature(key: &[u8], peer_id: &PeerId, bytes: &[u8], signature: &[u8]) -> Result<()> {
+    let key = PublicKey::try_decode_protobuf(key).context("Invalid signer key")?;
+    ensure!(
+        key.to_peer_id() == *peer_id,
+        "signer key belongs to {}",
+        key.to_peer_id()
+    );
+    ensure!(key.verify(bytes, signature), "invalid signature");
+
+    Ok(())
+}
+
+/// Serializes bytes as a single byte string rather than a sequence of integers.
+mod bytes {
+    use std::fmt;
+
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BytesVisitor;
+
+        impl<'de> de::Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a byte string")
+            }
+
+            fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+                Ok(bytes.to_vec())
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+                Ok(bytes)
+            }
+
+            // Self-describing formats without byte strings, like JSON, use a sequence
+            fn visit_seq<A>(self, mut seq: A) -> Result<Vec<u8>, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
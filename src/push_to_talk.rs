@@ -0,0 +1,371 @@
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use gst::prelude::*;
+use gtk::{
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{
+    audio_device,
+    call::{AudioCodec, AudioProcessing},
+    input_stream::InputStream,
+    output_stream::OutputStream,
+    peer::Peer,
+};
+
+const STREAMSRC_ELEMENT_NAME: &str = "giostreamsrc";
+const PULSESRC_ELEMENT_NAME: &str = "pulsesrc";
+const TEE_ELEMENT_NAME: &str = "tee";
+
+/// Codec of the broadcast clips, supported by every build so any peer can play them.
+pub const PUSH_TO_TALK_CODEC: AudioCodec = AudioCodec::Vorbis;
+
+/// Longest a peer can hold the floor, after which it is released automatically.
+pub const MAX_TALK_DURATION: Duration = Duration::from_secs(30);
+
+/// Extra time before a remote floor is considered released, in case its end was not received.
+const FLOOR_EXPIRY_GRACE: Duration = Duration::from_secs(5);
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use gst::bus::BusWatchGuard;
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::PushToTalk)]
+    pub struct PushToTalk {
+        /// Whether we hold the floor and broadcast our microphone.
+        #[property(get)]
+        pub(super) is_talking: Cell<bool>,
+        /// Nearby peer currently holding the floor, if any.
+        #[property(get, nullable)]
+        pub(super) floor_holder: RefCell<Option<Peer>>,
+
+        pub(super) talk_timeout_source_id: RefCell<Option<glib::SourceId>>,
+        pub(super) floor_expiry_source_id: RefCell<Option<glib::SourceId>>,
+
+        pub(super) talk: RefCell<Option<(Vec<OutputStream>, gst::Pipeline, BusWatchGuard)>>,
+        pub(super) playback: RefCell<Option<(InputStream, gst::Pipeline, BusWatchGuard)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PushToTalk {
+        const NAME: &'static str = "DeltaPushToTalk";
+        type Type = super::PushToTalk;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PushToTalk {}
+}
+
+glib::wrapper! {
+    /// Half-duplex broadcast of short voice clips to all nearby peers.
+    pub struct PushToTalk(ObjectSubclass<imp::PushToTalk>);
+}
+
+impl PushToTalk {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Claims the floor locally. The audio is sent once the streams are set.
+    ///
+    /// The floor is released automatically after [`MAX_TALK_DURATION`].
+    pub fn start_talking(&self) -> Result<()> {
+        let imp = self.imp();
+
+        ensure!(!self.is_talking(), "Already talking");
+        ensure!(
+            self.floor_holder().is_none() && imp.playback.borrow().is_none(),
+            "Another peer is talking"
+        );
+
+        let source_id = glib::timeout_add_local_once(
+            MAX_TALK_DURATION,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    tracing::debug!("Reached maximum talk duration");
+
+                    obj.imp().talk_timeout_source_id.replace(None);
+                    obj.stop_talking();
+                }
+            ),
+        );
+        imp.talk_timeout_source_id.replace(Some(source_id));
+
+        imp.is_talking.set(true);
+        self.notify_is_talking();
+
+        Ok(())
+    }
+
+    /// Starts broadcasting our microphone to the given streams.
+    ///
+    /// The streams are closed right away if we stopped talking while they were opened.
+    pub fn set_output_streams(&self, output_streams: Vec<OutputStream>) -> Result<()> {
+        let imp = self.imp();
+
+        if !self.is_talking() {
+            close_output_streams(output_streams);
+            return Ok(());
+        }
+
+        ensure!(imp.talk.borrow().is_none(), "Output streams already set");
+
+        let audio_processing = AudioProcessing::from_settings();
+
+        // Encode once and fan out to every peer
+        let mut desc = format!(
+            "pulsesrc name={PULSESRC_ELEMENT_NAME} ! audioconvert ! {}{} ! tee name={TEE_ELEMENT_NAME}",
+            audio_processing.dsp_pipeline_desc(),
+            PUSH_TO_TALK_CODEC.encoder_pipeline_desc()
+        );
+        for index in 0..output_streams.len() {
+            desc.push_str(&format!(
+                " {TEE_ELEMENT_NAME}. ! queue ! giostreamsink name=giostreamsink{index}"
+            ));
+        }
+
+        let pipeline = gst::parse::launch(&desc)?
+            .downcast::<gst::Pipeline>()
+            .unwrap();
+
+        let pulsesrc = pipeline.by_name(PULSESRC_ELEMENT_NAME).unwrap();
+        let device = audio_device::find_default_source()?;
+        device.reconfigure_element(&pulsesrc)?;
+
+        for (index, output_stream) in output_streams.iter().enumerate() {
+            let streamsink = pipeline
+                .by_name(&format!("giostreamsink{index}"))
+                .context("Missing stream sink")?;
+            streamsink.set_property("stream", output_stream);
+        }
+
+        let bus_watch_guard = pipeline
+            .bus()
+            .unwrap()
+            .add_watch_local(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or_panic]
+                move |_, message| obj.handle_talk_bus_message(message)
+            ))
+            .unwrap();
+
+        pipeline.set_state(gst::State::Playing)?;
+
+        tracing::debug!("Talking to {} peers", output_streams.len());
+
+        imp.talk
+            .replace(Some((output_streams, pipeline, bus_watch_guard)));
+
+        Ok(())
+    }
+
+    /// Releases the floor, flushing the audio that was already captured.
+    pub fn stop_talking(&self) {
+        let imp = self.imp();
+
+        if !self.is_talking() {
+            return;
+        }
+
+        if let Some(source_id) = imp.talk_timeout_source_id.take() {
+            source_id.remove();
+        }
+
+        if let Some((_, ref pipeline, _)) = *imp.talk.borrow() {
+            pipeline.send_event(gst::event::Eos::new());
+
+            tracing::debug!("Sent EOS to talk pipeline");
+        }
+
+        imp.is_talking.set(false);
+        self.notify_is_talking();
+    }
+
+    /// Sets the peer holding the floor, released automatically if its end is never received.
+    pub fn set_floor_holder(&self, floor_holder: Option<&Peer>) {
+        let imp = self.imp();
+
+        if let Some(source_id) = imp.floor_expiry_source_id.take() {
+            source_id.remove();
+        }
+
+        if floor_holder.is_some() {
+            let source_id = glib::timeout_add_local_once(
+                MAX_TALK_DURATION + FLOOR_EXPIRY_GRACE,
+                clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    move || {
+                        tracing::debug!("Floor expired");
+
+                        obj.imp().floor_expiry_source_id.replace(None);
+                        obj.set_floor_holder(None);
+                    }
+                ),
+            );
+            imp.floor_expiry_source_id.replace(Some(source_id));
+        }
+
+        if floor_holder == self.floor_holder().as_ref() {
+            return;
+        }
+
+        imp.floor_holder.replace(floor_holder.cloned());
+        self.notify_floor_holder();
+    }
+
+    /// Plays the clip broadcast by the peer holding the floor.
+    pub fn play(&self, input_stream: InputStream, codec: AudioCodec) -> Result<()> {
+        let imp = self.imp();
+
+        ensure!(!self.is_talking(), "Cannot play while talking");
+        ensure!(imp.playback.borrow().is_none(), "Already playing");
+
+        let pipeline = gst::parse::launch(&format!(
+            "giostreamsrc name={STREAMSRC_ELEMENT_NAME} ! {} ! audioconvert ! autoaudiosink",
+            codec.decoder_pipeline_desc(),
+        ))?
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let streamsrc = pipeline.by_name(STREAMSRC_ELEMENT_NAME).unwrap();
+        streamsrc.set_property("stream", &input_stream);
+
+        if codec == AudioCodec::Opus {
+            streamsrc.set_property("do-timestamp", true);
+        }
+
+        let bus_watch_guard = pipeline
+            .bus()
+            .unwrap()
+            .add_watch_local(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or_panic]
+                move |_, message| obj.handle_playback_bus_message(message)
+            ))
+            .unwrap();
+
+        pipeline.set_state(gst::State::Playing)?;
+
+        imp.playback
+            .replace(Some((input_stream, pipeline, bus_watch_guard)));
+
+        Ok(())
+    }
+
+    fn handle_talk_bus_message(&self, message: &gst::Message) -> glib::ControlFlow {
+        match message.view() {
+            gst::MessageView::Eos(..) => {
+                tracing::debug!("Received EOS event on talk bus");
+
+                glib::spawn_future_local(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    async move {
+                        obj.dispose_talk();
+                    }
+                ));
+
+                glib::ControlFlow::Break
+            }
+            gst::MessageView::Error(err) => {
+                tracing::warn!("Error from talk bus: {:?}", err);
+
+                self.stop_talking();
+                glib::spawn_future_local(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    async move {
+                        obj.dispose_talk();
+                    }
+                ));
+
+                glib::ControlFlow::Break
+            }
+            _ => glib::ControlFlow::Continue,
+        }
+    }
+
+    fn handle_playback_bus_message(&self, message: &gst::Message) -> glib::ControlFlow {
+        match message.view() {
+            gst::MessageView::Eos(..) => {
+                tracing::debug!("Received EOS event on push-to-talk playback bus");
+
+                glib::spawn_future_local(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    async move {
+                        obj.dispose_playback();
+                    }
+                ));
+
+                glib::ControlFlow::Break
+            }
+            gst::MessageView::Error(err) => {
+                tracing::warn!("Error from push-to-talk playback bus: {:?}", err);
+
+                glib::spawn_future_local(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    async move {
+                        obj.dispose_playback();
+                    }
+                ));
+
+                glib::ControlFlow::Break
+            }
+            _ => glib::ControlFlow::Continue,
+        }
+    }
+
+    fn dispose_talk(&self) {
+        let Some((output_streams, pipeline, _bus_watch_guard)) = self.imp().talk.take() else {
+            return;
+        };
+
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        close_output_streams(output_streams);
+    }
+
+    fn dispose_playback(&self) {
+        let Some((input_stream, pipeline, _bus_watch_guard)) = self.imp().playback.take() else {
+            return;
+        };
+
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        glib::spawn_future_local(async move {
+            if let Err(err) = input_stream.close_future(glib::Priority::LOW).await {
+                tracing::error!("Failed to close input stream: {:?}", err);
+            }
+        });
+    }
+}
+
+impl Default for PushToTalk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn close_output_streams(output_streams: Vec<OutputStream>) {
+    glib::spawn_future_local(async move {
+        for output_stream in output_streams {
+            if let Err(err) = output_stream.close_future(glib::Priority::LOW).await {
+                tracing::error!("Failed to close output stream: {:?}", err);
+            }
+        }
+    });
+}
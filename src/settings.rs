@@ -1,4 +1,8 @@
-use std::{cell::RefCell, collections::HashSet, fmt};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
 use anyhow::Result;
 use gtk::{
@@ -7,6 +11,7 @@ use gtk::{
     prelude::*,
     subclass::prelude::*,
 };
+use libp2p::PeerId;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
@@ -46,33 +51,91 @@ impl TryFrom<i32> for AllowedPeers {
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize, glib::Boxed)]
-#[serde(transparent)]
+#[serde(from = "MutedPeersRepr")]
 #[boxed_type(name = "DeltaMutedPeers")]
 pub struct MutedPeers {
-    inner: HashSet<String>,
+    /// Muted peers with their last known name.
+    by_id: HashMap<PeerId, String>,
+    /// Names muted by older versions, which keep matching any peer with the name until the
+    /// user confirms which peer was meant.
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    legacy_names: HashSet<String>,
+    /// Peers seen with a legacy muted name, which the user may confirm as the muted one.
+    #[serde(skip)]
+    legacy_candidates: HashMap<String, HashSet<PeerId>>,
 }
 
 impl MutedPeers {
-    pub fn contains(&self, name: &str) -> bool {
-        self.inner.contains(name)
+    /// Returns whether the peer is muted, either by id or by a name muted by older versions.
+    pub fn contains(&self, peer_id: &PeerId, peer_name: &str) -> bool {
+        self.by_id.contains_key(peer_id) || self.legacy_names.contains(peer_name)
     }
 
     pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
+        self.by_id.is_empty() && self.legacy_names.is_empty()
+    }
+
+    /// Returns the muted peer ids with their last known name.
+    pub fn iter(&self) -> impl Iterator<Item = (&PeerId, &String)> {
+        self.by_id.iter()
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &String> {
-        self.inner.iter()
+    /// Returns the names muted by older versions that are not yet associated with a peer id.
+    pub fn iter_legacy_names(&self) -> impl Iterator<Item = &String> {
+        self.legacy_names.iter()
+    }
+
+    /// Returns the peers seen with the legacy muted name.
+    pub fn iter_legacy_candidates(&self, peer_name: &str) -> impl Iterator<Item = &PeerId> {
+        self.legacy_candidates.get(peer_name).into_iter().flatten()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MutedPeersRepr {
+    Current {
+        by_id: HashMap<PeerId, String>,
+        #[serde(default)]
+        legacy_names: HashSet<String>,
+    },
+    /// Older versions stored the muted peers as a set of names.
+    Legacy(HashSet<String>),
+}
+
+impl From<MutedPeersRepr> for MutedPeers {
+    fn from(repr: MutedPeersRepr) -> Self {
+        match repr {
+            MutedPeersRepr::Current {
+                by_id,
+                legacy_names,
+            } => Self {
+                by_id,
+                legacy_names,
+                legacy_candidates: HashMap::new(),
+            },
+            MutedPeersRepr::Legacy(legacy_names) => Self {
+                by_id: HashMap::new(),
+                legacy_names,
+                legacy_candidates: HashMap::new(),
+            },
+        }
     }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
 struct Data {
     allowed_peers: AllowedPeers,
     muted_peers: MutedPeers,
     icon_name: String,
     remote_ip_addr: String,
     accel_impact_threshold: f32,
+    call_ringing_timeout_secs: u32,
+    echo_cancellation: bool,
+    noise_suppression: bool,
+    alert_radius_m: u32,
+    alerts_ahead_only: bool,
 }
 
 impl Default for Data {
@@ -83,6 +146,11 @@ impl Default for Data {
             icon_name: "driving-symbolic".into(),
             remote_ip_addr: "192.168.100.203".into(),
             accel_impact_threshold: 20.0,
+            call_ringing_timeout_secs: 30,
+            echo_cancellation: true,
+            noise_suppression: true,
+            alert_radius_m: 5000,
+            alerts_ahead_only: false,
         }
     }
 }
@@ -98,6 +166,11 @@ mod imp {
         #[property(name = "icon-name", get, set, member = icon_name, type = String)]
         #[property(name = "remote-ip-addr", get, set, member = remote_ip_addr, type = String)]
         #[property(name = "accel-impact-threshold", get, set, member = accel_impact_threshold, type = f32)]
+        #[property(name = "call-ringing-timeout-secs", get, set, member = call_ringing_timeout_secs, type = u32)]
+        #[property(name = "echo-cancellation", get, set, member = echo_cancellation, type = bool)]
+        #[property(name = "noise-suppression", get, set, member = noise_suppression, type = bool)]
+        #[property(name = "alert-radius-m", get, set, member = alert_radius_m, type = u32)]
+        #[property(name = "alerts-ahead-only", get, set, member = alerts_ahead_only, type = bool)]
         pub(super) data: RefCell<Data>,
 
         pub(super) etag: RefCell<Option<glib::GString>>,
@@ -167,30 +240,127 @@ impl Settings {
         Ok(())
     }
 
-    pub fn insert_muted_peer(&self, peer_name: String) {
+    pub fn insert_muted_peer(&self, peer_id: PeerId, peer_name: String) {
         let imp = self.imp();
 
-        if imp.data.borrow_mut().muted_peers.inner.insert(peer_name) {
+        let prev_name = imp
+            .data
+            .borrow_mut()
+            .muted_peers
+            .by_id
+            .insert(peer_id, peer_name.clone());
+
+        if prev_name.as_ref() != Some(&peer_name) {
             self.notify_muted_peers();
         }
     }
 
-    pub fn remove_muted_peer(&self, peer_name: &str) {
+    pub fn remove_muted_peer(&self, peer_id: &PeerId) {
         let imp = self.imp();
 
-        if imp.data.borrow_mut().muted_peers.inner.remove(peer_name) {
+        if imp
+            .data
+            .borrow_mut()
+            .muted_peers
+            .by_id
+            .remove(peer_id)
+            .is_some()
+        {
             self.notify_muted_peers();
         }
     }
 
-    pub fn is_allowed_peer(&self, peer_name: &str) -> bool {
+    pub fn remove_legacy_muted_peer(&self, peer_name: &str) {
+        let imp = self.imp();
+
+        let mut data = imp.data.borrow_mut();
+        let muted_peers = &mut data.muted_peers;
+
+        let removed = muted_peers.legacy_names.remove(peer_name);
+        muted_peers.legacy_candidates.remove(peer_name);
+
+        drop(data);
+
+        if removed {
+            self.notify_muted_peers();
+        }
+    }
+
+    /// Converts a legacy name-based mute to an id-based one, once the user confirmed the peer
+    /// seen with the name is the one they meant to mute.
+    pub fn confirm_legacy_muted_peer(&self, peer_name: &str, peer_id: PeerId) {
+        let imp = self.imp();
+
+        let mut data = imp.data.borrow_mut();
+        let muted_peers = &mut data.muted_peers;
+
+        if !muted_peers.legacy_names.remove(peer_name) {
+            return;
+        }
+
+        tracing::debug!("Migrated legacy muted peer `{peer_name}` to {peer_id}");
+
+        muted_peers.legacy_candidates.remove(peer_name);
+        muted_peers.by_id.insert(peer_id, peer_name.to_string());
+
+        drop(data);
+
+        self.notify_muted_peers();
+    }
+
+    /// Updates the last known name of a muted peer, and records it as a candidate for a legacy
+    /// name-based mute if the name matches.
+    ///
+    /// Legacy mutes are not converted automatically, as anyone can claim a name.
+    pub fn update_muted_peer_name(&self, peer_id: &PeerId, peer_name: &str) {
+        let imp = self.imp();
+
+        let mut data = imp.data.borrow_mut();
+        let muted_peers = &mut data.muted_peers;
+
+        let changed = if let Some(name) = muted_peers.by_id.get_mut(peer_id) {
+            if name != peer_name {
+                *name = peer_name.to_string();
+                true
+            } else {
+                false
+            }
+        } else if muted_peers.legacy_names.contains(peer_name) {
+            muted_peers
+                .legacy_candidates
+                .entry(peer_name.to_string())
+                .or_default()
+                .insert(*peer_id)
+        } else {
+            false
+        };
+
+        drop(data);
+
+        if changed {
+            self.notify_muted_peers();
+        }
+    }
+
+    pub fn is_allowed_peer(&self, peer_id: &PeerId, peer_name: &str) -> bool {
         match self.allowed_peers() {
-            AllowedPeers::ExceptMuted => !self.muted_peers().contains(peer_name),
+            AllowedPeers::ExceptMuted => !self.muted_peers().contains(peer_id, peer_name),
             AllowedPeers::All => true,
             AllowedPeers::None => false,
         }
     }
 
+    /// Like [`Self::is_allowed_peer`], but authorities are heard even when no peers are
+    /// allowed, unless muted individually.
+    pub fn is_allowed_authority(&self, peer_id: &PeerId, peer_name: &str) -> bool {
+        match self.allowed_peers() {
+            AllowedPeers::ExceptMuted | AllowedPeers::None => {
+                !self.muted_peers().contains(peer_id, peer_name)
+            }
+            AllowedPeers::All => true,
+        }
+    }
+
     fn load(&self) -> Result<()> {
         let imp = self.imp();
 
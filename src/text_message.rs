@@ -0,0 +1,190 @@
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+
+use crate::protocol::MessageId;
+
+/// Canned replies that can be sent with a single tap or voice command.
+pub const QUICK_REPLIES: &[&str] = &["On my way", "Stopping for fuel", "Need help"];
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, glib::Boxed)]
+#[boxed_type(name = "DeltaTextMessageStatus")]
+pub enum TextMessageStatus {
+    #[default]
+    Sending,
+    Delivered,
+    Failed,
+    /// The message was sent to us.
+    Received,
+}
+
+mod text_message_imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::TextMessage)]
+    pub struct TextMessage {
+        pub(super) id: OnceCell<MessageId>,
+
+        #[property(get, set, construct_only)]
+        pub(super) text: RefCell<String>,
+        #[property(get, set, construct_only)]
+        pub(super) timestamp: Cell<i64>,
+        #[property(get, set, construct_only)]
+        pub(super) is_outgoing: Cell<bool>,
+        #[property(get, set, construct_only)]
+        pub(super) is_broadcast: Cell<bool>,
+        #[property(get)]
+        pub(super) status: Cell<TextMessageStatus>,
+        /// Number of peers that received the message, more than one for broadcasts.
+        #[property(get)]
+        pub(super) delivered_count: Cell<u32>,
+
+        pub(super) pending_count: Cell<u32>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TextMessage {
+        const NAME: &'static str = "DeltaTextMessage";
+        type Type = super::TextMessage;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for TextMessage {}
+}
+
+glib::wrapper! {
+    /// Text message sent to or received from peers.
+    pub struct TextMessage(ObjectSubclass<text_message_imp::TextMessage>);
+}
+
+impl TextMessage {
+    /// Creates a message we are sending to `recipient_count` peers.
+    pub fn new_outgoing(
+        id: MessageId,
+        text: &str,
+        is_broadcast: bool,
+        recipient_count: u32,
+    ) -> Self {
+        let this = Self::new(id, text, true, is_broadcast);
+        this.imp().pending_count.set(recipient_count);
+        this
+    }
+
+    pub fn new_incoming(id: MessageId, text: &str, is_broadcast: bool) -> Self {
+        let this = Self::new(id, text, false, is_broadcast);
+        this.set_status(TextMessageStatus::Received);
+        this
+    }
+
+    fn new(id: MessageId, text: &str, is_outgoing: bool, is_broadcast: bool) -> Self {
+        let this = glib::Object::builder::<Self>()
+            .property("text", text)
+            .property("timestamp", glib::DateTime::now_utc().unwrap().to_unix())
+            .property("is-outgoing", is_outgoing)
+            .property("is-broadcast", is_broadcast)
+            .build();
+        this.imp().id.set(id).unwrap();
+        this
+    }
+
+    pub fn id(&self) -> MessageId {
+        *self.imp().id.get().unwrap()
+    }
+
+    pub fn date_time(&self) -> glib::DateTime {
+        glib::DateTime::from_unix_local(self.timestamp()).unwrap()
+    }
+
+    /// Records the receipt of one of the recipients, or its absence if `is_delivered` is false.
+    ///
+    /// The message only fails once no recipient received it.
+    pub fn handle_receipt(&self, is_delivered: bool) {
+        let imp = self.imp();
+
+        imp.pending_count
+            .set(imp.pending_count.get().saturating_sub(1));
+
+        if is_delivered {
+            imp.delivered_count.set(imp.delivered_count.get() + 1);
+            self.notify_delivered_count();
+
+            self.set_status(TextMessageStatus::Delivered);
+        } else if imp.pending_count.get() == 0 && self.delivered_count() == 0 {
+            self.set_status(TextMessageStatus::Failed);
+        }
+    }
+
+    fn set_status(&self, status: TextMessageStatus) {
+        if status == self.status() {
+            return;
+        }
+
+        self.imp().status.set(status);
+        self.notify_status();
+    }
+}
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct Conversation {
+        pub(super) list: RefCell<Vec<TextMessage>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Conversation {
+        const NAME: &'static str = "DeltaConversation";
+        type Type = super::Conversation;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    impl ObjectImpl for Conversation {}
+
+    impl ListModelImpl for Conversation {
+        fn item_type(&self) -> glib::Type {
+            TextMessage::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.list.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.list
+                .borrow()
+                .get(position as usize)
+                .map(|v| v.upcast_ref::<glib::Object>())
+                .cloned()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Messages exchanged with a peer, oldest first.
+    pub struct Conversation(ObjectSubclass<imp::Conversation>)
+        @implements gio::ListModel;
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn push(&self, message: TextMessage) {
+        let imp = self.imp();
+
+        let position = imp.list.borrow().len() as u32;
+        imp.list.borrow_mut().push(message);
+        self.items_changed(position, 0, 1);
+    }
+}
+
+impl Default for Conversation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,134 @@
+use std::f64::consts::TAU;
+
+use adw::prelude::*;
+use gtk::{
+    glib::{self, clone},
+    graphene::Rect,
+};
+use shumate::{prelude::*, subclass::prelude::*};
+
+use crate::colors;
+
+/// Smallest size the circle is drawn at, so it stays visible when zoomed out.
+const MIN_SIZE: i32 = 2;
+
+mod imp {
+    use std::cell::{Cell, OnceCell};
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct AccuracyCircle {
+        pub(super) viewport: OnceCell<shumate::Viewport>,
+        /// Radius in meters
+        pub(super) radius: Cell<f64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AccuracyCircle {
+        const NAME: &'static str = "DeltaAccuracyCircle";
+        type Type = super::AccuracyCircle;
+        type ParentType = shumate::Marker;
+    }
+
+    impl ObjectImpl for AccuracyCircle {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            obj.set_can_target(false);
+            obj.set_can_focus(false);
+
+            // The size in pixels of a meter depends on the latitude too
+            obj.connect_notify_local(
+                Some("latitude"),
+                clone!(
+                    #[weak]
+                    obj,
+                    move |_, _| {
+                        obj.update_size();
+                    }
+                ),
+            );
+        }
+    }
+
+    impl WidgetImpl for AccuracyCircle {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = self.obj();
+
+            let width = obj.width();
+            let height = obj.height();
+
+            let radius = width.min(height) as f64 / 2.0;
+
+            let cr = snapshot.append_cairo(&Rect::new(0.0, 0.0, width as f32, height as f32));
+            cr.arc(width as f64 / 2.0, height as f64 / 2.0, radius, 0.0, TAU);
+
+            cr.set_source_color(&colors::BLUE_3.with_alpha(0.2));
+            cr.fill_preserve().unwrap();
+
+            cr.set_source_color(&colors::BLUE_3.with_alpha(0.6));
+            cr.set_line_width(1.0);
+            cr.stroke().unwrap();
+
+            self.parent_snapshot(snapshot);
+        }
+    }
+
+    impl MarkerImpl for AccuracyCircle {}
+}
+
+glib::wrapper! {
+    /// Circle around a location showing how accurate it is.
+    pub struct AccuracyCircle(ObjectSubclass<imp::AccuracyCircle>)
+        @extends gtk::Widget, shumate::Marker,
+        @implements shumate::Location;
+}
+
+impl AccuracyCircle {
+    pub fn new(viewport: &shumate::Viewport) -> Self {
+        let this = glib::Object::new::<Self>();
+
+        viewport.connect_zoom_level_notify(clone!(
+            #[weak]
+            this,
+            move |_| {
+                this.update_size();
+            }
+        ));
+        this.imp().viewport.set(viewport.clone()).unwrap();
+
+        this
+    }
+
+    /// Returns the radius in meters within which the actual location likely is.
+    pub fn radius(&self) -> f64 {
+        self.imp().radius.get()
+    }
+
+    /// Sets the radius in meters within which the actual location likely is.
+    pub fn set_radius(&self, radius: f64) {
+        self.imp().radius.set(radius);
+        self.update_size();
+    }
+
+    fn update_size(&self) {
+        let imp = self.imp();
+
+        let Some(viewport) = imp.viewport.get() else {
+            return;
+        };
+        let Some(map_source) = viewport.reference_map_source() else {
+            return;
+        };
+
+        let meters_per_pixel =
+            map_source.meters_per_pixel(viewport.zoom_level(), self.latitude(), self.longitude());
+        let size = ((2.0 * imp.radius.get() / meters_per_pixel).ceil() as i32).max(MIN_SIZE);
+
+        self.set_size_request(size, size);
+        self.queue_draw();
+    }
+}
@@ -0,0 +1,311 @@
+use adw::prelude::*;
+use anyhow::Result;
+use gtk::{
+    gio,
+    glib::{self, clone, closure_local},
+    subclass::prelude::*,
+};
+
+use crate::{
+    alert::AlertResponseKind,
+    alert_log::{AlertDirection, AlertLogEntry},
+    Application,
+};
+
+#[derive(Debug, Clone, Copy)]
+enum ExportFormat {
+    GeoJson,
+    Csv,
+}
+
+mod imp {
+    use std::sync::OnceLock;
+
+    use glib::subclass::Signal;
+
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/seadve/Delta/ui/alert_log_view.ui")]
+    pub struct AlertLogView {
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) clear_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) export_geojson_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) export_csv_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AlertLogView {
+        const NAME: &'static str = "DeltaAlertLogView";
+        type Type = super::AlertLogView;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for AlertLogView {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let alert_log = Application::get().alert_log();
+
+            let placeholder_label = gtk::Label::builder()
+                .margin_top(12)
+                .margin_bottom(12)
+                .margin_start(12)
+                .margin_end(12)
+                .label("No Alerts")
+                .build();
+            self.list_box.set_placeholder(Some(&placeholder_label));
+
+            self.list_box.bind_model(
+                Some(&alert_log),
+                clone!(
+                    #[weak]
+                    obj,
+                    #[upgrade_or_panic]
+                    move |entry| {
+                        let entry = entry.downcast_ref::<AlertLogEntry>().unwrap();
+                        obj.create_row(entry).upcast()
+                    }
+                ),
+            );
+
+            self.clear_button.connect_clicked(|_| {
+                Application::get().alert_log().clear();
+            });
+            self.export_geojson_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.spawn_export(ExportFormat::GeoJson);
+                }
+            ));
+            self.export_csv_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.spawn_export(ExportFormat::Csv);
+                }
+            ));
+
+            alert_log.connect_items_changed(clone!(
+                #[weak]
+                obj,
+                move |_, _, _, _| {
+                    obj.update_buttons_sensitivity();
+                }
+            ));
+
+            obj.update_buttons_sensitivity();
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("show-on-map-requested")
+                    .param_types([AlertLogEntry::static_type()])
+                    .build()]
+            })
+        }
+    }
+
+    impl WidgetImpl for AlertLogView {}
+}
+
+glib::wrapper! {
+    pub struct AlertLogView(ObjectSubclass<imp::AlertLogView>)
+        @extends gtk::Widget;
+}
+
+impl AlertLogView {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn connect_show_on_map_requested<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &AlertLogEntry) + 'static,
+    {
+        self.connect_closure(
+            "show-on-map-requested",
+            false,
+            closure_local!(|obj: &Self, entry: &AlertLogEntry| f(obj, entry)),
+        )
+    }
+
+    fn create_row(&self, entry: &AlertLogEntry) -> adw::ActionRow {
+        let title = match entry.direction() {
+            AlertDirection::Sent => entry.alert_type().to_string(),
+            AlertDirection::Received => {
+                format!("{} from {}", entry.alert_type(), entry.sender_name())
+            }
+        };
+
+        let row = adw::ActionRow::builder()
+            .title(title)
+            .title_lines(1)
+            .subtitle_lines(2)
+            .build();
+        row.add_prefix(&gtk::Image::from_icon_name(&entry.alert_type().icon_name()));
+
+        let update_subtitle = clone!(
+            #[weak]
+            row,
+            move |entry: &AlertLogEntry| {
+                let mut parts = vec![
+                    entry
+                        .logged_date_time()
+                        .format("%b %e, %H:%M")
+                        .unwrap()
+                        .to_string(),
+                    entry.direction().to_string(),
+                ];
+
+                match entry.direction() {
+                    AlertDirection::Sent => {
+                        parts.push(format!("{} acknowledged", entry.acknowledged_count()));
+                        parts.push(format!("{} responding", entry.responding_count()));
+                    }
+                    AlertDirection::Received => match entry.our_response() {
+                        Some(AlertResponseKind::Acknowledged) => {
+                            parts.push("You acknowledged".to_string());
+                        }
+                        Some(AlertResponseKind::Responding) => {
+                            parts.push("You responded".to_string());
+                        }
+                        None => {}
+                    },
+                }
+
+                if let Some(description) = entry.description() {
+                    parts.push(description);
+                }
+
+                row.set_subtitle(&glib::markup_escape_text(&parts.join(" · ")));
+            }
+        );
+        update_subtitle(entry);
+        entry.connect_acknowledged_count_notify(update_subtitle.clone());
+        entry.connect_responding_count_notify(update_subtitle);
+
+        let show_on_map_button = gtk::Button::builder()
+            .icon_name("map-marker-symbolic")
+            .tooltip_text("Show on Map")
+            .valign(gtk::Align::Center)
+            .sensitive(entry.location().is_some())
+            .build();
+        show_on_map_button.add_css_class("flat");
+        show_on_map_button.connect_clicked(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[weak]
+            entry,
+            move |_| {
+                obj.emit_by_name::<()>("show-on-map-requested", &[&entry]);
+            }
+        ));
+        row.add_suffix(&show_on_map_button);
+
+        row
+    }
+
+    fn spawn_export(&self, format: ExportFormat) {
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                if let Err(err) = obj.export(format).await {
+                    tracing::error!("Failed to export alert log: {:?}", err);
+
+                    obj.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new("Failed to export alert log"));
+                }
+            }
+        ));
+    }
+
+    async fn export(&self, format: ExportFormat) -> Result<()> {
+        let imp = self.imp();
+
+        let (title, extension) = match format {
+            ExportFormat::GeoJson => ("Export Alert Log as GeoJSON", "geojson"),
+            ExportFormat::Csv => ("Export Alert Log as CSV", "csv"),
+        };
+
+        let now = glib::DateTime::now_local()?;
+        let dialog = gtk::FileDialog::builder()
+            .title(title)
+            .initial_name(format!(
+                "alert-log-{}.{}",
+                now.format("%Y%m%d-%H%M%S")?,
+                extension
+            ))
+            .modal(true)
+            .build();
+
+        let file = match dialog
+            .save_future(self.root().and_downcast_ref::<gtk::Window>())
+            .await
+        {
+            Ok(file) => file,
+            Err(err) => {
+                if err.matches(gtk::DialogError::Dismissed) {
+                    return Ok(());
+                }
+
+                return Err(err.into());
+            }
+        };
+
+        let alert_log = Application::get().alert_log();
+        let contents = match format {
+            ExportFormat::GeoJson => alert_log.to_geojson()?,
+            ExportFormat::Csv => alert_log.to_csv(),
+        };
+        file.replace_contents_future(
+            contents.into_bytes(),
+            None,
+            false,
+            gio::FileCreateFlags::REPLACE_DESTINATION,
+        )
+        .await
+        .map_err(|(_, err)| err)?;
+
+        tracing::debug!("Exported alert log to {}", file.uri());
+
+        imp.toast_overlay
+            .add_toast(adw::Toast::new("Exported alert log"));
+
+        Ok(())
+    }
+
+    fn update_buttons_sensitivity(&self) {
+        let imp = self.imp();
+
+        let has_entries = Application::get().alert_log().n_items() > 0;
+        imp.clear_button.set_sensitive(has_entries);
+        imp.export_geojson_button.set_sensitive(has_entries);
+        imp.export_csv_button.set_sensitive(has_entries);
+    }
+}
@@ -8,20 +8,19 @@ use gtk::{
 };
 use shumate::{prelude::*, subclass::prelude::*};
 
-use crate::peer::Peer;
+use crate::alert::{Alert, AlertSeverity};
 
 const ANIMATION_DURATION_MS: u32 = 1000;
 const MAX_CIRCLE_SIZE: i32 = 200;
 
 mod imp {
-    use std::cell::{Cell, OnceCell, RefCell};
+    use std::cell::{Cell, OnceCell};
 
     use super::*;
 
     #[derive(Default)]
     pub struct AlertMarker {
-        pub(super) peer: RefCell<Option<Peer>>,
-        pub(super) peer_signals: OnceCell<glib::SignalGroup>,
+        pub(super) alert: OnceCell<Alert>,
 
         pub(super) animation: OnceCell<adw::TimedAnimation>,
         pub(super) circle_color: Cell<Option<gdk::RGBA>>,
@@ -46,19 +45,6 @@ mod imp {
             obj.set_can_target(false);
             obj.set_can_focus(false);
 
-            let peer_signals = glib::SignalGroup::new::<Peer>();
-            peer_signals.connect_notify_local(
-                Some("location"),
-                clone!(
-                    #[weak]
-                    obj,
-                    move |_, _| {
-                        obj.update_location();
-                    }
-                ),
-            );
-            self.peer_signals.set(peer_signals).unwrap();
-
             let animation_target = adw::CallbackAnimationTarget::new(clone!(
                 #[weak]
                 obj,
@@ -73,8 +59,6 @@ mod imp {
                 .target(&animation_target)
                 .build();
             self.animation.set(animation).unwrap();
-
-            obj.update_location();
         }
     }
 
@@ -85,20 +69,33 @@ mod imp {
             let width = obj.width();
             let height = obj.height();
 
+            let color = self.circle_color.get().unwrap_or(gdk::RGBA::BLACK);
+
             let value = self.animation.get().unwrap().value();
             let radius = MAX_CIRCLE_SIZE as f64 / 2.0 * value;
 
             let cr = snapshot.append_cairo(&Rect::new(0.0, 0.0, width as f32, height as f32));
-            cr.set_source_color(
-                &self
-                    .circle_color
-                    .get()
-                    .unwrap_or(gdk::RGBA::BLACK)
-                    .with_alpha(0.4 * (1.0 - value as f32)),
-            );
+            cr.set_source_color(&color.with_alpha(0.4 * (1.0 - value as f32)));
             cr.arc(width as f64 / 2.0, height as f64 / 2.0, radius, 0.0, TAU);
             cr.fill().unwrap();
 
+            // Keep the hazard visible between animations until the alert expires
+            let dot_radius = match self.alert.get().unwrap().severity() {
+                AlertSeverity::Low => 6.0,
+                AlertSeverity::Medium => 8.0,
+                AlertSeverity::High => 10.0,
+                AlertSeverity::Critical => 12.0,
+            };
+            cr.set_source_color(&color.with_alpha(0.8));
+            cr.arc(
+                width as f64 / 2.0,
+                height as f64 / 2.0,
+                dot_radius,
+                0.0,
+                TAU,
+            );
+            cr.fill().unwrap();
+
             self.parent_snapshot(snapshot);
         }
     }
@@ -113,17 +110,22 @@ glib::wrapper! {
 }
 
 impl AlertMarker {
-    pub fn new() -> Self {
-        glib::Object::new()
-    }
+    /// Creates a marker at the location of the alert, which must have one.
+    pub fn new(alert: &Alert, color: gdk::RGBA) -> Self {
+        let this = glib::Object::new::<Self>();
 
-    pub fn set_peer(&self, peer: Option<Peer>) {
-        let imp = self.imp();
+        let imp = this.imp();
+        imp.alert.set(alert.clone()).unwrap();
+        imp.circle_color.set(Some(color));
+
+        let location = alert.location().unwrap();
+        this.set_location(location.latitude, location.longitude);
 
-        imp.peer_signals.get().unwrap().set_target(peer.as_ref());
-        imp.peer.replace(peer);
+        this
+    }
 
-        self.update_location();
+    pub fn alert(&self) -> &Alert {
+        self.imp().alert.get().unwrap()
     }
 
     pub fn play_animation(&self, repeat_count: u32, color: gdk::RGBA) {
@@ -138,15 +140,4 @@ impl AlertMarker {
 
         animation.play();
     }
-
-    fn update_location(&self) {
-        let imp = self.imp();
-
-        let location = imp.peer.borrow().as_ref().and_then(|peer| peer.location());
-        self.set_visible(location.is_some());
-
-        if let Some(location) = location {
-            self.set_location(location.latitude, location.longitude);
-        }
-    }
 }
@@ -0,0 +1,181 @@
+use adw::prelude::*;
+use gtk::{
+    glib::{self, clone, closure_local},
+    subclass::prelude::*,
+};
+
+use crate::{
+    alert::{Alert, AlertResponse, AlertResponseKind},
+    Application,
+};
+
+mod imp {
+    use std::{cell::RefCell, sync::OnceLock};
+
+    use glib::subclass::Signal;
+
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/seadve/Delta/ui/alert_responses_page.ui")]
+    pub struct AlertResponsesPage {
+        #[template_child]
+        pub(super) back_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) response_list_box: TemplateChild<gtk::ListBox>,
+
+        pub(super) alert: RefCell<Option<Alert>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AlertResponsesPage {
+        const NAME: &'static str = "DeltaAlertResponsesPage";
+        type Type = super::AlertResponsesPage;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for AlertResponsesPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            self.back_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.emit_by_name::<()>("done", &[]);
+                }
+            ));
+
+            let placeholder_label = gtk::Label::builder()
+                .margin_top(12)
+                .margin_bottom(12)
+                .margin_start(12)
+                .margin_end(12)
+                .label("No Responses Yet")
+                .build();
+            placeholder_label.add_css_class("dim-label");
+            self.response_list_box
+                .set_placeholder(Some(&placeholder_label));
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+
+            SIGNALS.get_or_init(|| vec![Signal::builder("done").build()])
+        }
+    }
+
+    impl WidgetImpl for AlertResponsesPage {}
+}
+
+glib::wrapper! {
+    pub struct AlertResponsesPage(ObjectSubclass<imp::AlertResponsesPage>)
+        @extends gtk::Widget;
+}
+
+impl AlertResponsesPage {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn connect_done<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self) + 'static,
+    {
+        self.connect_closure("done", false, closure_local!(|obj: &Self| f(obj)))
+    }
+
+    pub fn alert(&self) -> Option<Alert> {
+        self.imp().alert.borrow().clone()
+    }
+
+    /// Shows the peers that acknowledged or are responding to our alert.
+    pub fn set_alert(&self, alert: Option<&Alert>) {
+        let imp = self.imp();
+
+        if let Some(alert) = alert {
+            imp.title_label
+                .set_label(&format!("Responses to {} Alert", alert.alert_type()));
+        }
+
+        let model_alert = alert.cloned();
+        imp.response_list_box.bind_model(alert, move |response| {
+            let response = response.downcast_ref::<AlertResponse>().unwrap();
+            response_row(model_alert.as_ref().unwrap(), response).upcast()
+        });
+
+        imp.alert.replace(alert.cloned());
+    }
+}
+
+fn response_row(alert: &Alert, response: &AlertResponse) -> adw::ActionRow {
+    let row = adw::ActionRow::builder()
+        .title(response.responder_name())
+        .activatable(false)
+        .build();
+
+    let icon = gtk::Image::new();
+    row.add_prefix(&icon);
+
+    let update_row = clone!(
+        #[weak]
+        alert,
+        #[weak]
+        row,
+        #[weak]
+        icon,
+        move |response: &AlertResponse| {
+            let kind_str = match response.kind() {
+                AlertResponseKind::Acknowledged => {
+                    icon.set_icon_name(Some("check-plain-symbolic"));
+                    "Acknowledged"
+                }
+                AlertResponseKind::Responding => {
+                    icon.set_icon_name(Some("driving-symbolic"));
+                    "Responding"
+                }
+            };
+
+            // Fall back to our current location if we had no fix when sending the alert
+            let target = alert
+                .location()
+                .or_else(|| Application::get().gps().location());
+
+            let distance_str = target
+                .and_then(|target| response.distance(&target))
+                .map(|distance| format!("{:.0} m away", distance));
+            let eta_str = target
+                .and_then(|target| response.eta(&target))
+                .map(|eta| format!("ETA {} min", eta.as_secs().div_ceil(60)));
+
+            let subtitle = [Some(kind_str.to_string()), distance_str, eta_str]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" • ");
+            row.set_subtitle(&subtitle);
+        }
+    );
+    update_row(response);
+    response.connect_kind_notify(update_row.clone());
+    response.connect_location_notify(update_row.clone());
+    response.connect_speed_notify(update_row);
+
+    row
+}
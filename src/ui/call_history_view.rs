@@ -0,0 +1,188 @@
+use adw::prelude::*;
+use anyhow::Result;
+use gtk::{
+    gio,
+    glib::{self, clone},
+    subclass::prelude::*,
+};
+
+use crate::{
+    call_history::{CallDirection, CallRecord},
+    ui::call_page,
+    Application,
+};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/seadve/Delta/ui/call_history_view.ui")]
+    pub struct CallHistoryView {
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) clear_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) export_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CallHistoryView {
+        const NAME: &'static str = "DeltaCallHistoryView";
+        type Type = super::CallHistoryView;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for CallHistoryView {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let call_history = Application::get().call_history();
+
+            let placeholder_label = gtk::Label::builder()
+                .margin_top(12)
+                .margin_bottom(12)
+                .margin_start(12)
+                .margin_end(12)
+                .label("No Call History")
+                .build();
+            self.list_box.set_placeholder(Some(&placeholder_label));
+
+            self.list_box.bind_model(Some(&call_history), |record| {
+                let record = record.downcast_ref::<CallRecord>().unwrap();
+
+                let subtitle = format!(
+                    "{} · {} · {}",
+                    record.start_date_time().format("%b %e, %H:%M").unwrap(),
+                    call_page::format_time(record.duration_secs()),
+                    record.end_reason()
+                );
+
+                let row = adw::ActionRow::builder()
+                    .title(record.peer_name())
+                    .subtitle(subtitle)
+                    .build();
+
+                let icon_name = match record.direction() {
+                    CallDirection::Incoming => "call-incoming-symbolic",
+                    CallDirection::Outgoing => "call-start-symbolic",
+                };
+                row.add_prefix(&gtk::Image::from_icon_name(icon_name));
+
+                row.upcast()
+            });
+
+            self.clear_button.connect_clicked(|_| {
+                Application::get().call_history().clear();
+            });
+            self.export_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    glib::spawn_future_local(clone!(
+                        #[weak]
+                        obj,
+                        async move {
+                            if let Err(err) = obj.export_csv().await {
+                                tracing::error!("Failed to export call history: {:?}", err);
+
+                                obj.imp()
+                                    .toast_overlay
+                                    .add_toast(adw::Toast::new("Failed to export call history"));
+                            }
+                        }
+                    ));
+                }
+            ));
+
+            call_history.connect_items_changed(clone!(
+                #[weak]
+                obj,
+                move |_, _, _, _| {
+                    obj.update_buttons_sensitivity();
+                }
+            ));
+
+            obj.update_buttons_sensitivity();
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for CallHistoryView {}
+}
+
+glib::wrapper! {
+    pub struct CallHistoryView(ObjectSubclass<imp::CallHistoryView>)
+        @extends gtk::Widget;
+}
+
+impl CallHistoryView {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    async fn export_csv(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let now = glib::DateTime::now_local()?;
+        let dialog = gtk::FileDialog::builder()
+            .title("Export Call History")
+            .initial_name(format!("call-history-{}.csv", now.format("%Y%m%d-%H%M%S")?))
+            .modal(true)
+            .build();
+
+        let file = match dialog
+            .save_future(self.root().and_downcast_ref::<gtk::Window>())
+            .await
+        {
+            Ok(file) => file,
+            Err(err) => {
+                if err.matches(gtk::DialogError::Dismissed) {
+                    return Ok(());
+                }
+
+                return Err(err.into());
+            }
+        };
+
+        let csv = Application::get().call_history().to_csv();
+        file.replace_contents_future(
+            csv.into_bytes(),
+            None,
+            false,
+            gio::FileCreateFlags::REPLACE_DESTINATION,
+        )
+        .await
+        .map_err(|(_, err)| err)?;
+
+        tracing::debug!("Exported call history to {}", file.uri());
+
+        imp.toast_overlay
+            .add_toast(adw::Toast::new("Exported call history"));
+
+        Ok(())
+    }
+
+    fn update_buttons_sensitivity(&self) {
+        let imp = self.imp();
+
+        let has_records = Application::get().call_history().n_items() > 0;
+        imp.clear_button.set_sensitive(has_records);
+        imp.export_button.set_sensitive(has_records);
+    }
+}
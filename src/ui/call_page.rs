@@ -1,12 +1,13 @@
+use adw::prelude::*;
 use gtk::{
     glib::{self, clone, closure_local},
-    prelude::*,
     subclass::prelude::*,
 };
 
 use crate::{
     call::{Call, CallState},
     peer::Peer,
+    voice_channel::VoiceChannel,
     Application,
 };
 
@@ -26,6 +27,8 @@ mod imp {
     pub struct CallPage {
         #[property(get, set = Self::set_call, explicit_notify, nullable)]
         pub(super) call: RefCell<Option<Call>>,
+        #[property(get, set = Self::set_voice_channel, explicit_notify, nullable)]
+        pub(super) voice_channel: RefCell<Option<VoiceChannel>>,
 
         #[template_child]
         pub(super) vbox: TemplateChild<gtk::Box>, // Unused
@@ -40,6 +43,8 @@ mod imp {
         #[template_child]
         pub(super) wireless_status_icon: TemplateChild<gtk::Image>,
         #[template_child]
+        pub(super) call_stats_label: TemplateChild<gtk::Label>,
+        #[template_child]
         pub(super) stack: TemplateChild<gtk::Stack>,
         #[template_child]
         pub(super) incoming_page: TemplateChild<gtk::Box>,
@@ -57,11 +62,19 @@ mod imp {
         pub(super) duration_label: TemplateChild<gtk::Label>,
         #[template_child]
         pub(super) end_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) channel_page: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) participants_list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) leave_button: TemplateChild<gtk::Button>,
 
         pub(super) call_signals: OnceCell<glib::SignalGroup>,
         pub(super) call_bindings: glib::BindingGroup,
 
         pub(super) peer_signals: OnceCell<glib::SignalGroup>,
+
+        pub(super) participants_changed_handler_id: RefCell<Option<glib::SignalHandlerId>>,
     }
 
     #[glib::object_subclass]
@@ -117,6 +130,24 @@ mod imp {
                 }
             ));
 
+            self.leave_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.emit_by_name::<()>("channel-left", &[]);
+                }
+            ));
+
+            let placeholder_label = gtk::Label::builder()
+                .margin_top(12)
+                .margin_bottom(12)
+                .margin_start(12)
+                .margin_end(12)
+                .label("Waiting for Others to Join")
+                .build();
+            self.participants_list_box
+                .set_placeholder(Some(&placeholder_label));
+
             let call_signals = glib::SignalGroup::new::<Call>();
             call_signals.connect_notify_local(
                 Some("state"),
@@ -138,6 +169,18 @@ mod imp {
                     }
                 ),
             );
+            for property_name in ["state", "latency-ms", "late-packets", "bitrate"] {
+                call_signals.connect_notify_local(
+                    Some(property_name),
+                    clone!(
+                        #[weak]
+                        obj,
+                        move |_, _| {
+                            obj.update_call_stats_label();
+                        }
+                    ),
+                );
+            }
             self.call_signals.set(call_signals).unwrap();
 
             let peer_signals = glib::SignalGroup::new::<Peer>();
@@ -211,6 +254,7 @@ mod imp {
             obj.update_caller_speed_label();
             obj.update_duration_label();
             obj.update_wireless_status_icon();
+            obj.update_call_stats_label();
             obj.update_image_icon_name();
         }
 
@@ -227,6 +271,7 @@ mod imp {
                     Signal::builder("incoming-declined").build(),
                     Signal::builder("outgoing-cancelled").build(),
                     Signal::builder("ongoing-ended").build(),
+                    Signal::builder("channel-left").build(),
                 ]
             })
         }
@@ -253,10 +298,68 @@ mod imp {
             obj.update_caller_speed_label();
             obj.update_duration_label();
             obj.update_wireless_status_icon();
+            obj.update_call_stats_label();
             obj.update_image_icon_name();
 
             obj.notify_call();
         }
+
+        fn set_voice_channel(&self, voice_channel: Option<VoiceChannel>) {
+            let obj = self.obj();
+
+            if voice_channel == obj.voice_channel() {
+                return;
+            }
+
+            if let Some(prev_voice_channel) = self.voice_channel.replace(voice_channel.clone()) {
+                if let Some(handler_id) = self.participants_changed_handler_id.take() {
+                    prev_voice_channel.participants().disconnect(handler_id);
+                }
+            }
+
+            if let Some(ref voice_channel) = voice_channel {
+                let handler_id = voice_channel.participants().connect_items_changed(clone!(
+                    #[weak]
+                    obj,
+                    move |_, _, _, _| {
+                        obj.update_caller_distance_label();
+                    }
+                ));
+                self.participants_changed_handler_id
+                    .replace(Some(handler_id));
+            }
+
+            self.participants_list_box.bind_model(
+                voice_channel
+                    .as_ref()
+                    .map(|voice_channel| voice_channel.participants()),
+                |peer| {
+                    let peer = peer.downcast_ref::<Peer>().unwrap();
+
+                    let row = adw::ActionRow::new();
+                    peer.bind_property("name", &row, "title")
+                        .sync_create()
+                        .build();
+
+                    let image = gtk::Image::new();
+                    peer.bind_property("icon-name", &image, "icon-name")
+                        .sync_create()
+                        .build();
+                    row.add_prefix(&image);
+
+                    row.upcast()
+                },
+            );
+
+            obj.update_stack();
+            obj.update_caller_name_label();
+            obj.update_caller_distance_label();
+            obj.update_caller_speed_label();
+            obj.update_wireless_status_icon();
+            obj.update_image_icon_name();
+
+            obj.notify_voice_channel();
+        }
     }
 }
 
@@ -310,16 +413,38 @@ impl CallPage {
         self.connect_closure("ongoing-ended", false, closure_local!(|obj: &Self| f(obj)))
     }
 
+    pub fn connect_channel_left<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self) + 'static,
+    {
+        self.connect_closure("channel-left", false, closure_local!(|obj: &Self| f(obj)))
+    }
+
     fn update_caller_name_label(&self) {
         let imp = self.imp();
 
-        let name = self.call().map(|call| call.peer().name());
+        let name = if let Some(voice_channel) = self.voice_channel() {
+            Some(format!("Channel “{}”", voice_channel.name()))
+        } else {
+            self.call().map(|call| call.peer().name())
+        };
         imp.caller_name_label.set_label(&name.unwrap_or_default());
     }
 
     fn update_caller_distance_label(&self) {
         let imp = self.imp();
 
+        if let Some(voice_channel) = self.voice_channel() {
+            let n_participants = voice_channel.participants().n_items();
+            let label = if n_participants == 1 {
+                "1 participant".to_string()
+            } else {
+                format!("{} participants", n_participants)
+            };
+            imp.caller_distance_label.set_label(&label);
+            return;
+        }
+
         let distance_str = self
             .call()
             .and_then(|call| call.peer().location())
@@ -346,6 +471,11 @@ impl CallPage {
     fn update_stack(&self) {
         let imp = self.imp();
 
+        if self.voice_channel().is_some() {
+            imp.stack.set_visible_child(&*imp.channel_page);
+            return;
+        }
+
         match self.call().map(|call| call.state()) {
             Some(CallState::Incoming) => {
                 imp.stack.set_visible_child(&*imp.incoming_page);
@@ -379,14 +509,37 @@ impl CallPage {
 
         imp.wireless_status_icon
             .set_icon_name(Some(signal_quality.icon_name()));
+        imp.wireless_status_icon.set_visible(self.call().is_some());
 
         signal_quality.apply_css_class_to_image(&imp.wireless_status_icon);
     }
 
+    fn update_call_stats_label(&self) {
+        let imp = self.imp();
+
+        let stats_str = self
+            .call()
+            .filter(|call| call.state() == CallState::Ongoing)
+            .map(|call| {
+                format!(
+                    "{} ms · {:.1}% late · {} kbps",
+                    call.latency_ms(),
+                    call.late_packets() * 100.0,
+                    call.bitrate() / 1000
+                )
+            });
+        imp.call_stats_label
+            .set_label(&stats_str.unwrap_or_default());
+    }
+
     fn update_image_icon_name(&self) {
         let imp = self.imp();
 
-        let icon_name = self.call().map(|call| call.peer().icon_name());
+        let icon_name = if self.voice_channel().is_some() {
+            Some("system-users-symbolic".to_string())
+        } else {
+            self.call().map(|call| call.peer().icon_name())
+        };
         imp.image.set_icon_name(icon_name.as_deref());
     }
 }
@@ -394,7 +547,7 @@ impl CallPage {
 /// Formats time in MM:SS.
 ///
 /// The MM part will be more than 2 digits if the time is >= 100 minutes.
-fn format_time(secs: u64) -> String {
+pub fn format_time(secs: u64) -> String {
     let seconds_display = secs % 60;
     let minutes_display = secs / 60;
     format!("{:02}∶{:02}", minutes_display, seconds_display)
@@ -0,0 +1,328 @@
+use gtk::{
+    glib::{self, clone, closure_local},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{
+    peer::Peer,
+    text_message::{Conversation, TextMessage, TextMessageStatus, QUICK_REPLIES},
+};
+
+mod imp {
+    use std::{cell::RefCell, sync::OnceLock};
+
+    use glib::subclass::Signal;
+
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/seadve/Delta/ui/conversation_page.ui")]
+    pub struct ConversationPage {
+        #[template_child]
+        pub(super) back_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) scrolled_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub(super) message_list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) quick_reply_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub(super) send_button: TemplateChild<gtk::Button>,
+
+        pub(super) peer: RefCell<Option<Peer>>,
+        pub(super) conversation: RefCell<Option<(Conversation, glib::SignalHandlerId)>>,
+        pub(super) title_binding: RefCell<Option<glib::Binding>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ConversationPage {
+        const NAME: &'static str = "DeltaConversationPage";
+        type Type = super::ConversationPage;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ConversationPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            self.back_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.emit_by_name::<()>("done", &[]);
+                }
+            ));
+
+            for quick_reply in QUICK_REPLIES {
+                let button = gtk::Button::builder().label(*quick_reply).build();
+                button.add_css_class("pill");
+                button.connect_clicked(clone!(
+                    #[weak]
+                    obj,
+                    move |_| {
+                        obj.emit_by_name::<()>("send-requested", &[&quick_reply.to_string()]);
+                    }
+                ));
+                self.quick_reply_box.append(&button);
+            }
+
+            self.entry.connect_activate(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.send_entry_text();
+                }
+            ));
+            self.send_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.send_entry_text();
+                }
+            ));
+            self.entry.connect_changed(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.update_send_button_sensitivity();
+                }
+            ));
+
+            let placeholder_label = gtk::Label::builder()
+                .margin_top(12)
+                .margin_bottom(12)
+                .margin_start(12)
+                .margin_end(12)
+                .label("No Messages")
+                .build();
+            placeholder_label.add_css_class("dim-label");
+            self.message_list_box
+                .set_placeholder(Some(&placeholder_label));
+
+            obj.update_send_button_sensitivity();
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("done").build(),
+                    Signal::builder("send-requested")
+                        .param_types([String::static_type()])
+                        .build(),
+                ]
+            })
+        }
+    }
+
+    impl WidgetImpl for ConversationPage {}
+}
+
+glib::wrapper! {
+    pub struct ConversationPage(ObjectSubclass<imp::ConversationPage>)
+        @extends gtk::Widget;
+}
+
+impl ConversationPage {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn connect_done<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self) + 'static,
+    {
+        self.connect_closure("done", false, closure_local!(|obj: &Self| f(obj)))
+    }
+
+    pub fn connect_send_requested<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self, &str) + 'static,
+    {
+        self.connect_closure(
+            "send-requested",
+            false,
+            closure_local!(|obj: &Self, text: &str| f(obj, text)),
+        )
+    }
+
+    /// Returns the peer of the conversation, or `None` if it is the broadcast conversation.
+    pub fn peer(&self) -> Option<Peer> {
+        self.imp().peer.borrow().clone()
+    }
+
+    /// Shows the conversation with the peer, or the broadcast one if `peer` is `None`.
+    pub fn set_conversation(&self, peer: Option<&Peer>, conversation: Option<&Conversation>) {
+        let imp = self.imp();
+
+        if let Some(binding) = imp.title_binding.take() {
+            binding.unbind();
+        }
+
+        if let Some((prev_conversation, handler_id)) = imp.conversation.take() {
+            prev_conversation.disconnect(handler_id);
+        }
+
+        if let Some(peer) = peer {
+            let binding = peer
+                .bind_property("name", &*imp.title_label, "label")
+                .sync_create()
+                .build();
+            imp.title_binding.replace(Some(binding));
+        } else {
+            imp.title_label.set_label("All Nearby Peers");
+        }
+
+        imp.message_list_box.bind_model(conversation, |message| {
+            let message = message.downcast_ref::<TextMessage>().unwrap();
+            message_row(message).upcast()
+        });
+
+        if let Some(conversation) = conversation {
+            let handler_id = conversation.connect_items_changed(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_, _, _, _| {
+                    obj.scroll_to_bottom();
+                }
+            ));
+            imp.conversation
+                .replace(Some((conversation.clone(), handler_id)));
+        }
+
+        imp.peer.replace(peer.cloned());
+        imp.entry.set_text("");
+
+        self.scroll_to_bottom();
+    }
+
+    fn send_entry_text(&self) {
+        let imp = self.imp();
+
+        let text = imp.entry.text();
+        let text = text.trim();
+
+        if text.is_empty() {
+            return;
+        }
+
+        self.emit_by_name::<()>("send-requested", &[&text.to_string()]);
+
+        imp.entry.set_text("");
+    }
+
+    fn scroll_to_bottom(&self) {
+        glib::idle_add_local_once(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move || {
+                let adjustment = obj.imp().scrolled_window.vadjustment();
+                adjustment.set_value(adjustment.upper());
+            }
+        ));
+    }
+
+    fn update_send_button_sensitivity(&self) {
+        let imp = self.imp();
+
+        imp.send_button
+            .set_sensitive(!imp.entry.text().trim().is_empty());
+    }
+}
+
+fn message_row(message: &TextMessage) -> gtk::ListBoxRow {
+    let text_label = gtk::Label::builder()
+        .label(message.text())
+        .wrap(true)
+        .xalign(0.0)
+        .build();
+    text_label.add_css_class("message-bubble");
+    if message.is_outgoing() {
+        text_label.add_css_class("outgoing");
+    } else {
+        text_label.add_css_class("card");
+    }
+
+    let status_label = gtk::Label::new(None);
+    status_label.add_css_class("caption");
+    status_label.add_css_class("dim-label");
+
+    let update_status_label = |message: &TextMessage, status_label: &gtk::Label| {
+        let time_str = message.date_time().format("%H:%M").unwrap();
+
+        let status_str = match message.status() {
+            TextMessageStatus::Sending => Some("Sending…".to_string()),
+            TextMessageStatus::Delivered if message.is_broadcast() => {
+                Some(format!("Delivered to {}", message.delivered_count()))
+            }
+            TextMessageStatus::Delivered => Some("Delivered".to_string()),
+            TextMessageStatus::Failed => Some("Not Delivered".to_string()),
+            TextMessageStatus::Received if message.is_broadcast() => Some("Broadcast".to_string()),
+            TextMessageStatus::Received => None,
+        };
+
+        let label = [Some(time_str.to_string()), status_str]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" • ");
+        status_label.set_label(&label);
+    };
+    update_status_label(message, &status_label);
+    message.connect_status_notify(clone!(
+        #[weak]
+        status_label,
+        move |message| {
+            update_status_label(message, &status_label);
+        }
+    ));
+    message.connect_delivered_count_notify(clone!(
+        #[weak]
+        status_label,
+        move |message| {
+            update_status_label(message, &status_label);
+        }
+    ));
+
+    let halign = if message.is_outgoing() {
+        gtk::Align::End
+    } else {
+        gtk::Align::Start
+    };
+    text_label.set_halign(halign);
+    status_label.set_halign(halign);
+
+    let vbox = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(3)
+        .margin_top(3)
+        .margin_bottom(3)
+        .build();
+    vbox.append(&text_label);
+    vbox.append(&status_label);
+
+    gtk::ListBoxRow::builder()
+        .activatable(false)
+        .child(&vbox)
+        .build()
+}
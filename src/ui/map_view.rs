@@ -10,11 +10,15 @@ use gtk::{
 use shumate::prelude::*;
 
 use crate::{
+    alert::Alert,
     location::Location,
     peer::Peer,
     peer_list::PeerList,
     place_finder::{Place, PlaceFinder, PlaceType},
-    ui::{alert_marker::AlertMarker, peer_marker::PeerMarker, place_marker::PlaceMarker},
+    ui::{
+        accuracy_circle::AccuracyCircle, alert_marker::AlertMarker, peer_marker::PeerMarker,
+        place_marker::PlaceMarker,
+    },
 };
 
 const DEFAULT_ZOOM_LEVEL: f64 = 20.0;
@@ -56,7 +60,9 @@ mod imp {
 
         pub(super) marker_layer: OnceCell<shumate::MarkerLayer>,
         pub(super) our_marker: OnceCell<shumate::Marker>,
-        pub(super) peer_markers: RefCell<Vec<(Peer, PeerMarker, AlertMarker)>>,
+        pub(super) our_accuracy_circle: OnceCell<AccuracyCircle>,
+        pub(super) peer_markers: RefCell<Vec<(Peer, PeerMarker)>>,
+        pub(super) alert_markers: RefCell<Vec<AlertMarker>>,
 
         pub(super) places_marker_layer: OnceCell<shumate::MarkerLayer>,
         pub(super) place_finder: PlaceFinder,
@@ -103,6 +109,15 @@ mod imp {
             self.map.add_layer(&marker_layer);
             self.marker_layer.set(marker_layer).unwrap();
 
+            // Added first so it is drawn below our marker
+            let accuracy_circle = AccuracyCircle::new(&viewport);
+            accuracy_circle.set_visible(false);
+            self.marker_layer
+                .get()
+                .unwrap()
+                .add_marker(&accuracy_circle);
+            self.our_accuracy_circle.set(accuracy_circle).unwrap();
+
             let image = gtk::Image::from_icon_name("map-marker-symbolic");
             image.add_css_class("map-marker");
 
@@ -276,14 +291,10 @@ impl MapView {
                         }
                     ));
 
-                    let alert_marker = AlertMarker::new();
-                    alert_marker.set_peer(Some(peer.clone()));
-
                     let marker_layer = imp.marker_layer.get().unwrap();
                     marker_layer.add_marker(&peer_marker);
-                    marker_layer.add_marker(&alert_marker);
 
-                    (peer, peer_marker, alert_marker)
+                    (peer, peer_marker)
                 });
                 let removed = imp
                     .peer_markers
@@ -294,10 +305,9 @@ impl MapView {
                     )
                     .collect::<Vec<_>>();
 
-                for (_, peer_marker, alert_marker) in removed {
+                for (_, peer_marker) in removed {
                     let marker_layer = imp.marker_layer.get().unwrap();
                     marker_layer.remove_marker(&peer_marker);
-                    marker_layer.remove_marker(&alert_marker);
                 }
             }
         ));
@@ -309,8 +319,12 @@ impl MapView {
         let our_marker = imp.our_marker.get().unwrap();
         our_marker.set_visible(location.is_some());
 
+        let accuracy_circle = imp.our_accuracy_circle.get().unwrap();
+        accuracy_circle.set_visible(location.is_some() && accuracy_circle.radius() > 0.0);
+
         if let Some(location) = &location {
             our_marker.set_location(location.latitude, location.longitude);
+            accuracy_circle.set_location(location.latitude, location.longitude);
 
             let viewport = imp.map.viewport().unwrap();
             if viewport.latitude() == 0.0 && viewport.longitude() == 0.0 {
@@ -327,6 +341,15 @@ impl MapView {
         *self.imp().location.borrow()
     }
 
+    /// Sets the radius in meters around our location within which we likely are.
+    pub fn set_location_accuracy(&self, accuracy: Option<f64>) {
+        let imp = self.imp();
+
+        let accuracy_circle = imp.our_accuracy_circle.get().unwrap();
+        accuracy_circle.set_radius(accuracy.unwrap_or(0.0));
+        accuracy_circle.set_visible(self.location().is_some() && accuracy.is_some());
+    }
+
     pub fn go_to(&self, location: &Location) {
         let imp = self.imp();
 
@@ -338,15 +361,64 @@ impl MapView {
         );
     }
 
-    pub fn play_alert_animation(&self, peer: &Peer, repeat_count: u32, color: gdk::RGBA) {
+    /// Shows the alert at its location until it expires.
+    pub fn add_alert(&self, alert: &Alert, color: gdk::RGBA) {
+        let imp = self.imp();
+
+        if alert.location().is_none() || alert.is_expired() {
+            return;
+        }
+
+        if imp
+            .alert_markers
+            .borrow()
+            .iter()
+            .any(|marker| marker.alert().id() == alert.id())
+        {
+            return;
+        }
+
+        let alert_marker = AlertMarker::new(alert, color);
+        imp.marker_layer.get().unwrap().add_marker(&alert_marker);
+
+        alert.connect_is_expired_notify(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |alert| {
+                obj.remove_alert(alert);
+            }
+        ));
+
+        imp.alert_markers.borrow_mut().push(alert_marker);
+    }
+
+    pub fn play_alert_animation(&self, alert: &Alert, repeat_count: u32, color: gdk::RGBA) {
         let imp = self.imp();
 
-        if let Some((_, _, alert_marker)) =
-            imp.peer_markers.borrow().iter().find(|(p, _, _)| p == peer)
+        if let Some(alert_marker) = imp
+            .alert_markers
+            .borrow()
+            .iter()
+            .find(|marker| marker.alert().id() == alert.id())
         {
             alert_marker.play_animation(repeat_count, color);
         } else {
-            tracing::warn!("Failed to play alert animation: No marker found for peer");
+            tracing::warn!("Failed to play alert animation: No marker found for alert");
+        }
+    }
+
+    fn remove_alert(&self, alert: &Alert) {
+        let imp = self.imp();
+
+        let position = imp
+            .alert_markers
+            .borrow()
+            .iter()
+            .position(|marker| marker.alert().id() == alert.id());
+
+        if let Some(position) = position {
+            let alert_marker = imp.alert_markers.borrow_mut().remove(position);
+            imp.marker_layer.get().unwrap().remove_marker(&alert_marker);
         }
     }
 
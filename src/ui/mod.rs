@@ -1,5 +1,10 @@
+mod accuracy_circle;
+mod alert_log_view;
 mod alert_marker;
+mod alert_responses_page;
+mod call_history_view;
 mod call_page;
+mod conversation_page;
 mod crashed_page;
 mod listening_overlay;
 mod map_view;
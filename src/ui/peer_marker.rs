@@ -2,7 +2,7 @@ use adw::prelude::*;
 use gtk::glib::{self, clone, closure_local};
 use shumate::{prelude::*, subclass::prelude::*};
 
-use crate::{peer::Peer, ui::toggle_button::ToggleButton, Application};
+use crate::{authority::PeerRole, peer::Peer, ui::toggle_button::ToggleButton, Application};
 
 mod imp {
     use std::{
@@ -17,6 +17,8 @@ mod imp {
     #[derive(Default, gtk::CompositeTemplate)]
     #[template(resource = "/io/github/seadve/Delta/ui/peer_marker.ui")]
     pub struct PeerMarker {
+        #[template_child]
+        pub(super) direction_arrow: TemplateChild<gtk::DrawingArea>,
         #[template_child]
         pub(super) image: TemplateChild<gtk::Image>,
         #[template_child]
@@ -92,6 +94,16 @@ mod imp {
                     }
                 ),
             );
+            peer_signals.connect_notify_local(
+                Some("heading"),
+                clone!(
+                    #[weak]
+                    obj,
+                    move |_, _| {
+                        obj.update_direction_arrow();
+                    }
+                ),
+            );
             peer_signals.connect_notify_local(
                 Some("signal-quality"),
                 clone!(
@@ -112,8 +124,55 @@ mod imp {
                     }
                 ),
             );
+            peer_signals.connect_notify_local(
+                Some("is-emergency-en-route"),
+                clone!(
+                    #[weak]
+                    obj,
+                    move |_, _| {
+                        obj.update_emergency_indicator();
+                    }
+                ),
+            );
+            peer_signals.connect_notify_local(
+                Some("role"),
+                clone!(
+                    #[weak]
+                    obj,
+                    move |_, _| {
+                        obj.update_emergency_indicator();
+                    }
+                ),
+            );
             self.peer_signals.set(peer_signals).unwrap();
 
+            self.direction_arrow.set_draw_func(clone!(
+                #[weak]
+                obj,
+                move |area, cr, width, height| {
+                    let Some(heading) = obj.peer().and_then(|peer| peer.known_heading()) else {
+                        return;
+                    };
+
+                    let size = width.min(height) as f64;
+
+                    cr.translate(width as f64 / 2.0, height as f64 / 2.0);
+                    cr.rotate(heading.to_radians());
+
+                    // Arrow pointing up, to the north, before rotation
+                    cr.move_to(0.0, -size / 2.0);
+                    cr.line_to(size * 0.4, size / 2.0);
+                    cr.line_to(0.0, size * 0.25);
+                    cr.line_to(-size * 0.4, size / 2.0);
+                    cr.close_path();
+
+                    cr.set_source_color(&area.color());
+                    if let Err(err) = cr.fill() {
+                        tracing::warn!("Failed to draw direction arrow: {:?}", err);
+                    }
+                }
+            ));
+
             let gesture_click = gtk::GestureClick::new();
             gesture_click.connect_released(clone!(
                 #[weak]
@@ -147,10 +206,18 @@ mod imp {
                         return;
                     };
 
+                    // Don't convert a legacy name-based mute when the button is synced to it
+                    let peer_name = peer.name();
+                    if button.is_active() == settings.muted_peers().contains(peer.id(), &peer_name)
+                    {
+                        return;
+                    }
+
                     if button.is_active() {
-                        settings.insert_muted_peer(peer.name());
+                        settings.insert_muted_peer(*peer.id(), peer_name);
                     } else {
-                        settings.remove_muted_peer(&peer.name());
+                        settings.remove_muted_peer(peer.id());
+                        settings.remove_legacy_muted_peer(&peer_name);
                     }
                 }
             ));
@@ -177,8 +244,10 @@ mod imp {
             obj.update_distance_label();
             obj.update_speed_label();
             obj.update_location();
+            obj.update_direction_arrow();
             obj.update_wireless_status_icon();
             obj.update_image_icon_name();
+            obj.update_emergency_indicator();
             obj.update_mute_button();
         }
 
@@ -225,8 +294,10 @@ impl PeerMarker {
         self.update_distance_label();
         self.update_speed_label();
         self.update_location();
+        self.update_direction_arrow();
         self.update_wireless_status_icon();
         self.update_image_icon_name();
+        self.update_emergency_indicator();
         self.update_mute_button();
     }
 
@@ -286,6 +357,16 @@ impl PeerMarker {
         }
     }
 
+    fn update_direction_arrow(&self) {
+        let imp = self.imp();
+
+        // Hide without changing the size, so the marker doesn't shift on the map
+        let heading = self.peer().and_then(|peer| peer.known_heading());
+        imp.direction_arrow
+            .set_opacity(if heading.is_some() { 1.0 } else { 0.0 });
+        imp.direction_arrow.queue_draw();
+    }
+
     fn update_wireless_status_icon(&self) {
         let imp = self.imp();
 
@@ -307,6 +388,20 @@ impl PeerMarker {
         imp.image.set_icon_name(icon_name.as_deref());
     }
 
+    fn update_emergency_indicator(&self) {
+        let imp = self.imp();
+
+        let is_emergency_en_route = self.peer().is_some_and(|peer| {
+            peer.is_emergency_en_route() && peer.role() == PeerRole::EmergencyServices
+        });
+
+        if is_emergency_en_route {
+            imp.image.add_css_class("emergency-en-route");
+        } else {
+            imp.image.remove_css_class("emergency-en-route");
+        }
+    }
+
     fn update_mute_button(&self) {
         let imp = self.imp();
 
@@ -314,7 +409,7 @@ impl PeerMarker {
             Application::get()
                 .settings()
                 .muted_peers()
-                .contains(&peer.name())
+                .contains(peer.id(), &peer.name())
         });
         imp.mute_button.set_is_active(is_muted);
     }
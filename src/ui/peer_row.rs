@@ -24,6 +24,8 @@ mod imp {
         #[template_child]
         pub(super) call_button: TemplateChild<gtk::Button>,
         #[template_child]
+        pub(super) message_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub(super) view_on_map_button: TemplateChild<gtk::Button>,
         #[template_child]
         pub(super) mute_button: TemplateChild<ToggleButton>,
@@ -58,6 +60,13 @@ mod imp {
                     obj.emit_by_name::<()>("called", &[]);
                 }
             ));
+            self.message_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.emit_by_name::<()>("messaged", &[]);
+                }
+            ));
             self.view_on_map_button.connect_clicked(clone!(
                 #[weak]
                 obj,
@@ -72,10 +81,18 @@ mod imp {
                     let settings = Application::get().settings();
                     let peer = obj.peer();
 
+                    // Don't convert a legacy name-based mute when the button is synced to it
+                    let peer_name = peer.name();
+                    if button.is_active() == settings.muted_peers().contains(peer.id(), &peer_name)
+                    {
+                        return;
+                    }
+
                     if button.is_active() {
-                        settings.insert_muted_peer(peer.name());
+                        settings.insert_muted_peer(*peer.id(), peer_name);
                     } else {
-                        settings.remove_muted_peer(&peer.name());
+                        settings.remove_muted_peer(peer.id());
+                        settings.remove_legacy_muted_peer(&peer_name);
                     }
                 }
             ));
@@ -87,22 +104,29 @@ mod imp {
             peer.bind_property("icon-name", &*self.image, "icon-name")
                 .sync_create()
                 .build();
-            peer.connect_name_notify(clone!(
+            peer.connect_location_notify(clone!(
                 #[weak]
                 obj,
                 move |_| {
-                    obj.update_mute_button();
+                    obj.update_subtitle();
+                    obj.update_view_on_map_button_sensitivity();
                 }
             ));
-            peer.connect_location_notify(clone!(
+            peer.connect_speed_notify(clone!(
                 #[weak]
                 obj,
                 move |_| {
                     obj.update_subtitle();
-                    obj.update_view_on_map_button_sensitivity();
                 }
             ));
-            peer.connect_speed_notify(clone!(
+            peer.connect_name_notify(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.update_mute_button();
+                }
+            ));
+            peer.connect_role_notify(clone!(
                 #[weak]
                 obj,
                 move |_| {
@@ -147,6 +171,7 @@ mod imp {
             SIGNALS.get_or_init(|| {
                 vec![
                     Signal::builder("called").build(),
+                    Signal::builder("messaged").build(),
                     Signal::builder("viewed-on-map").build(),
                 ]
             })
@@ -176,6 +201,13 @@ impl PeerRow {
         self.connect_closure("called", false, closure_local!(|obj: &Self| f(obj)))
     }
 
+    pub fn connect_messaged<F>(&self, f: F) -> glib::SignalHandlerId
+    where
+        F: Fn(&Self) + 'static,
+    {
+        self.connect_closure("messaged", false, closure_local!(|obj: &Self| f(obj)))
+    }
+
     pub fn connect_viewed_on_map<F>(&self, f: F) -> glib::SignalHandlerId
     where
         F: Fn(&Self) + 'static,
@@ -193,8 +225,9 @@ impl PeerRow {
                 .map(|l| format!("{:.2} m away", l.distance(&location)))
         });
         let speed_str = format!("{:.2} m/s", peer.speed());
+        let role_str = peer.role().is_authority().then(|| peer.role().to_string());
 
-        let subtitle = [distance_str, Some(speed_str)]
+        let subtitle = [role_str, distance_str, Some(speed_str)]
             .into_iter()
             .flatten()
             .collect::<Vec<_>>()
@@ -226,7 +259,7 @@ impl PeerRow {
         let is_muted = Application::get()
             .settings()
             .muted_peers()
-            .contains(&self.peer().name());
+            .contains(self.peer().id(), &self.peer().name());
         imp.mute_button.set_is_active(is_muted);
     }
 }
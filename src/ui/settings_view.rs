@@ -8,7 +8,10 @@ use gtk::{
 };
 use shumate::prelude::*;
 
-use crate::{location::Location, remote::RemoteStatus, settings::AllowedPeers, Application};
+use crate::{
+    authority, identity, location::Location, remote::RemoteStatus, settings::AllowedPeers,
+    Application,
+};
 
 const DEFAULT_MAP_ZOOM_LEVEL: f64 = 16.0;
 const ICON_LIST: &[&str] = &[
@@ -42,6 +45,20 @@ mod imp {
         #[template_child]
         pub(super) muted_peers_row: TemplateChild<adw::ExpanderRow>,
         #[template_child]
+        pub(super) identity_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub(super) regenerate_identity_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) call_ringing_timeout_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub(super) echo_cancellation_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub(super) noise_suppression_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub(super) alert_radius_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub(super) alerts_ahead_only_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
         pub(super) remote_ip_addr_row: TemplateChild<adw::EntryRow>,
         #[template_child]
         pub(super) accel_impact_threshold_row: TemplateChild<adw::SpinRow>,
@@ -105,6 +122,35 @@ mod imp {
                 .bidirectional()
                 .sync_create()
                 .build();
+            settings
+                .bind_property(
+                    "call-ringing-timeout-secs",
+                    &*self.call_ringing_timeout_row,
+                    "value",
+                )
+                .bidirectional()
+                .sync_create()
+                .build();
+            settings
+                .bind_property("echo-cancellation", &*self.echo_cancellation_row, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+            settings
+                .bind_property("noise-suppression", &*self.noise_suppression_row, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+            settings
+                .bind_property("alert-radius-m", &*self.alert_radius_row, "value")
+                .bidirectional()
+                .sync_create()
+                .build();
+            settings
+                .bind_property("alerts-ahead-only", &*self.alerts_ahead_only_row, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
 
             let remote = app.remote();
             remote.connect_status_notify(clone!(
@@ -157,6 +203,22 @@ mod imp {
                     settings.set_icon_name(icon_name);
                 });
 
+            let peer_id = app.keypair().public().to_peer_id();
+            self.identity_row.set_subtitle(&peer_id.to_string());
+            self.regenerate_identity_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    glib::spawn_future_local(clone!(
+                        #[weak]
+                        obj,
+                        async move {
+                            obj.regenerate_identity().await;
+                        }
+                    ));
+                }
+            ));
+
             self.simulate_crash_button.connect_clicked(clone!(
                 #[weak]
                 obj,
@@ -325,6 +387,58 @@ impl SettingsView {
         )
     }
 
+    /// Asks for confirmation, then replaces our identity, removing the credential issued to
+    /// the previous one.
+    async fn regenerate_identity(&self) {
+        let imp = self.imp();
+
+        let credential = Application::get().credential();
+
+        let mut body = String::from(
+            "Peers will no longer recognize you, and those that muted or allowed you will have \
+            to do so again. The new identity is used after restarting.",
+        );
+        if let Some(credential) = &credential {
+            body.push_str(&format!(
+                "\n\nYour {} credential was issued to the current identity, so it will be removed.",
+                credential.role
+            ));
+        }
+
+        let dialog = adw::AlertDialog::builder()
+            .heading("Regenerate Identity?")
+            .body(body)
+            .default_response("cancel")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[("cancel", "_Cancel"), ("regenerate", "_Regenerate")]);
+        dialog.set_response_appearance("regenerate", adw::ResponseAppearance::Destructive);
+
+        if dialog.choose_future(self).await != "regenerate" {
+            return;
+        }
+
+        let keypair = match identity::regenerate() {
+            Ok(keypair) => keypair,
+            Err(err) => {
+                tracing::error!("Failed to regenerate identity: {:?}", err);
+                return;
+            }
+        };
+
+        let peer_id = keypair.public().to_peer_id();
+        imp.identity_row
+            .set_subtitle(&format!("{peer_id} (applies after restart)"));
+
+        if credential.is_some() {
+            tracing::warn!("Removing credential of the previous identity");
+
+            if let Err(err) = authority::remove_credential() {
+                tracing::error!("Failed to remove credential: {:?}", err);
+            }
+        }
+    }
+
     fn update_marker_location(&self) {
         let imp = self.imp();
 
@@ -354,8 +468,38 @@ impl SettingsView {
 
         let muted_peers = settings.muted_peers();
 
-        for peer_name in muted_peers.iter() {
-            let row = adw::ActionRow::builder().title(peer_name).build();
+        for (peer_id, peer_name) in muted_peers.iter() {
+            let row = adw::ActionRow::builder()
+                .title(peer_name)
+                .subtitle(peer_id.to_string())
+                .build();
+
+            let unmute_button = gtk::Button::builder()
+                .icon_name("edit-delete-symbolic")
+                .valign(gtk::Align::Center)
+                .build();
+            unmute_button.add_css_class("flat");
+            unmute_button.connect_clicked(clone!(
+                #[strong]
+                peer_id,
+                #[weak]
+                settings,
+                move |_| {
+                    settings.remove_muted_peer(&peer_id);
+                }
+            ));
+            row.add_suffix(&unmute_button);
+
+            imp.muted_peers_row.add_row(&row);
+
+            imp.muted_peers_row_items.borrow_mut().push(row);
+        }
+
+        for peer_name in muted_peers.iter_legacy_names() {
+            let row = adw::ActionRow::builder()
+                .title(peer_name)
+                .subtitle("Muted by name until a peer is confirmed")
+                .build();
 
             let unmute_button = gtk::Button::builder()
                 .icon_name("edit-delete-symbolic")
@@ -368,7 +512,7 @@ impl SettingsView {
                 #[weak]
                 settings,
                 move |_| {
-                    settings.remove_muted_peer(&peer_name);
+                    settings.remove_legacy_muted_peer(&peer_name);
                 }
             ));
             row.add_suffix(&unmute_button);
@@ -376,6 +520,37 @@ impl SettingsView {
             imp.muted_peers_row.add_row(&row);
 
             imp.muted_peers_row_items.borrow_mut().push(row);
+
+            // Anyone can claim a name, so let the user pick which peer was meant
+            for peer_id in muted_peers.iter_legacy_candidates(peer_name) {
+                let row = adw::ActionRow::builder()
+                    .title(peer_name)
+                    .subtitle(format!("Seen as {}", peer_id))
+                    .build();
+
+                let confirm_button = gtk::Button::builder()
+                    .icon_name("object-select-symbolic")
+                    .tooltip_text("Mute Only This Peer")
+                    .valign(gtk::Align::Center)
+                    .build();
+                confirm_button.add_css_class("flat");
+                confirm_button.connect_clicked(clone!(
+                    #[strong]
+                    peer_name,
+                    #[strong]
+                    peer_id,
+                    #[weak]
+                    settings,
+                    move |_| {
+                        settings.confirm_legacy_muted_peer(&peer_name, peer_id);
+                    }
+                ));
+                row.add_suffix(&confirm_button);
+
+                imp.muted_peers_row.add_row(&row);
+
+                imp.muted_peers_row_items.borrow_mut().push(row);
+            }
         }
 
         imp.muted_peers_row
@@ -1,37 +1,56 @@
+mod alerts;
+mod warnings;
+
 use std::time::Duration;
 
 use adw::{prelude::*, subclass::prelude::*};
 use anyhow::Result;
 use gst::prelude::*;
 use gtk::glib::{self, clone};
+use libp2p::PeerId;
 
 use crate::{
-    application::{Application, ALERT_LED_ID},
+    alert::{Alert, AlertResponseKind, AlertType},
+    application::Application,
+    authority::PeerRole,
     call::{Call, CallEndReason, CallState},
-    client::{AlertType, Client},
-    colors,
-    gps::FixMode,
-    location::Location,
+    client::Client,
+    gps::{self, FixMode},
+    missed_call_list::MissedCall,
     peer::Peer,
     place_finder::PlaceType,
-    remote::LedColor,
     settings::AllowedPeers,
     stt::Stt,
+    text_message::QUICK_REPLIES,
     tts,
     ui::{
-        call_page::CallPage, crashed_page::CrashedPage, listening_overlay::ListeningOverlay,
-        map_view::MapView, peer_row::PeerRow, place_page::PlacePage, settings_view::SettingsView,
+        alert_log_view::AlertLogView, alert_responses_page::AlertResponsesPage,
+        call_history_view::CallHistoryView, call_page::CallPage,
+        conversation_page::ConversationPage, crashed_page::CrashedPage,
+        listening_overlay::ListeningOverlay, map_view::MapView, peer_row::PeerRow,
+        place_page::PlacePage, settings_view::SettingsView,
     },
+    voice_message::{self, VoiceMessage, MAX_VOICE_MESSAGE_DURATION},
     GRESOURCE_PREFIX,
 };
 
 const ALERT_LED_BLINK_INTERVAL: Duration = Duration::from_millis(500);
 
+const ALERT_GRID_COLUMNS: i32 = 4;
+
 const ALERT_AUTO_BROADCAST_WITHOUT_RESPONSE_DURATION: Duration = Duration::from_secs(30);
 
+/// How long we talk when push-to-talk is started by voice, as there is no button to release.
+const VOICE_COMMAND_TALK_DURATION: Duration = Duration::from_secs(10);
+
+/// Time for the recording prompt to be spoken before the tone.
+const VOICE_MESSAGE_PROMPT_DURATION: Duration = Duration::from_secs(3);
+
 mod imp {
     use std::cell::{Cell, OnceCell, RefCell};
 
+    use gst::bus::BusWatchGuard;
+
     use super::*;
 
     #[derive(Default, gtk::CompositeTemplate)]
@@ -56,13 +75,31 @@ mod imp {
         #[template_child]
         pub(super) map_view: TemplateChild<MapView>,
         #[template_child]
+        pub(super) join_channel_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub(super) message_all_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
         pub(super) peer_list_box: TemplateChild<gtk::ListBox>,
         #[template_child]
-        pub(super) sos_button: TemplateChild<gtk::Button>,
+        pub(super) missed_call_list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) clear_missed_calls_button: TemplateChild<gtk::Button>,
         #[template_child]
-        pub(super) hazard_button: TemplateChild<gtk::Button>,
+        pub(super) voice_message_list_box: TemplateChild<gtk::ListBox>,
         #[template_child]
-        pub(super) yielding_button: TemplateChild<gtk::Button>,
+        pub(super) call_history_view: TemplateChild<CallHistoryView>,
+        #[template_child]
+        pub(super) alert_log_view: TemplateChild<AlertLogView>,
+        #[template_child]
+        pub(super) alert_grid: TemplateChild<gtk::Grid>,
+        #[template_child]
+        pub(super) alert_description_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub(super) emergency_en_route_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub(super) talk_card: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) talk_label: TemplateChild<gtk::Label>,
         #[template_child]
         pub(super) settings_view: TemplateChild<SettingsView>,
         #[template_child]
@@ -72,6 +109,10 @@ mod imp {
         #[template_child]
         pub(super) place_page: TemplateChild<PlacePage>,
         #[template_child]
+        pub(super) conversation_page: TemplateChild<ConversationPage>,
+        #[template_child]
+        pub(super) alert_responses_page: TemplateChild<AlertResponsesPage>,
+        #[template_child]
         pub(super) listening_overlay_revealer: TemplateChild<gtk::Revealer>,
         #[template_child]
         pub(super) listening_overlay: TemplateChild<ListeningOverlay>,
@@ -83,6 +124,17 @@ mod imp {
         pub(super) stt_is_accepting_segments: Cell<bool>,
 
         pub(super) alert_auto_broadcast_source_id: RefCell<Option<glib::SourceId>>,
+        /// Stops push-to-talk started by voice, which has no button to release.
+        pub(super) push_to_talk_stop_source_id: RefCell<Option<glib::SourceId>>,
+
+        /// Peer that last sent us a text message, for replying by voice.
+        pub(super) last_text_message_sender: RefCell<Option<Peer>>,
+        pub(super) last_received_alert: RefCell<Option<Alert>>,
+
+        pub(super) is_recording_voice_message: Cell<bool>,
+        pub(super) voice_message_playback: RefCell<Option<(gst::Element, BusWatchGuard)>>,
+
+        pub(super) collision_flash_source_id: RefCell<Option<glib::SourceId>>,
     }
 
     #[glib::object_subclass]
@@ -122,73 +174,128 @@ mod imp {
             client.connect_alert_received(clone!(
                 #[weak]
                 obj,
-                move |_, peer, alert_type| {
+                move |_, alert| {
+                    obj.handle_alert_received(alert);
+                }
+            ));
+            client.connect_alert_response_received(clone!(
+                #[weak]
+                obj,
+                move |_, alert, response| {
+                    obj.handle_alert_response_received(alert, response);
+                }
+            ));
+            client.connect_alert_sender_auto_muted(clone!(
+                #[weak]
+                obj,
+                move |_, sender_name, duration_secs| {
+                    obj.handle_alert_sender_auto_muted(sender_name, duration_secs);
+                }
+            ));
+            client.connect_emergency_vehicle_approaching(clone!(
+                #[weak]
+                obj,
+                move |_, peer, direction, distance, eta_secs| {
+                    obj.handle_emergency_vehicle_approaching(peer, direction, distance, eta_secs);
+                }
+            ));
+            client.connect_collision_warning(clone!(
+                #[weak]
+                obj,
+                move |_, peer, ttc_secs| {
+                    obj.handle_collision_warning(peer, ttc_secs);
+                }
+            ));
+            client.connect_call_missed(clone!(
+                #[weak]
+                obj,
+                move |_, missed_call| {
                     let imp = obj.imp();
 
-                    let (text, alert_color) = match alert_type {
-                        AlertType::Sos => (
-                            format!("{} is in a life-threatening situation", peer.name()),
-                            colors::RED_3,
-                        ),
-                        AlertType::Hazard => (
-                            format!("{} is in a hazardous situation", peer.name()),
-                            colors::YELLOW_4,
-                        ),
-                        AlertType::Yielding => {
-                            (format!("{} is yielding", peer.name()), colors::BLUE_3)
-                        }
-                    };
+                    let text = format!("Missed call from {}", missed_call.peer_name());
 
                     tts::speak(&text);
 
-                    if imp
-                        .view_stack
+                    let toast = adw::Toast::builder()
+                        .title(text)
+                        .button_label("Call Back")
+                        .priority(adw::ToastPriority::High)
+                        .build();
+                    toast.connect_button_clicked(clone!(
+                        #[weak]
+                        obj,
+                        #[weak]
+                        missed_call,
+                        move |_| {
+                            obj.call_back(&missed_call);
+                        }
+                    ));
+
+                    imp.toast_overlay.add_toast(toast);
+                }
+            ));
+            client.connect_text_message_received(clone!(
+                #[weak]
+                obj,
+                move |_, peer, message| {
+                    let imp = obj.imp();
+
+                    imp.last_text_message_sender.replace(Some(peer.clone()));
+
+                    // Read it out so drivers never have to look at the screen
+                    tts::speak(format!("{} says {}", peer.name(), message.text()));
+
+                    let is_viewing_conversation = imp
+                        .page_stack
                         .visible_child()
-                        .is_some_and(|child| child == *imp.map_view)
-                    {
-                        imp.map_view.play_alert_animation(
-                            peer,
-                            alert_type.blink_count(),
-                            alert_color,
-                        );
+                        .is_some_and(|child| child == *imp.conversation_page)
+                        && imp.conversation_page.peer().as_ref() == Some(peer);
+                    if is_viewing_conversation {
+                        return;
                     }
 
                     let toast = adw::Toast::builder()
-                        .title(text)
+                        .title(format!("{}: {}", peer.name(), message.text()))
+                        .button_label("Reply")
                         .priority(adw::ToastPriority::High)
                         .build();
-
                     toast.connect_button_clicked(clone!(
                         #[weak]
                         obj,
                         #[weak]
                         peer,
                         move |_| {
-                            let imp = obj.imp();
+                            obj.show_conversation(Some(&peer));
+                        }
+                    ));
 
-                            let location = peer.location().unwrap();
-                            imp.map_view.go_to(&location);
+                    imp.toast_overlay.add_toast(toast);
+                }
+            ));
+            client.connect_voice_message_received(clone!(
+                #[weak]
+                obj,
+                move |_, message| {
+                    let imp = obj.imp();
 
-                            imp.view_stack.set_visible_child(&*imp.map_view);
+                    let text = format!("New voice message from {}", message.sender_name());
 
-                            imp.map_view.play_alert_animation(
-                                &peer,
-                                alert_type.blink_count(),
-                                alert_color,
-                            );
-                        }
-                    ));
+                    tts::speak(&text);
 
-                    let binding = peer
-                        .bind_property("location", &toast, "button-label")
-                        .transform_to(|_, location: Option<Location>| {
-                            Some(location.map(|_| "View"))
-                        })
-                        .sync_create()
+                    let toast = adw::Toast::builder()
+                        .title(text)
+                        .button_label("Play")
+                        .priority(adw::ToastPriority::High)
                         .build();
-                    toast.connect_dismissed(move |_| {
-                        binding.unbind();
-                    });
+                    toast.connect_button_clicked(clone!(
+                        #[weak]
+                        obj,
+                        #[weak]
+                        message,
+                        move |_| {
+                            obj.play_voice_message(&message);
+                        }
+                    ));
 
                     imp.toast_overlay.add_toast(toast);
                 }
@@ -205,7 +312,9 @@ mod imp {
                             CallState::Incoming | CallState::Outgoing
                         ));
 
-                        if active_call.state() == CallState::Incoming {
+                        let is_incoming = active_call.state() == CallState::Incoming;
+
+                        if is_incoming {
                             tts::speak(format!("Incoming call from {}", active_call.peer().name()));
                         }
 
@@ -222,12 +331,14 @@ mod imp {
                                     CallState::Ended(reason) => {
                                         match reason {
                                             CallEndReason::PeerInAnotherCall => {
-                                                imp.toast_overlay.add_toast(adw::Toast::new(
+                                                obj.add_leave_voice_message_toast(
                                                     &format!(
                                                         "{} is in another call",
                                                         call.peer().name()
                                                     ),
-                                                ));
+                                                    *call.peer().id(),
+                                                    &call.peer().name(),
+                                                );
                                             }
                                             CallEndReason::PeerRejected => {
                                                 imp.toast_overlay.add_toast(adw::Toast::new(
@@ -245,7 +356,28 @@ mod imp {
                                                     ),
                                                 ));
                                             }
-                                            CallEndReason::Other => {}
+                                            CallEndReason::NoAnswer if !is_incoming => {
+                                                obj.add_leave_voice_message_toast(
+                                                    &format!(
+                                                        "{} did not answer",
+                                                        call.peer().name()
+                                                    ),
+                                                    *call.peer().id(),
+                                                    &call.peer().name(),
+                                                );
+                                            }
+                                            CallEndReason::SignallingFailed(err) => {
+                                                obj.add_leave_voice_message_toast(
+                                                    &format!(
+                                                        "Could not reach {}: {}",
+                                                        call.peer().name(),
+                                                        err
+                                                    ),
+                                                    *call.peer().id(),
+                                                    &call.peer().name(),
+                                                );
+                                            }
+                                            CallEndReason::NoAnswer | CallEndReason::Other => {}
                                         }
 
                                         imp.call_page.set_call(None::<Call>);
@@ -264,6 +396,22 @@ mod imp {
                     }
                 }
             ));
+            client.connect_active_voice_channel_notify(clone!(
+                #[weak]
+                obj,
+                move |client| {
+                    let imp = obj.imp();
+
+                    let voice_channel = client.active_voice_channel();
+                    imp.call_page.set_voice_channel(voice_channel.clone());
+
+                    if voice_channel.is_some() {
+                        imp.page_stack.set_visible_child(&*imp.call_page);
+                    } else if client.active_call().is_none() {
+                        imp.page_stack.set_visible_child(&*imp.main_page);
+                    }
+                }
+            ));
 
             self.map_view.connect_called(clone!(
                 #[weak]
@@ -288,25 +436,69 @@ mod imp {
                 }
             ));
 
-            self.sos_button.connect_clicked(clone!(
+            for (index, alert_type) in AlertType::all().iter().enumerate() {
+                let button = alerts::alert_button(*alert_type);
+                button.connect_clicked(clone!(
+                    #[weak]
+                    obj,
+                    move |_| {
+                        obj.publish_alert_with_entry_description(*alert_type);
+                    }
+                ));
+
+                let index = index as i32;
+                self.alert_grid.attach(
+                    &button,
+                    index % ALERT_GRID_COLUMNS,
+                    index / ALERT_GRID_COLUMNS,
+                    1,
+                    1,
+                );
+            }
+
+            let talk_gesture = gtk::GestureClick::new();
+            talk_gesture.connect_pressed(clone!(
                 #[weak]
                 obj,
-                move |_| {
-                    obj.publish_alert(AlertType::Sos);
+                move |gesture, _, _, _| {
+                    gesture.set_state(gtk::EventSequenceState::Claimed);
+                    obj.push_to_talk_start(None);
+                }
+            ));
+            talk_gesture.connect_released(clone!(
+                #[weak]
+                client,
+                move |_, _, _, _| {
+                    client.push_to_talk_stop();
+                }
+            ));
+            talk_gesture.connect_cancel(clone!(
+                #[weak]
+                client,
+                move |_, _| {
+                    client.push_to_talk_stop();
                 }
             ));
-            self.hazard_button.connect_clicked(clone!(
+            self.talk_card.add_controller(talk_gesture);
+
+            let push_to_talk = client.push_to_talk();
+            push_to_talk.connect_is_talking_notify(clone!(
                 #[weak]
                 obj,
-                move |_| {
-                    obj.publish_alert(AlertType::Hazard);
+                move |push_to_talk| {
+                    // Don't let the timer of a stopped session stop a later one
+                    if !push_to_talk.is_talking() {
+                        obj.remove_push_to_talk_stop_timer();
+                    }
+
+                    obj.update_talk_card();
                 }
             ));
-            self.yielding_button.connect_clicked(clone!(
+            push_to_talk.connect_floor_holder_notify(clone!(
                 #[weak]
                 obj,
                 move |_| {
-                    obj.publish_alert(AlertType::Yielding);
+                    obj.update_talk_card();
                 }
             ));
 
@@ -355,6 +547,25 @@ mod imp {
                 }
             ));
 
+            self.call_page.connect_channel_left(clone!(
+                #[weak]
+                client,
+                move |_| {
+                    glib::spawn_future_local(async move {
+                        client.leave_voice_channel().await;
+                    });
+                }
+            ));
+
+            self.join_channel_row.connect_apply(clone!(
+                #[weak]
+                obj,
+                move |row| {
+                    obj.join_voice_channel(&row.text());
+                    row.set_text("");
+                }
+            ));
+
             self.listening_overlay.connect_cancelled(clone!(
                 #[weak]
                 obj,
@@ -378,6 +589,52 @@ mod imp {
                 }
             ));
 
+            self.message_all_row.connect_activated(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.show_conversation(None);
+                }
+            ));
+
+            self.alert_responses_page.connect_done(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    let imp = obj.imp();
+                    imp.page_stack.set_visible_child(&*imp.main_page);
+                }
+            ));
+
+            self.alert_log_view.connect_show_on_map_requested(clone!(
+                #[weak]
+                obj,
+                move |_, entry| {
+                    let imp = obj.imp();
+
+                    let location = entry.location().unwrap();
+                    imp.map_view.go_to(&location);
+
+                    imp.view_stack.set_visible_child(&*imp.map_view);
+                }
+            ));
+
+            self.conversation_page.connect_done(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    let imp = obj.imp();
+                    imp.page_stack.set_visible_child(&*imp.main_page);
+                }
+            ));
+            self.conversation_page.connect_send_requested(clone!(
+                #[weak]
+                obj,
+                move |page, text| {
+                    obj.send_text_message(page.peer().as_ref(), text);
+                }
+            ));
+
             self.place_page.connect_done(clone!(
                 #[weak]
                 obj,
@@ -422,6 +679,13 @@ mod imp {
                                 });
                             }
                         ));
+                        row.connect_messaged(clone!(
+                            #[weak]
+                            obj,
+                            move |row| {
+                                obj.show_conversation(Some(&row.peer()));
+                            }
+                        ));
                         row.connect_viewed_on_map(clone!(
                             #[weak]
                             obj,
@@ -440,6 +704,154 @@ mod imp {
                 ),
             );
 
+            let missed_call_list = Application::get().missed_call_list();
+
+            let missed_call_placeholder_label = gtk::Label::builder()
+                .margin_top(12)
+                .margin_bottom(12)
+                .margin_start(12)
+                .margin_end(12)
+                .label("No Missed Calls")
+                .build();
+            self.missed_call_list_box
+                .set_placeholder(Some(&missed_call_placeholder_label));
+
+            self.missed_call_list_box.bind_model(
+                Some(&missed_call_list),
+                clone!(
+                    #[weak]
+                    obj,
+                    #[upgrade_or_panic]
+                    move |missed_call| {
+                        let missed_call = missed_call.downcast_ref::<MissedCall>().unwrap();
+
+                        let row = adw::ActionRow::builder()
+                            .title(missed_call.peer_name())
+                            .subtitle(missed_call.date_time().format("%b %e, %H:%M").unwrap())
+                            .build();
+
+                        let call_back_button = gtk::Button::builder()
+                            .icon_name("call-start-symbolic")
+                            .tooltip_text("Call Back")
+                            .valign(gtk::Align::Center)
+                            .build();
+                        call_back_button.add_css_class("flat");
+                        call_back_button.connect_clicked(clone!(
+                            #[weak]
+                            obj,
+                            #[weak]
+                            missed_call,
+                            move |_| {
+                                obj.call_back(&missed_call);
+                            }
+                        ));
+                        row.add_suffix(&call_back_button);
+
+                        let remove_button = gtk::Button::builder()
+                            .icon_name("edit-delete-symbolic")
+                            .valign(gtk::Align::Center)
+                            .build();
+                        remove_button.add_css_class("flat");
+                        remove_button.connect_clicked(clone!(
+                            #[weak]
+                            missed_call,
+                            move |_| {
+                                Application::get().missed_call_list().remove(&missed_call);
+                            }
+                        ));
+                        row.add_suffix(&remove_button);
+
+                        row.upcast()
+                    }
+                ),
+            );
+
+            self.clear_missed_calls_button.connect_clicked(|_| {
+                Application::get().missed_call_list().clear();
+            });
+            missed_call_list.connect_items_changed(clone!(
+                #[weak]
+                obj,
+                move |_, _, _, _| {
+                    obj.update_clear_missed_calls_button_sensitivity();
+                }
+            ));
+            obj.update_clear_missed_calls_button_sensitivity();
+
+            let voice_message_inbox = Application::get().voice_message_inbox();
+
+            let voice_message_placeholder_label = gtk::Label::builder()
+                .margin_top(12)
+                .margin_bottom(12)
+                .margin_start(12)
+                .margin_end(12)
+                .label("No Voice Messages")
+                .build();
+            self.voice_message_list_box
+                .set_placeholder(Some(&voice_message_placeholder_label));
+
+            self.voice_message_list_box.bind_model(
+                Some(&voice_message_inbox),
+                clone!(
+                    #[weak]
+                    obj,
+                    #[upgrade_or_panic]
+                    move |message| {
+                        let message = message.downcast_ref::<VoiceMessage>().unwrap();
+
+                        let row = adw::ActionRow::builder()
+                            .title(message.sender_name())
+                            .subtitle(message.date_time().format("%b %e, %H:%M").unwrap())
+                            .build();
+
+                        let unplayed_icon = gtk::Image::builder()
+                            .icon_name("media-record-symbolic")
+                            .tooltip_text("New")
+                            .build();
+                        unplayed_icon.add_css_class("accent");
+                        message
+                            .bind_property("played", &unplayed_icon, "visible")
+                            .invert_boolean()
+                            .sync_create()
+                            .build();
+                        row.add_prefix(&unplayed_icon);
+
+                        let play_button = gtk::Button::builder()
+                            .icon_name("media-playback-start-symbolic")
+                            .tooltip_text("Play")
+                            .valign(gtk::Align::Center)
+                            .build();
+                        play_button.add_css_class("flat");
+                        play_button.connect_clicked(clone!(
+                            #[weak]
+                            obj,
+                            #[weak]
+                            message,
+                            move |_| {
+                                obj.play_voice_message(&message);
+                            }
+                        ));
+                        row.add_suffix(&play_button);
+
+                        let remove_button = gtk::Button::builder()
+                            .icon_name("edit-delete-symbolic")
+                            .valign(gtk::Align::Center)
+                            .build();
+                        remove_button.add_css_class("flat");
+                        remove_button.connect_clicked(clone!(
+                            #[weak]
+                            message,
+                            move |_| {
+                                Application::get().voice_message_inbox().remove(&message);
+                            }
+                        ));
+                        row.add_suffix(&remove_button);
+
+                        row.upcast()
+                    }
+                ),
+            );
+
             self.stt.connect_transcripted(clone!(
                 #[weak]
                 obj,
@@ -448,8 +860,25 @@ mod imp {
                 }
             ));
 
+            let is_emergency_services = Application::get()
+                .credential()
+                .is_some_and(|credential| credential.role == PeerRole::EmergencyServices);
+            self.emergency_en_route_button
+                .set_visible(is_emergency_services);
+            client
+                .bind_property(
+                    "is-emergency-en-route",
+                    &*self.emergency_en_route_button,
+                    "active",
+                )
+                .bidirectional()
+                .sync_create()
+                .build();
+
             self.client.set(client.clone()).unwrap();
 
+            obj.update_talk_card();
+
             let app = Application::get();
 
             let gps = app.gps();
@@ -460,6 +889,27 @@ mod imp {
                     obj.update_gps_status_icon();
                 }
             ));
+            gps.connect_satellites_used_notify(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.update_gps_status_icon();
+                }
+            ));
+            gps.connect_satellites_visible_notify(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.update_gps_status_icon();
+                }
+            ));
+            gps.connect_hdop_notify(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.update_gps_status_icon();
+                }
+            ));
             gps.connect_location_notify(clone!(
                 #[weak]
                 obj,
@@ -467,8 +917,16 @@ mod imp {
                     obj.update_location();
                 }
             ));
+            gps.connect_horizontal_accuracy_notify(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.update_location_accuracy();
+                }
+            ));
             obj.update_gps_status_icon();
             obj.update_location();
+            obj.update_location_accuracy();
 
             let settings = app.settings();
             settings.connect_allowed_peers_notify(clone!(
@@ -534,38 +992,320 @@ impl Window {
             .build()
     }
 
-    fn publish_alert(&self, alert_type: AlertType) {
+    fn call_back(&self, missed_call: &MissedCall) {
         let imp = self.imp();
         let client = imp.client.get().unwrap();
 
-        let color = match alert_type {
-            AlertType::Sos => LedColor::Red,
-            AlertType::Hazard => LedColor::Yellow,
-            AlertType::Yielding => LedColor::Blue,
-        };
+        if client.active_call().is_some() {
+            imp.toast_overlay
+                .add_toast(adw::Toast::new("Cannot call back while in a call"));
+            return;
+        }
+
+        let peer_id = *missed_call.peer_id();
+
+        if client.peer_list().get(&peer_id).is_none() {
+            let text = format!("{} is not nearby", missed_call.peer_name());
+            tts::speak(&text);
+            self.add_leave_voice_message_toast(&text, peer_id, &missed_call.peer_name());
+            return;
+        }
+
+        tts::speak(format!("Calling back {}", missed_call.peer_name()));
+
+        Application::get().missed_call_list().remove(missed_call);
 
         glib::spawn_future_local(clone!(
             #[weak]
             client,
             async move {
-                client.publish_alert(alert_type).await;
-
-                if let Err(err) = Application::get()
-                    .remote()
-                    .blink_led(
-                        ALERT_LED_ID,
-                        color,
-                        alert_type.blink_count(),
-                        ALERT_LED_BLINK_INTERVAL,
-                    )
-                    .await
-                {
-                    tracing::error!("Failed to blink LED: {:?}", err);
+                if let Err(err) = client.call_request(peer_id).await {
+                    tracing::error!("Failed to request call: {:?}", err);
+                }
+            }
+        ));
+    }
+
+    fn add_leave_voice_message_toast(&self, title: &str, peer_id: PeerId, peer_name: &str) {
+        let imp = self.imp();
+
+        let toast = adw::Toast::builder()
+            .title(title)
+            .button_label("Leave Message")
+            .build();
+
+        let peer_name = peer_name.to_string();
+        toast.connect_button_clicked(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                obj.record_voice_message(peer_id, &peer_name);
+            }
+        ));
+
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    /// Records a voice message after a prompt and queues it for delivery to the peer.
+    fn record_voice_message(&self, peer_id: PeerId, peer_name: &str) {
+        let imp = self.imp();
+        let client = imp.client.get().unwrap();
+
+        if client.active_call().is_some()
+            || client.active_voice_channel().is_some()
+            || client.push_to_talk().is_talking()
+            || imp.is_recording_voice_message.get()
+        {
+            imp.toast_overlay.add_toast(adw::Toast::new(
+                "Cannot record a message while the microphone is in use",
+            ));
+            return;
+        }
+
+        imp.is_recording_voice_message.set(true);
+
+        let peer_name = peer_name.to_string();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let imp = obj.imp();
+
+                tts::speak(format!("Leave a message for {} after the tone", peer_name));
+
+                glib::timeout_future(VOICE_MESSAGE_PROMPT_DURATION).await;
+
+                if let Err(err) = play_quick_tone() {
+                    tracing::error!("Failed to play quick tone: {:?}", err);
+                }
+
+                imp.toast_overlay.add_toast(
+                    adw::Toast::builder()
+                        .title(format!("Recording message for {}…", peer_name))
+                        .timeout(MAX_VOICE_MESSAGE_DURATION.as_secs() as u32)
+                        .build(),
+                );
+
+                let res = voice_message::record(MAX_VOICE_MESSAGE_DURATION).await;
+
+                imp.is_recording_voice_message.set(false);
+
+                let audio = match res {
+                    Ok(audio) => audio,
+                    Err(err) => {
+                        tracing::error!("Failed to record voice message: {:?}", err);
+
+                        imp.toast_overlay
+                            .add_toast(adw::Toast::new("Failed to record message"));
+                        return;
+                    }
+                };
+
+                let client = imp.client.get().unwrap();
+                if let Err(err) = client.send_voice_message(peer_id, audio).await {
+                    tracing::error!("Failed to send voice message: {:?}", err);
+
+                    imp.toast_overlay
+                        .add_toast(adw::Toast::new("Failed to send message"));
+                    return;
+                }
+
+                let text = format!("Message for {} will be delivered once reachable", peer_name);
+                tts::speak(&text);
+                imp.toast_overlay.add_toast(adw::Toast::new(&text));
+            }
+        ));
+    }
+
+    fn play_voice_message(&self, message: &VoiceMessage) {
+        let imp = self.imp();
+
+        if let Some((prev_playbin, _)) = imp.voice_message_playback.take() {
+            let _ = prev_playbin.set_state(gst::State::Null);
+        }
+
+        if let Err(err) = self.play_voice_message_inner(message) {
+            tracing::error!("Failed to play voice message: {:?}", err);
+
+            imp.toast_overlay
+                .add_toast(adw::Toast::new("Failed to play message"));
+            return;
+        }
+
+        Application::get()
+            .voice_message_inbox()
+            .mark_played(message);
+    }
+
+    fn play_voice_message_inner(&self, message: &VoiceMessage) -> Result<()> {
+        let imp = self.imp();
+
+        let playbin = gst::ElementFactory::make("playbin").build()?;
+        playbin.set_property("uri", message.audio_file().uri());
+
+        let bus_watch_guard = playbin.bus().unwrap().add_watch_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[upgrade_or_panic]
+            move |_, message| match message.view() {
+                gst::MessageView::Eos(..) | gst::MessageView::Error(..) => {
+                    if let gst::MessageView::Error(err) = message.view() {
+                        tracing::warn!("Error from voice message playback bus: {:?}", err);
+                    }
+
+                    glib::spawn_future_local(async move {
+                        if let Some((playbin, _)) = obj.imp().voice_message_playback.take() {
+                            let _ = playbin.set_state(gst::State::Null);
+                        }
+                    });
+
+                    glib::ControlFlow::Break
+                }
+                _ => glib::ControlFlow::Continue,
+            }
+        ))?;
+
+        playbin.set_state(gst::State::Playing)?;
+
+        imp.voice_message_playback
+            .replace(Some((playbin, bus_watch_guard)));
+
+        Ok(())
+    }
+
+    /// Shows the conversation with the peer, or the broadcast one if `peer` is `None`.
+    fn show_conversation(&self, peer: Option<&Peer>) {
+        let imp = self.imp();
+        let client = imp.client.get().unwrap();
+
+        let conversation = match peer {
+            Some(peer) => client.conversation(peer.id()),
+            None => client.broadcast_conversation().clone(),
+        };
+        imp.conversation_page
+            .set_conversation(peer, Some(&conversation));
+
+        imp.page_stack.set_visible_child(&*imp.conversation_page);
+    }
+
+    /// Sends the text to the peer, or to all nearby peers if `peer` is `None`.
+    fn send_text_message(&self, peer: Option<&Peer>, text: &str) {
+        let peer_id = peer.map(|peer| *peer.id());
+        let text = text.to_string();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let client = obj.imp().client.get().unwrap();
+
+                let res = match peer_id {
+                    Some(peer_id) => client.send_text_message(peer_id, &text).await,
+                    None => client.broadcast_text_message(&text).await,
+                };
+
+                if let Err(err) = res {
+                    tracing::warn!("Failed to send text message: {:?}", err);
+
+                    let text = format!("Cannot send message: {}", err);
+                    tts::speak(&text);
+                    obj.imp().toast_overlay.add_toast(adw::Toast::new(&text));
+                }
+            }
+        ));
+    }
+
+    fn join_voice_channel(&self, name: &str) {
+        let name = name.to_string();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let client = obj.imp().client.get().unwrap();
+
+                if let Err(err) = client.join_voice_channel(&name).await {
+                    tracing::error!("Failed to join voice channel: {:?}", err);
+
+                    let text = format!("Failed to join channel {}", name);
+                    tts::speak(&text);
+                    obj.imp().toast_overlay.add_toast(adw::Toast::new(&text));
+                    return;
+                }
+
+                tts::speak(format!("Joined channel {}", name));
+            }
+        ));
+    }
+
+    /// Starts talking to nearby peers, stopping automatically after `duration` if given.
+    fn push_to_talk_start(&self, duration: Option<Duration>) {
+        self.remove_push_to_talk_stop_timer();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let client = obj.imp().client.get().unwrap();
+
+                if let Err(err) = client.push_to_talk_start().await {
+                    tracing::warn!("Failed to start push-to-talk: {:?}", err);
+
+                    obj.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new(&format!("Cannot talk: {}", err)));
+                    return;
+                }
+
+                if let Some(duration) = duration {
+                    let source_id = glib::timeout_add_local_once(
+                        duration,
+                        clone!(
+                            #[weak]
+                            obj,
+                            move || {
+                                obj.imp().push_to_talk_stop_source_id.replace(None);
+
+                                obj.imp().client.get().unwrap().push_to_talk_stop();
+                            }
+                        ),
+                    );
+                    obj.imp()
+                        .push_to_talk_stop_source_id
+                        .replace(Some(source_id));
                 }
             }
         ));
     }
 
+    fn remove_push_to_talk_stop_timer(&self) {
+        if let Some(source_id) = self.imp().push_to_talk_stop_source_id.take() {
+            source_id.remove();
+        }
+    }
+
+    fn update_talk_card(&self) {
+        let imp = self.imp();
+
+        let push_to_talk = imp.client.get().unwrap().push_to_talk();
+
+        let label = if push_to_talk.is_talking() {
+            "Talking…".to_string()
+        } else if let Some(floor_holder) = push_to_talk.floor_holder() {
+            format!("{} Is Talking", floor_holder.name())
+        } else {
+            "Hold to Talk".to_string()
+        };
+        imp.talk_label.set_label(&label);
+
+        if push_to_talk.is_talking() {
+            imp.talk_card.add_css_class("accent");
+        } else {
+            imp.talk_card.remove_css_class("accent");
+        }
+    }
+
     fn reset_stt_segments(&self) {
         let imp = self.imp();
 
@@ -709,6 +1449,18 @@ impl Window {
                         break;
                     };
 
+                    if peer_name == "back" {
+                        let missed_call_list = Application::get().missed_call_list();
+
+                        if let Some(missed_call) = missed_call_list.item(0) {
+                            self.call_back(missed_call.downcast_ref().unwrap());
+                        } else {
+                            tts::speak("No missed calls to call back");
+                        }
+
+                        break;
+                    }
+
                     let peer = client.peer_list().iter::<Peer>().find_map(|peer| {
                         let peer = peer.unwrap();
 
@@ -734,23 +1486,195 @@ impl Window {
                     break;
                 }
                 "alert" => {
-                    let Some(alert_type_str) = iter.next() else {
+                    let words = iter.by_ref().collect::<Vec<_>>();
+
+                    if words.is_empty() {
+                        break;
+                    }
+
+                    if let Some((alert_type, description_words)) = AlertType::from_words(&words) {
+                        tts::speak(format!(
+                            "Publishing {} alert",
+                            alert_type.to_string().to_lowercase()
+                        ));
+
+                        self.publish_alert(alert_type, Some(description_words.join(" ")));
+                    } else {
+                        tts::speak("Unknown alert type");
+                    }
+
+                    break;
+                }
+                "join" => {
+                    let mut channel_name = iter.next();
+
+                    if channel_name == Some("channel") {
+                        channel_name = iter.next();
+                    }
+
+                    if let Some(channel_name) = channel_name {
+                        self.join_voice_channel(channel_name);
+                    }
+
+                    break;
+                }
+                "leave" if client.active_voice_channel().is_some() => {
+                    tts::speak("Leaving channel");
+
+                    glib::spawn_future_local(clone!(
+                        #[weak]
+                        client,
+                        async move {
+                            client.leave_voice_channel().await;
+                        }
+                    ));
+
+                    break;
+                }
+                "talk" => {
+                    self.push_to_talk_start(Some(VOICE_COMMAND_TALK_DURATION));
+
+                    break;
+                }
+                "over" if client.push_to_talk().is_talking() => {
+                    client.push_to_talk_stop();
+
+                    break;
+                }
+                "message" if client.active_call().is_none() => {
+                    let Some(peer_name) = iter.next() else {
                         break;
                     };
 
-                    let alert_type = match alert_type_str {
-                        "sos" => Some(AlertType::Sos),
-                        "hazard" => Some(AlertType::Hazard),
-                        "yielding" => Some(AlertType::Yielding),
-                        _ => None,
+                    let recipient = client
+                        .peer_list()
+                        .iter::<Peer>()
+                        .map(|peer| peer.unwrap())
+                        .find(|peer| peer_name.eq_ignore_ascii_case(&peer.name()))
+                        .map(|peer| (*peer.id(), peer.name()))
+                        .or_else(|| {
+                            Application::get()
+                                .missed_call_list()
+                                .iter::<MissedCall>()
+                                .map(|missed_call| missed_call.unwrap())
+                                .find(|missed_call| {
+                                    peer_name.eq_ignore_ascii_case(&missed_call.peer_name())
+                                })
+                                .map(|missed_call| {
+                                    (*missed_call.peer_id(), missed_call.peer_name())
+                                })
+                        });
+
+                    if let Some((peer_id, peer_name)) = recipient {
+                        self.record_voice_message(peer_id, &peer_name);
+                    } else {
+                        tts::speak(format!("Peer {} not found", peer_name));
+                    }
+
+                    break;
+                }
+                "acknowledge" | "respond" => {
+                    let Some(alert) = imp
+                        .last_received_alert
+                        .borrow()
+                        .clone()
+                        .filter(|alert| !alert.is_expired())
+                    else {
+                        tts::speak("No alerts to respond to");
+                        break;
+                    };
+
+                    let kind = if word == "respond" {
+                        AlertResponseKind::Responding
+                    } else {
+                        AlertResponseKind::Acknowledged
                     };
 
-                    if let Some(alert_type) = alert_type {
-                        tts::speak(format!("Publishing {} alert", alert_type_str));
+                    tts::speak(match kind {
+                        AlertResponseKind::Acknowledged => {
+                            format!("Acknowledging {}'s alert", alert.sender_name())
+                        }
+                        AlertResponseKind::Responding => {
+                            format!("Responding to {}'s alert", alert.sender_name())
+                        }
+                    });
+
+                    self.respond_to_alert(&alert, kind);
+
+                    break;
+                }
+                "reply" => {
+                    let text = iter.by_ref().collect::<Vec<_>>().join(" ");
+
+                    if text.is_empty() {
+                        break;
+                    }
+
+                    let Some(peer) = imp.last_text_message_sender.borrow().clone() else {
+                        tts::speak("No messages to reply to");
+                        break;
+                    };
+
+                    tts::speak(format!("Replying to {}", peer.name()));
+
+                    self.send_text_message(Some(&peer), &quick_reply_or_text(&text));
+
+                    break;
+                }
+                "broadcast" => {
+                    let text = iter.by_ref().collect::<Vec<_>>().join(" ");
+
+                    if text.is_empty() {
+                        break;
+                    }
 
-                        self.publish_alert(alert_type);
+                    tts::speak("Sending message to all nearby peers");
+
+                    self.send_text_message(None, &quick_reply_or_text(&text));
+
+                    break;
+                }
+                "play" => {
+                    let voice_message_inbox = Application::get().voice_message_inbox();
+
+                    if let Some(message) = voice_message_inbox.first_unplayed() {
+                        tts::speak(format!("Message from {}", message.sender_name()));
+
+                        self.play_voice_message(&message);
                     } else {
-                        tts::speak("Unknown alert type");
+                        tts::speak("No new voice messages");
+                    }
+
+                    break;
+                }
+                "missed" => {
+                    let missed_call_list = Application::get().missed_call_list();
+
+                    let missed_calls = missed_call_list
+                        .iter::<MissedCall>()
+                        .map(|missed_call| missed_call.unwrap())
+                        .collect::<Vec<_>>();
+
+                    if missed_calls.is_empty() {
+                        tts::speak("You have no missed calls");
+                    } else {
+                        let summary = missed_calls
+                            .iter()
+                            .map(|missed_call| {
+                                format!(
+                                    "{} at {}",
+                                    missed_call.peer_name(),
+                                    missed_call.date_time().format("%H:%M").unwrap()
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ");
+
+                        tts::speak(format!(
+                            "You have {} missed calls from {}",
+                            missed_calls.len(),
+                            summary
+                        ));
                     }
 
                     break;
@@ -829,7 +1753,7 @@ impl Window {
 
         imp.page_stack.set_visible_child(&*imp.main_page);
 
-        self.publish_alert(AlertType::Sos);
+        self.publish_alert(AlertType::Sos, None);
     }
 
     fn handle_crashed_ignored(&self) {
@@ -905,6 +1829,30 @@ impl Window {
                 imp.gps_status_icon.add_css_class("success");
             }
         }
+
+        let fix_text = match gps.fix_mode() {
+            FixMode::None => "No Fix",
+            FixMode::TwoD => "2D Fix",
+            FixMode::ThreeD => "3D Fix",
+        };
+        let mut tooltip_text = format!(
+            "{} · {}/{} Satellites",
+            fix_text,
+            gps.satellites_used(),
+            gps.satellites_visible()
+        );
+        if gps.hdop() != gps::UNKNOWN {
+            tooltip_text.push_str(&format!(" · HDOP {:.1}", gps.hdop()));
+        }
+        imp.gps_status_icon.set_tooltip_text(Some(&tooltip_text));
+    }
+
+    fn update_clear_missed_calls_button_sensitivity(&self) {
+        let imp = self.imp();
+
+        let missed_call_list = Application::get().missed_call_list();
+        imp.clear_missed_calls_button
+            .set_sensitive(missed_call_list.n_items() > 0);
     }
 
     fn update_our_icon(&self) {
@@ -922,6 +1870,22 @@ impl Window {
 
         imp.map_view.set_location(location);
     }
+
+    fn update_location_accuracy(&self) {
+        let imp = self.imp();
+
+        let gps = Application::get().gps();
+        imp.map_view
+            .set_location_accuracy(gps.known_horizontal_accuracy());
+    }
+}
+
+/// Returns the quick reply matching the transcribed text, so it is sent properly capitalized.
+fn quick_reply_or_text(text: &str) -> String {
+    QUICK_REPLIES
+        .iter()
+        .find(|quick_reply| quick_reply.eq_ignore_ascii_case(text))
+        .map_or_else(|| text.to_string(), |quick_reply| quick_reply.to_string())
 }
 
 fn play_quick_tone() -> Result<()> {
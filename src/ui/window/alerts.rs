@@ -0,0 +1,333 @@
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::{
+    gdk,
+    glib::{self, clone},
+};
+
+use super::{Window, ALERT_LED_BLINK_INTERVAL};
+use crate::{
+    alert::{Alert, AlertResponse, AlertResponseKind, AlertSeverity, AlertType},
+    application::{Application, ALERT_LED_ID},
+    colors,
+    remote::LedColor,
+    tts,
+};
+
+impl Window {
+    pub(super) fn handle_alert_received(&self, alert: &Alert) {
+        let imp = self.imp();
+
+        let alert_type = alert.alert_type();
+        let alert_color = alert_color(alert_type);
+
+        let name = alert.sender_name();
+        let mut text = match alert_type {
+            AlertType::Sos => format!("{} is in a life-threatening situation", name),
+            AlertType::Hazard => format!("{} is in a hazardous situation", name),
+            AlertType::Yielding => format!("{} is yielding", name),
+            AlertType::Accident => format!("{} reported an accident ahead", name),
+            AlertType::RoadWork => format!("{} reported road work ahead", name),
+            AlertType::Flooding => format!("{} reported a flooded road", name),
+            AlertType::Debris => format!("{} reported debris on the road", name),
+            AlertType::StalledVehicle => format!("{} reported a stalled vehicle", name),
+            AlertType::PoliceCheckpoint => format!("{} reported a police checkpoint", name),
+            AlertType::TrafficJam => format!("{} reported a traffic jam", name),
+            AlertType::EmergencyVehicle => {
+                format!("{} reported an emergency vehicle approaching", name)
+            }
+        };
+        if let Some(description) = alert.description() {
+            text.push_str(&format!(": {}", description));
+        }
+
+        let sender_role = alert.sender_role();
+        if sender_role.is_authority() {
+            text = format!("{}: {}", sender_role, text);
+        }
+
+        tts::speak(&text);
+
+        imp.last_received_alert.replace(Some(alert.clone()));
+
+        imp.map_view.add_alert(alert, alert_color);
+
+        if imp
+            .view_stack
+            .visible_child()
+            .is_some_and(|child| child == *imp.map_view)
+        {
+            imp.map_view
+                .play_alert_animation(alert, alert_type.blink_count(), alert_color);
+        }
+
+        let priority = if alert.severity() >= AlertSeverity::High || sender_role.is_authority() {
+            adw::ToastPriority::High
+        } else {
+            adw::ToastPriority::Normal
+        };
+        let toast = adw::Toast::builder().priority(priority).build();
+        toast.set_custom_title(Some(&alert_toast_title(self, &toast, alert, &text)));
+
+        if let Some(location) = alert.location() {
+            toast.set_button_label(Some("View"));
+            toast.connect_button_clicked(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[weak]
+                alert,
+                move |_| {
+                    let imp = obj.imp();
+
+                    imp.map_view.go_to(&location);
+
+                    imp.view_stack.set_visible_child(&*imp.map_view);
+
+                    imp.map_view.play_alert_animation(
+                        &alert,
+                        alert_type.blink_count(),
+                        alert_color,
+                    );
+                }
+            ));
+        }
+
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    pub(super) fn handle_alert_response_received(&self, alert: &Alert, response: &AlertResponse) {
+        let imp = self.imp();
+
+        let text = match response.kind() {
+            AlertResponseKind::Acknowledged => {
+                format!("{} saw your alert", response.responder_name())
+            }
+            AlertResponseKind::Responding => {
+                format!("{} is coming to help", response.responder_name())
+            }
+        };
+
+        tts::speak(&text);
+
+        let is_viewing_responses = imp
+            .page_stack
+            .visible_child()
+            .is_some_and(|child| child == *imp.alert_responses_page)
+            && imp.alert_responses_page.alert().as_ref() == Some(alert);
+        if is_viewing_responses {
+            return;
+        }
+
+        let toast = adw::Toast::builder()
+            .title(text)
+            .button_label("Show")
+            .build();
+        toast.connect_button_clicked(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[weak]
+            alert,
+            move |_| {
+                obj.show_alert_responses(&alert);
+            }
+        ));
+
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    pub(super) fn handle_alert_sender_auto_muted(&self, sender_name: &str, duration_secs: u64) {
+        let imp = self.imp();
+
+        let toast = adw::Toast::builder()
+            .title(format!(
+                "Muted alerts from {} for {} minutes for sending too many",
+                sender_name,
+                duration_secs / 60
+            ))
+            .priority(adw::ToastPriority::High)
+            .build();
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    pub(super) fn publish_alert_with_entry_description(&self, alert_type: AlertType) {
+        let imp = self.imp();
+
+        let description = imp.alert_description_entry.text();
+        imp.alert_description_entry.set_text("");
+
+        self.publish_alert(alert_type, Some(description.to_string()));
+    }
+
+    pub(super) fn publish_alert(&self, alert_type: AlertType, description: Option<String>) {
+        let imp = self.imp();
+        let client = imp.client.get().unwrap();
+
+        let color = match alert_type {
+            AlertType::Sos | AlertType::Accident | AlertType::EmergencyVehicle => LedColor::Red,
+            AlertType::Hazard
+            | AlertType::RoadWork
+            | AlertType::Debris
+            | AlertType::StalledVehicle
+            | AlertType::TrafficJam => LedColor::Yellow,
+            AlertType::Yielding | AlertType::Flooding | AlertType::PoliceCheckpoint => {
+                LedColor::Blue
+            }
+        };
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[weak]
+            client,
+            async move {
+                let alert = client
+                    .publish_alert(alert_type, description.as_deref())
+                    .await;
+                obj.imp()
+                    .map_view
+                    .add_alert(&alert, alert_color(alert_type));
+
+                if let Err(err) = Application::get()
+                    .remote()
+                    .blink_led(
+                        ALERT_LED_ID,
+                        color,
+                        alert_type.blink_count(),
+                        ALERT_LED_BLINK_INTERVAL,
+                    )
+                    .await
+                {
+                    tracing::error!("Failed to blink LED: {:?}", err);
+                }
+            }
+        ));
+    }
+
+    fn show_alert_responses(&self, alert: &Alert) {
+        let imp = self.imp();
+
+        imp.alert_responses_page.set_alert(Some(alert));
+        imp.page_stack.set_visible_child(&*imp.alert_responses_page);
+    }
+
+    pub(super) fn respond_to_alert(&self, alert: &Alert, kind: AlertResponseKind) {
+        let imp = self.imp();
+        let client = imp.client.get().unwrap();
+
+        let text = match kind {
+            AlertResponseKind::Acknowledged => {
+                format!("Let {} know you saw the alert", alert.sender_name())
+            }
+            AlertResponseKind::Responding => {
+                format!("Let {} know you are coming to help", alert.sender_name())
+            }
+        };
+        imp.toast_overlay.add_toast(adw::Toast::new(&text));
+
+        glib::spawn_future_local(clone!(
+            #[weak]
+            client,
+            #[weak]
+            alert,
+            async move {
+                client.respond_to_alert(&alert, kind).await;
+            }
+        ));
+    }
+}
+
+fn alert_color(alert_type: AlertType) -> gdk::RGBA {
+    match alert_type {
+        AlertType::Sos => colors::RED_3,
+        AlertType::Hazard => colors::YELLOW_4,
+        AlertType::Yielding => colors::BLUE_3,
+        AlertType::Accident => colors::RED_1,
+        AlertType::RoadWork => colors::ORANGE_3,
+        AlertType::Flooding => colors::BLUE_5,
+        AlertType::Debris => colors::BROWN_3,
+        AlertType::StalledVehicle => colors::ORANGE_1,
+        AlertType::PoliceCheckpoint => colors::PURPLE_3,
+        AlertType::TrafficJam => colors::YELLOW_1,
+        AlertType::EmergencyVehicle => colors::RED_4,
+    }
+}
+
+/// Returns the toast title for a received alert, with buttons to respond to it.
+fn alert_toast_title(window: &Window, toast: &adw::Toast, alert: &Alert, text: &str) -> gtk::Box {
+    let label = gtk::Label::builder()
+        .label(text)
+        .wrap(true)
+        .hexpand(true)
+        .xalign(0.0)
+        .build();
+    label.add_css_class("heading");
+
+    let hbox = gtk::Box::builder().spacing(6).build();
+
+    let sender_role = alert.sender_role();
+    if let Some(icon_name) = sender_role.icon_name() {
+        hbox.add_css_class("authority-alert");
+        hbox.append(&gtk::Image::from_icon_name(icon_name));
+    }
+
+    hbox.append(&label);
+
+    for kind in [
+        AlertResponseKind::Acknowledged,
+        AlertResponseKind::Responding,
+    ] {
+        let label = match kind {
+            AlertResponseKind::Acknowledged => "Acknowledge",
+            AlertResponseKind::Responding => "Respond",
+        };
+        let button = gtk::Button::builder()
+            .label(label)
+            .valign(gtk::Align::Center)
+            .build();
+        button.add_css_class("flat");
+        button.connect_clicked(clone!(
+            #[weak]
+            window,
+            #[weak]
+            toast,
+            #[weak]
+            alert,
+            move |_| {
+                window.respond_to_alert(&alert, kind);
+                toast.dismiss();
+            }
+        ));
+        hbox.append(&button);
+    }
+
+    hbox
+}
+
+pub(super) fn alert_button(alert_type: AlertType) -> gtk::Button {
+    let image = gtk::Image::builder()
+        .icon_name(alert_type.icon_name())
+        .icon_size(gtk::IconSize::Large)
+        .build();
+    image.add_css_class(match alert_type.severity() {
+        AlertSeverity::Critical => "error",
+        AlertSeverity::High | AlertSeverity::Medium => "warning",
+        AlertSeverity::Low => "accent",
+    });
+
+    let label = gtk::Label::builder()
+        .label(alert_type.to_string())
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+    label.add_css_class("heading");
+
+    let vbox = gtk::Box::builder()
+        .valign(gtk::Align::Center)
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(6)
+        .build();
+    vbox.append(&image);
+    vbox.append(&label);
+
+    let button = gtk::Button::builder().child(&vbox).build();
+    button.add_css_class("card");
+    button
+}
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::glib::{self, clone};
+
+use super::{Window, ALERT_LED_BLINK_INTERVAL};
+use crate::{
+    alert::AlertType,
+    application::{Application, ALERT_LED_ID},
+    emergency_approach::ApproachDirection,
+    peer::Peer,
+    remote::LedColor,
+    tts,
+};
+
+const EMERGENCY_VEHICLE_LED_BLINK_COUNT: u32 = 6;
+const COLLISION_LED_BLINK_COUNT: u32 = 8;
+const COLLISION_LED_BLINK_INTERVAL: Duration = Duration::from_millis(150);
+const COLLISION_FLASH_DURATION: Duration = Duration::from_secs(2);
+
+impl Window {
+    pub(super) fn handle_emergency_vehicle_approaching(
+        &self,
+        peer: &Peer,
+        direction: ApproachDirection,
+        distance: f64,
+        eta_secs: u64,
+    ) {
+        let imp = self.imp();
+
+        let direction_str = match direction {
+            ApproachDirection::Ahead => " from ahead",
+            ApproachDirection::Behind => " from behind",
+            ApproachDirection::Unknown => "",
+        };
+        let eta_str = if eta_secs < 60 {
+            format!("{} seconds", eta_secs.max(1))
+        } else {
+            format!("{} minutes", eta_secs.div_ceil(60))
+        };
+        let text = format!(
+            "Emergency vehicle approaching{}, {:.0} meters away, arriving in about {}. Move aside.",
+            direction_str, distance, eta_str
+        );
+
+        tts::speak(&text);
+
+        glib::spawn_future_local(async move {
+            if let Err(err) = Application::get()
+                .remote()
+                .blink_led(
+                    ALERT_LED_ID,
+                    LedColor::Red,
+                    EMERGENCY_VEHICLE_LED_BLINK_COUNT,
+                    ALERT_LED_BLINK_INTERVAL,
+                )
+                .await
+            {
+                tracing::error!("Failed to blink LED: {:?}", err);
+            }
+        });
+
+        let toast = adw::Toast::builder()
+            .title(text)
+            .button_label("Yield")
+            .priority(adw::ToastPriority::High)
+            .build();
+        toast.connect_button_clicked(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[weak]
+            peer,
+            move |_| {
+                obj.publish_alert(
+                    AlertType::Yielding,
+                    Some(format!("Making way for {}", peer.name())),
+                );
+            }
+        ));
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    pub(super) fn handle_collision_warning(&self, peer: &Peer, ttc_secs: f64) {
+        let imp = self.imp();
+
+        let text = format!(
+            "Collision warning! {} in {:.0} seconds",
+            peer.name(),
+            ttc_secs.ceil()
+        );
+
+        tts::speak(&text);
+
+        glib::spawn_future_local(async move {
+            if let Err(err) = Application::get()
+                .remote()
+                .blink_led(
+                    ALERT_LED_ID,
+                    LedColor::Red,
+                    COLLISION_LED_BLINK_COUNT,
+                    COLLISION_LED_BLINK_INTERVAL,
+                )
+                .await
+            {
+                tracing::error!("Failed to blink LED: {:?}", err);
+            }
+        });
+
+        self.flash_collision_warning();
+
+        let toast = adw::Toast::builder()
+            .title(text)
+            .priority(adw::ToastPriority::High)
+            .build();
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    fn flash_collision_warning(&self) {
+        let imp = self.imp();
+
+        if let Some(source_id) = imp.collision_flash_source_id.take() {
+            source_id.remove();
+        }
+
+        imp.toast_overlay.add_css_class("collision-warning");
+
+        let source_id = glib::timeout_add_local_once(
+            COLLISION_FLASH_DURATION,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    let imp = obj.imp();
+
+                    imp.collision_flash_source_id.replace(None);
+                    imp.toast_overlay.remove_css_class("collision-warning");
+                }
+            ),
+        );
+        imp.collision_flash_source_id.replace(Some(source_id));
+    }
+}
@@ -1,4 +1,8 @@
-use gtk::glib;
+use std::borrow::Cow;
+
+use anyhow::Result;
+use gtk::{gio, glib, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
 
 pub fn spawn_future_local_idle<R: 'static, F: std::future::Future<Output = R> + 'static>(
     f: F,
@@ -6,3 +10,48 @@ pub fn spawn_future_local_idle<R: 'static, F: std::future::Future<Output = R> +
     let ctx = glib::MainContext::ref_thread_default();
     ctx.spawn_local_with_priority(glib::Priority::DEFAULT_IDLE, f)
 }
+
+/// Escapes a field for use in a CSV record, quoting it only if needed.
+pub fn csv_escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Saves the value as JSON to the file, creating its parent directories if needed.
+pub fn save_json<T: Serialize + ?Sized>(file: &gio::File, value: &T) -> Result<()> {
+    let bytes = serde_json::to_vec(value)?;
+    save_bytes(file, &bytes, gio::FileCreateFlags::REPLACE_DESTINATION)
+}
+
+/// Saves the bytes to the file with the given flags, creating its parent directories if
+/// needed.
+pub fn save_bytes(file: &gio::File, bytes: &[u8], flags: gio::FileCreateFlags) -> Result<()> {
+    if let Some(parent) = file.parent() {
+        if let Err(err) = parent.make_directory_with_parents(gio::Cancellable::NONE) {
+            if !err.matches(gio::IOErrorEnum::Exists) {
+                return Err(err.into());
+            }
+        }
+    }
+
+    file.replace_contents(bytes, None, false, flags, gio::Cancellable::NONE)?;
+
+    Ok(())
+}
+
+/// Loads a value saved with [`save_json`], returning `None` if the file doesn't exist yet.
+pub fn load_json<T: DeserializeOwned>(file: &gio::File) -> Result<Option<T>> {
+    match file.load_contents(gio::Cancellable::NONE) {
+        Ok((bytes, _)) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(err) => {
+            if err.matches(gio::IOErrorEnum::NotFound) {
+                Ok(None)
+            } else {
+                Err(err.into())
+            }
+        }
+    }
+}
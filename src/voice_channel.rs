@@ -0,0 +1,466 @@
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use anyhow::{ensure, Context, Result};
+use gst::{bus::BusWatchGuard, prelude::*};
+use gtk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+use libp2p::PeerId;
+
+use crate::{
+    audio_device,
+    call::{self, AudioCodec, AudioProcessing},
+    input_stream::InputStream,
+    output_stream::OutputStream,
+    peer::Peer,
+};
+
+const MIXER_ELEMENT_NAME: &str = "audiomixer";
+const TEE_ELEMENT_NAME: &str = "tee";
+const PULSESRC_ELEMENT_NAME: &str = "pulsesrc";
+const STREAMSRC_ELEMENT_NAME: &str = "giostreamsrc";
+const STREAMSINK_ELEMENT_NAME: &str = "giostreamsink";
+
+/// Time the mixer waits for late participants before mixing without them.
+const MIXER_LATENCY: Duration = Duration::from_millis(100);
+
+/// Codec used by every participant of a channel.
+///
+/// The mixer needs the arrival time of each packet, which only the RTP framing provides.
+pub const CHANNEL_CODEC: AudioCodec = AudioCodec::Opus;
+
+/// Audio received from a participant, mixed into the playback pipeline.
+struct ParticipantInput {
+    stream: InputStream,
+    bin: gst::Bin,
+    mixer_pad: gst::Pad,
+}
+
+/// Audio sent to a participant, branched from the capture pipeline.
+struct ParticipantOutput {
+    peer: Peer,
+    stream: OutputStream,
+    bin: gst::Bin,
+    tee_pad: gst::Pad,
+    peer_signal_quality_handler_id: glib::SignalHandlerId,
+}
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::VoiceChannel)]
+    pub struct VoiceChannel {
+        #[property(get, set, construct_only)]
+        pub(super) name: OnceCell<String>,
+
+        pub(super) participants: OnceCell<gio::ListStore>,
+
+        pub(super) playback: RefCell<Option<(gst::Pipeline, BusWatchGuard)>>,
+        pub(super) capture: RefCell<Option<(gst::Pipeline, BusWatchGuard)>>,
+
+        pub(super) inputs: RefCell<HashMap<PeerId, ParticipantInput>>,
+        pub(super) outputs: RefCell<HashMap<PeerId, ParticipantOutput>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VoiceChannel {
+        const NAME: &'static str = "DeltaVoiceChannel";
+        type Type = super::VoiceChannel;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for VoiceChannel {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.participants
+                .set(gio::ListStore::new::<Peer>())
+                .unwrap();
+        }
+
+        fn dispose(&self) {
+            self.obj().leave();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A named group call, where the audio of every participant is mixed together.
+    pub struct VoiceChannel(ObjectSubclass<imp::VoiceChannel>);
+}
+
+impl VoiceChannel {
+    /// Joins the channel locally, starting playback and capture without any participant.
+    pub fn new(name: &str) -> Result<Self> {
+        let this = glib::Object::builder::<Self>()
+            .property("name", name)
+            .build();
+
+        this.start()?;
+
+        Ok(this)
+    }
+
+    /// Returns the peers we currently exchange audio with.
+    pub fn participants(&self) -> &gio::ListStore {
+        self.imp().participants.get().unwrap()
+    }
+
+    pub fn has_participant(&self, peer_id: &PeerId) -> bool {
+        let imp = self.imp();
+
+        imp.inputs.borrow().contains_key(peer_id) || imp.outputs.borrow().contains_key(peer_id)
+    }
+
+    pub fn has_input(&self, peer_id: &PeerId) -> bool {
+        self.imp().inputs.borrow().contains_key(peer_id)
+    }
+
+    /// Mixes the audio received from the peer into the playback.
+    pub fn add_participant_input(&self, peer: &Peer, input_stream: InputStream) -> Result<()> {
+        let imp = self.imp();
+
+        ensure!(
+            !self.has_input(peer.id()),
+            "Already receiving audio from peer"
+        );
+
+        let pipeline = imp
+            .playback
+            .borrow()
+            .as_ref()
+            .map(|(pipeline, _)| pipeline.clone())
+            .context("Channel was left")?;
+
+        let bin = gst::parse::bin_from_description(
+            &format!(
+                "giostreamsrc name={STREAMSRC_ELEMENT_NAME} do-timestamp=true ! {} ! audioconvert ! audioresample",
+                CHANNEL_CODEC.decoder_pipeline_desc()
+            ),
+            true,
+        )?;
+        bin.set_property("name", format!("input-{}", peer.id()));
+
+        let streamsrc = bin.by_name(STREAMSRC_ELEMENT_NAME).unwrap();
+        streamsrc.set_property("stream", &input_stream);
+
+        let mixer = pipeline.by_name(MIXER_ELEMENT_NAME).unwrap();
+        let mixer_pad = mixer
+            .request_pad_simple("sink_%u")
+            .context("Failed to request mixer pad")?;
+
+        pipeline.add(&bin)?;
+        bin.static_pad("src").unwrap().link(&mixer_pad)?;
+        bin.sync_state_with_parent()?;
+
+        tracing::debug!("Added input from {} to channel", peer.id());
+
+        imp.inputs.borrow_mut().insert(
+            *peer.id(),
+            ParticipantInput {
+                stream: input_stream,
+                bin,
+                mixer_pad,
+            },
+        );
+        self.insert_participant(peer);
+
+        Ok(())
+    }
+
+    /// Sends our microphone audio to the peer, replacing the previous stream if any.
+    pub fn add_participant_output(&self, peer: &Peer, output_stream: OutputStream) -> Result<()> {
+        let imp = self.imp();
+
+        let prev_output = imp.outputs.borrow_mut().remove(peer.id());
+        if let Some(output) = prev_output {
+            tracing::debug!("Replacing output to {} in channel", peer.id());
+
+            self.dispose_output(output);
+        }
+
+        let pipeline = imp
+            .capture
+            .borrow()
+            .as_ref()
+            .map(|(pipeline, _)| pipeline.clone())
+            .context("Channel was left")?;
+
+        let bin = gst::parse::bin_from_description(
+            &format!(
+                "queue ! audioconvert ! {} ! giostreamsink name={STREAMSINK_ELEMENT_NAME}",
+                CHANNEL_CODEC.encoder_pipeline_desc()
+            ),
+            true,
+        )?;
+        bin.set_property("name", format!("output-{}", peer.id()));
+
+        let streamsink = bin.by_name(STREAMSINK_ELEMENT_NAME).unwrap();
+        streamsink.set_property("stream", &output_stream);
+
+        let opusenc = bin.by_name(call::OPUSENC_ELEMENT_NAME).unwrap();
+        opusenc.set_property("bitrate", call::opus_bitrate(peer.signal_quality()));
+        let peer_signal_quality_handler_id = peer.connect_signal_quality_notify(move |peer| {
+            opusenc.set_property("bitrate", call::opus_bitrate(peer.signal_quality()));
+        });
+
+        let tee = pipeline.by_name(TEE_ELEMENT_NAME).unwrap();
+        let tee_pad = tee
+            .request_pad_simple("src_%u")
+            .context("Failed to request tee pad")?;
+
+        pipeline.add(&bin)?;
+        tee_pad.link(&bin.static_pad("sink").unwrap())?;
+        bin.sync_state_with_parent()?;
+
+        tracing::debug!("Added output to {} to channel", peer.id());
+
+        imp.outputs.borrow_mut().insert(
+            *peer.id(),
+            ParticipantOutput {
+                peer: peer.clone(),
+                stream: output_stream,
+                bin,
+                tee_pad,
+                peer_signal_quality_handler_id,
+            },
+        );
+        self.insert_participant(peer);
+
+        Ok(())
+    }
+
+    /// Stops exchanging audio with the peer.
+    pub fn remove_participant(&self, peer_id: &PeerId) {
+        let imp = self.imp();
+
+        let input = imp.inputs.borrow_mut().remove(peer_id);
+        if let Some(input) = input {
+            self.dispose_input(input);
+        }
+
+        let output = imp.outputs.borrow_mut().remove(peer_id);
+        if let Some(output) = output {
+            self.dispose_output(output);
+        }
+
+        let participants = self.participants();
+        if let Some(position) = participants
+            .iter::<Peer>()
+            .position(|peer| peer.is_ok_and(|peer| peer.id() == peer_id))
+        {
+            participants.remove(position as u32);
+        }
+
+        tracing::debug!("Removed {peer_id} from channel");
+    }
+
+    /// Stops all audio of the channel. This is a no-op if the channel was already left.
+    pub fn leave(&self) {
+        let imp = self.imp();
+
+        // A stream may have been added for a peer that is not a participant yet
+        let peer_ids = self
+            .participants()
+            .iter::<Peer>()
+            .map(|peer| *peer.unwrap().id())
+            .chain(imp.inputs.borrow().keys().copied())
+            .chain(imp.outputs.borrow().keys().copied())
+            .collect::<HashSet<_>>();
+        for peer_id in peer_ids {
+            self.remove_participant(&peer_id);
+        }
+
+        if let Some((pipeline, _)) = imp.capture.take() {
+            if let Err(err) = pipeline.set_state(gst::State::Null) {
+                tracing::warn!("Failed to stop capture pipeline: {:?}", err);
+            }
+        }
+
+        if let Some((pipeline, _)) = imp.playback.take() {
+            if let Err(err) = pipeline.set_state(gst::State::Null) {
+                tracing::warn!("Failed to stop playback pipeline: {:?}", err);
+            }
+        }
+    }
+
+    fn start(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let audio_processing = AudioProcessing::from_settings();
+
+        // Live mixing, so a participant whose audio is late doesn't stall the others
+        let playback = gst::parse::launch(&format!(
+            "audiomixer name={MIXER_ELEMENT_NAME} force-live=true latency={} ! audioconvert ! {}autoaudiosink",
+            MIXER_LATENCY.as_nanos(),
+            audio_processing.probe_pipeline_desc()
+        ))?
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let bus_watch_guard = playback
+            .bus()
+            .unwrap()
+            .add_watch_local(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or_panic]
+                move |_, message| obj.handle_playback_bus_message(message)
+            ))
+            .unwrap();
+
+        // The DSP looks up the echo probe when it starts, so playback must be started first
+        playback.set_state(gst::State::Playing)?;
+        imp.playback.replace(Some((playback, bus_watch_guard)));
+
+        let capture = gst::parse::launch(&format!(
+            "pulsesrc name={PULSESRC_ELEMENT_NAME} ! audioconvert ! {}tee name={TEE_ELEMENT_NAME} allow-not-linked=true",
+            audio_processing.dsp_pipeline_desc()
+        ))?
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let pulsesrc = capture.by_name(PULSESRC_ELEMENT_NAME).unwrap();
+        let device = audio_device::find_default_source()?;
+        device.reconfigure_element(&pulsesrc)?;
+
+        let bus_watch_guard = capture
+            .bus()
+            .unwrap()
+            .add_watch_local(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or_panic]
+                move |_, message| obj.handle_capture_bus_message(message)
+            ))
+            .unwrap();
+
+        capture.set_state(gst::State::Playing)?;
+        imp.capture.replace(Some((capture, bus_watch_guard)));
+
+        Ok(())
+    }
+
+    fn participant(&self, peer_id: &PeerId) -> Option<Peer> {
+        self.participants()
+            .iter::<Peer>()
+            .map(|peer| peer.unwrap())
+            .find(|peer| peer.id() == peer_id)
+    }
+
+    fn insert_participant(&self, peer: &Peer) {
+        if self.participant(peer.id()).is_none() {
+            self.participants().append(peer);
+        }
+    }
+
+    fn dispose_input(&self, input: ParticipantInput) {
+        let imp = self.imp();
+
+        if let Err(err) = input.bin.set_state(gst::State::Null) {
+            tracing::warn!("Failed to stop input bin: {:?}", err);
+        }
+
+        if let Some((ref pipeline, _)) = *imp.playback.borrow() {
+            if let Err(err) = pipeline.remove(&input.bin) {
+                tracing::warn!("Failed to remove input from playback: {:?}", err);
+            }
+
+            let mixer = pipeline.by_name(MIXER_ELEMENT_NAME).unwrap();
+            mixer.release_request_pad(&input.mixer_pad);
+        }
+
+        glib::spawn_future_local(async move {
+            if let Err(err) = input.stream.close_future(glib::Priority::LOW).await {
+                tracing::error!("Failed to close input stream: {:?}", err);
+            }
+        });
+    }
+
+    fn dispose_output(&self, output: ParticipantOutput) {
+        let imp = self.imp();
+
+        output
+            .peer
+            .disconnect(output.peer_signal_quality_handler_id);
+
+        if let Err(err) = output.bin.set_state(gst::State::Null) {
+            tracing::warn!("Failed to stop output bin: {:?}", err);
+        }
+
+        if let Some((ref pipeline, _)) = *imp.capture.borrow() {
+            let tee = pipeline.by_name(TEE_ELEMENT_NAME).unwrap();
+            tee.release_request_pad(&output.tee_pad);
+
+            if let Err(err) = pipeline.remove(&output.bin) {
+                tracing::warn!("Failed to remove output from capture: {:?}", err);
+            }
+        }
+
+        glib::spawn_future_local(async move {
+            if let Err(err) = output.stream.close_future(glib::Priority::LOW).await {
+                tracing::error!("Failed to close output stream: {:?}", err);
+            }
+        });
+    }
+
+    /// Returns the participant whose input or output bin contains the message source.
+    fn participant_for_message(&self, message: &gst::Message) -> Option<PeerId> {
+        let imp = self.imp();
+
+        let src = message.src()?;
+
+        let input_peer_id = imp
+            .inputs
+            .borrow()
+            .iter()
+            .find(|(_, input)| src.has_as_ancestor(&input.bin))
+            .map(|(peer_id, _)| *peer_id);
+
+        input_peer_id.or_else(|| {
+            imp.outputs
+                .borrow()
+                .iter()
+                .find(|(_, output)| src.has_as_ancestor(&output.bin))
+                .map(|(peer_id, _)| *peer_id)
+        })
+    }
+
+    fn handle_playback_bus_message(&self, message: &gst::Message) -> glib::ControlFlow {
+        if let gst::MessageView::Error(err) = message.view() {
+            if let Some(peer_id) = self.participant_for_message(message) {
+                tracing::warn!("Error from input of {peer_id}: {:?}", err);
+
+                self.remove_participant(&peer_id);
+            } else {
+                tracing::error!("Error from playback bus: {:?}", err);
+            }
+        }
+
+        glib::ControlFlow::Continue
+    }
+
+    fn handle_capture_bus_message(&self, message: &gst::Message) -> glib::ControlFlow {
+        if let gst::MessageView::Error(err) = message.view() {
+            if let Some(peer_id) = self.participant_for_message(message) {
+                tracing::warn!("Error from output of {peer_id}: {:?}", err);
+
+                self.remove_participant(&peer_id);
+            } else {
+                tracing::error!("Error from capture bus: {:?}", err);
+            }
+        }
+
+        glib::ControlFlow::Continue
+    }
+}
@@ -0,0 +1,369 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use futures_util::StreamExt;
+use gst::prelude::*;
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio_device,
+    call::AudioProcessing,
+    config,
+    protocol::{MessageId, VoiceMessageInfo},
+    utils,
+};
+
+static VOICE_MESSAGES_DIR: Lazy<gio::File> = Lazy::new(|| {
+    let mut path = config::user_config_dir();
+    path.push("voice_messages");
+    gio::File::for_path(path)
+});
+
+static INBOX_FILE: Lazy<gio::File> = Lazy::new(|| VOICE_MESSAGES_DIR.child("inbox.json"));
+
+const PULSESRC_ELEMENT_NAME: &str = "pulsesrc";
+const FILESINK_ELEMENT_NAME: &str = "filesink";
+
+/// Longest voice message that can be recorded, so it fits in a single request.
+pub const MAX_VOICE_MESSAGE_DURATION: Duration = Duration::from_secs(15);
+
+/// Records a voice message from the microphone, returning its Ogg Vorbis audio.
+pub async fn record(duration: Duration) -> Result<Vec<u8>> {
+    let audio_processing = AudioProcessing::from_settings();
+
+    let mut path = glib::tmp_dir();
+    path.push(format!(
+        "delta-voice-message-{}.ogg",
+        MessageId::new_random()
+    ));
+    let file = gio::File::for_path(&path);
+
+    let pipeline = gst::parse::launch(&format!(
+        "pulsesrc name={PULSESRC_ELEMENT_NAME} ! audioconvert ! {}audioresample ! audio/x-raw,channels=1 ! vorbisenc ! oggmux ! filesink name={FILESINK_ELEMENT_NAME}",
+        audio_processing.dsp_pipeline_desc()
+    ))?
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    let pulsesrc = pipeline.by_name(PULSESRC_ELEMENT_NAME).unwrap();
+    let device = audio_device::find_default_source()?;
+    device.reconfigure_element(&pulsesrc)?;
+
+    let filesink = pipeline.by_name(FILESINK_ELEMENT_NAME).unwrap();
+    filesink.set_property("location", &path);
+
+    let bus = pipeline.bus().unwrap();
+    let mut messages = bus.stream_filtered(&[gst::MessageType::Eos, gst::MessageType::Error]);
+
+    pipeline.set_state(gst::State::Playing)?;
+
+    tracing::debug!("Recording voice message to {}", path.display());
+
+    glib::timeout_future(duration).await;
+
+    pipeline.send_event(gst::event::Eos::new());
+
+    let res = match messages.next().await {
+        Some(message) => match message.view() {
+            gst::MessageView::Eos(..) => Ok(()),
+            gst::MessageView::Error(err) => Err(anyhow!(err.error())),
+            _ => unreachable!(),
+        },
+        None => Err(anyhow!("Bus closed while recording")),
+    };
+
+    pipeline.set_state(gst::State::Null)?;
+
+    res.context("Failed to record voice message")?;
+
+    let (bytes, _) = file.load_contents_future().await?;
+
+    if let Err(err) = file.delete_future(glib::Priority::LOW).await {
+        tracing::warn!("Failed to delete recorded voice message: {:?}", err);
+    }
+
+    if bytes.is_empty() {
+        bail!("Recorded voice message is empty");
+    }
+
+    Ok(bytes.to_vec())
+}
+
+/// Returns the file where the audio of the message is stored.
+pub fn audio_file(id: MessageId) -> gio::File {
+    VOICE_MESSAGES_DIR.child(format!("{id}.ogg"))
+}
+
+pub fn save_audio(id: MessageId, audio: &[u8]) -> Result<()> {
+    ensure_voice_messages_dir()?;
+
+    audio_file(id).replace_contents(
+        audio,
+        None,
+        false,
+        gio::FileCreateFlags::REPLACE_DESTINATION,
+        gio::Cancellable::NONE,
+    )?;
+
+    Ok(())
+}
+
+pub fn load_audio(id: MessageId) -> Result<Vec<u8>> {
+    let (bytes, _) = audio_file(id).load_contents(gio::Cancellable::NONE)?;
+    Ok(bytes.to_vec())
+}
+
+pub fn delete_audio(id: MessageId) {
+    if let Err(err) = audio_file(id).delete(gio::Cancellable::NONE) {
+        if !err.matches(gio::IOErrorEnum::NotFound) {
+            tracing::warn!("Failed to delete voice message audio: {:?}", err);
+        }
+    }
+}
+
+/// Returns the file in the voice messages directory with the given name.
+pub fn data_file(name: &str) -> gio::File {
+    VOICE_MESSAGES_DIR.child(name)
+}
+
+pub fn ensure_voice_messages_dir() -> Result<()> {
+    if let Err(err) = VOICE_MESSAGES_DIR.make_directory_with_parents(gio::Cancellable::NONE) {
+        if !err.matches(gio::IOErrorEnum::Exists) {
+            return Err(err.into());
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VoiceMessageData {
+    info: VoiceMessageInfo,
+    played: bool,
+}
+
+mod voice_message_imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::VoiceMessage)]
+    pub struct VoiceMessage {
+        pub(super) info: OnceCell<VoiceMessageInfo>,
+
+        #[property(get, set, construct_only)]
+        pub(super) sender_name: RefCell<String>,
+        #[property(get, set, construct_only)]
+        pub(super) timestamp: Cell<i64>,
+        #[property(get, set)]
+        pub(super) played: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VoiceMessage {
+        const NAME: &'static str = "DeltaVoiceMessage";
+        type Type = super::VoiceMessage;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for VoiceMessage {}
+}
+
+glib::wrapper! {
+    /// Voice message received from a peer.
+    pub struct VoiceMessage(ObjectSubclass<voice_message_imp::VoiceMessage>);
+}
+
+impl VoiceMessage {
+    pub fn new(info: VoiceMessageInfo) -> Self {
+        let this = glib::Object::builder::<Self>()
+            .property("sender-name", &info.sender_name)
+            .property("timestamp", info.timestamp)
+            .build();
+        this.imp().info.set(info).unwrap();
+        this
+    }
+
+    pub fn info(&self) -> &VoiceMessageInfo {
+        self.imp().info.get().unwrap()
+    }
+
+    pub fn date_time(&self) -> glib::DateTime {
+        glib::DateTime::from_unix_local(self.timestamp()).unwrap()
+    }
+
+    pub fn audio_file(&self) -> gio::File {
+        audio_file(self.info().id)
+    }
+
+    fn to_data(&self) -> VoiceMessageData {
+        VoiceMessageData {
+            info: self.info().clone(),
+            played: self.played(),
+        }
+    }
+}
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct VoiceMessageInbox {
+        pub(super) list: RefCell<Vec<VoiceMessage>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VoiceMessageInbox {
+        const NAME: &'static str = "DeltaVoiceMessageInbox";
+        type Type = super::VoiceMessageInbox;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    impl ObjectImpl for VoiceMessageInbox {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            if let Err(err) = obj.load() {
+                tracing::error!("Failed to load voice message inbox: {:?}", err);
+            }
+        }
+    }
+
+    impl ListModelImpl for VoiceMessageInbox {
+        fn item_type(&self) -> glib::Type {
+            VoiceMessage::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.list.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.list
+                .borrow()
+                .get(position as usize)
+                .map(|v| v.upcast_ref::<glib::Object>())
+                .cloned()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Persisted list of received voice messages, newest first.
+    pub struct VoiceMessageInbox(ObjectSubclass<imp::VoiceMessageInbox>)
+        @implements gio::ListModel;
+}
+
+impl VoiceMessageInbox {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn contains(&self, id: MessageId) -> bool {
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .any(|message| message.info().id == id)
+    }
+
+    /// Returns the oldest message that was not played yet.
+    pub fn first_unplayed(&self) -> Option<VoiceMessage> {
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .rev()
+            .find(|message| !message.played())
+            .cloned()
+    }
+
+    /// Stores a received message, whose audio must already be saved.
+    pub fn insert(&self, message: VoiceMessage) {
+        self.imp().list.borrow_mut().insert(0, message);
+        self.items_changed(0, 0, 1);
+
+        self.save_and_log();
+    }
+
+    pub fn mark_played(&self, message: &VoiceMessage) {
+        if message.played() {
+            return;
+        }
+
+        message.set_played(true);
+
+        self.save_and_log();
+    }
+
+    pub fn remove(&self, message: &VoiceMessage) {
+        let imp = self.imp();
+
+        let position = imp.list.borrow().iter().position(|m| m == message);
+
+        if let Some(position) = position {
+            imp.list.borrow_mut().remove(position);
+            self.items_changed(position as u32, 1, 0);
+
+            delete_audio(message.info().id);
+
+            self.save_and_log();
+        }
+    }
+
+    fn save_and_log(&self) {
+        if let Err(err) = self.save() {
+            tracing::error!("Failed to save voice message inbox: {:?}", err);
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let data = self
+            .imp()
+            .list
+            .borrow()
+            .iter()
+            .map(|message| message.to_data())
+            .collect::<Vec<_>>();
+        utils::save_json(&INBOX_FILE, &data)
+    }
+
+    fn load(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let data = utils::load_json::<Vec<VoiceMessageData>>(&INBOX_FILE)?.unwrap_or_default();
+
+        tracing::debug!(
+            "Loaded {} voice messages from {}",
+            data.len(),
+            INBOX_FILE.path().unwrap().display()
+        );
+
+        let prev_len = imp.list.borrow().len();
+        imp.list.replace(
+            data.into_iter()
+                .map(|d| {
+                    let message = VoiceMessage::new(d.info);
+                    message.set_played(d.played);
+                    message
+                })
+                .collect(),
+        );
+        self.items_changed(0, prev_len as u32, self.n_items());
+
+        Ok(())
+    }
+}
+
+impl Default for VoiceMessageInbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::Result;
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use libp2p::PeerId;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    protocol::{MessageId, VoiceMessageInfo},
+    utils, voice_message,
+};
+
+static OUTBOX_FILE: Lazy<gio::File> = Lazy::new(|| voice_message::data_file("outbox.json"));
+
+/// Most relays a message can go through before reaching the recipient.
+const MAX_HOPS: u8 = 3;
+
+/// Time after which undelivered messages are dropped.
+const TTL_SECS: i64 = 24 * 60 * 60;
+
+/// Most audio bytes stored to relay for a single sender, so one peer can't fill the outbox.
+const MAX_RELAYED_SIZE_PER_SENDER: usize = 4 * 1024 * 1024;
+/// Most audio bytes stored to relay for all senders.
+const MAX_RELAYED_SIZE: usize = 32 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingVoiceMessage {
+    info: VoiceMessageInfo,
+    /// Peers the message was already handed to for relaying.
+    relayed_to: Vec<PeerId>,
+    /// Whether we are relaying the message for another sender.
+    #[serde(default)]
+    is_relay: bool,
+    /// Size of the audio in bytes.
+    #[serde(default)]
+    size: usize,
+}
+
+impl PendingVoiceMessage {
+    fn is_expired(&self, now: i64) -> bool {
+        now - self.info.timestamp > TTL_SECS
+    }
+}
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct VoiceMessageOutbox {
+        pub(super) list: RefCell<Vec<PendingVoiceMessage>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VoiceMessageOutbox {
+        const NAME: &'static str = "DeltaVoiceMessageOutbox";
+        type Type = super::VoiceMessageOutbox;
+    }
+
+    impl ObjectImpl for VoiceMessageOutbox {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            if let Err(err) = obj.load() {
+                tracing::error!("Failed to load voice message outbox: {:?}", err);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Persisted voice messages waiting to be delivered, either ours or ones we relay.
+    pub struct VoiceMessageOutbox(ObjectSubclass<imp::VoiceMessageOutbox>);
+}
+
+impl VoiceMessageOutbox {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn contains(&self, id: MessageId) -> bool {
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .any(|pending| pending.info.id == id)
+    }
+
+    pub fn insert(&self, info: VoiceMessageInfo, audio: &[u8]) -> Result<()> {
+        self.insert_inner(info, audio, false)
+    }
+
+    /// Stores a message to relay for another sender, dropping the oldest relayed ones to stay
+    /// within the size limits.
+    ///
+    /// Returns `false` if the message is too large to be relayed at all.
+    pub fn insert_relayed(&self, info: VoiceMessageInfo, audio: &[u8]) -> Result<bool> {
+        if audio.len() > MAX_RELAYED_SIZE_PER_SENDER {
+            return Ok(false);
+        }
+
+        let sender = info.sender;
+        self.remove_oldest_relayed(
+            |pending| pending.info.sender == sender,
+            MAX_RELAYED_SIZE_PER_SENDER - audio.len(),
+        );
+        self.remove_oldest_relayed(|_| true, MAX_RELAYED_SIZE - audio.len());
+
+        self.insert_inner(info, audio, true)?;
+
+        Ok(true)
+    }
+
+    /// Returns the messages to send to the peer, which are the ones addressed to it and the
+    /// ones it can relay.
+    ///
+    /// The hop count of relayed messages is already incremented.
+    pub fn to_send(&self, peer_id: &PeerId) -> Vec<VoiceMessageInfo> {
+        self.remove_expired();
+
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .filter_map(|pending| {
+                let info = &pending.info;
+
+                if &info.recipient == peer_id {
+                    Some(info.clone())
+                } else if info.hops < MAX_HOPS
+                    && &info.sender != peer_id
+                    && !pending.relayed_to.contains(peer_id)
+                {
+                    Some(VoiceMessageInfo {
+                        hops: info.hops + 1,
+                        ..info.clone()
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn mark_relayed(&self, id: MessageId, peer_id: PeerId) {
+        let mut list = self.imp().list.borrow_mut();
+
+        if let Some(pending) = list.iter_mut().find(|pending| pending.info.id == id) {
+            pending.relayed_to.push(peer_id);
+            drop(list);
+
+            self.save_and_log();
+        }
+    }
+
+    pub fn remove(&self, id: MessageId) {
+        let imp = self.imp();
+
+        let position = imp
+            .list
+            .borrow()
+            .iter()
+            .position(|pending| pending.info.id == id);
+
+        if let Some(position) = position {
+            imp.list.borrow_mut().remove(position);
+
+            voice_message::delete_audio(id);
+
+            self.save_and_log();
+        }
+    }
+
+    fn insert_inner(&self, info: VoiceMessageInfo, audio: &[u8], is_relay: bool) -> Result<()> {
+        voice_message::save_audio(info.id, audio)?;
+
+        self.imp().list.borrow_mut().push(PendingVoiceMessage {
+            info,
+            relayed_to: Vec::new(),
+            is_relay,
+            size: audio.len(),
+        });
+
+        self.save_and_log();
+
+        Ok(())
+    }
+
+    /// Removes the oldest relayed messages matching the predicate until their total size is at
+    /// most `max_size`.
+    fn remove_oldest_relayed(
+        &self,
+        predicate: impl Fn(&PendingVoiceMessage) -> bool,
+        max_size: usize,
+    ) {
+        let removed_ids = {
+            let list = self.imp().list.borrow();
+
+            // Messages are pushed as they are received, so the oldest come first
+            let relayed = list
+                .iter()
+                .filter(|pending| pending.is_relay && predicate(pending))
+                .collect::<Vec<_>>();
+
+            let mut size = relayed.iter().map(|pending| pending.size).sum::<usize>();
+            relayed
+                .into_iter()
+                .take_while(|pending| {
+                    let is_over_limit = size > max_size;
+                    size -= pending.size;
+                    is_over_limit
+                })
+                .map(|pending| pending.info.id)
+                .collect::<Vec<_>>()
+        };
+
+        for id in removed_ids {
+            tracing::debug!("Dropping relayed voice message {id} to make room for newer ones");
+
+            self.remove(id);
+        }
+    }
+
+    fn remove_expired(&self) {
+        let now = glib::DateTime::now_utc().unwrap().to_unix();
+
+        let expired_ids = self
+            .imp()
+            .list
+            .borrow()
+            .iter()
+            .filter(|pending| pending.is_expired(now))
+            .map(|pending| pending.info.id)
+            .collect::<Vec<_>>();
+
+        for id in expired_ids {
+            tracing::debug!("Dropping expired voice message {id}");
+
+            self.remove(id);
+        }
+    }
+
+    fn save_and_log(&self) {
+        if let Err(err) = self.save() {
+            tracing::error!("Failed to save voice message outbox: {:?}", err);
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        utils::save_json(&OUTBOX_FILE, &*self.imp().list.borrow())
+    }
+
+    fn load(&self) -> Result<()> {
+        let data = utils::load_json::<Vec<PendingVoiceMessage>>(&OUTBOX_FILE)?.unwrap_or_default();
+
+        tracing::debug!(
+            "Loaded {} pending voice messages from {}",
+            data.len(),
+            OUTBOX_FILE.path().unwrap().display()
+        );
+
+        self.imp().list.replace(data);
+
+        self.remove_expired();
+
+        Ok(())
+    }
+}
+
+impl Default for VoiceMessageOutbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}